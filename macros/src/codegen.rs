@@ -0,0 +1,508 @@
+use graphql_parser::query::{
+    self, FragmentDefinition, OperationDefinition, Selection, SelectionSet, Type, TypeCondition,
+};
+use graphql_parser::schema::{self, TypeDefinition};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use std::collections::{BTreeSet, HashMap};
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "static", "struct", "trait", "true", "try", "type", "unsafe", "use", "where",
+    "while", "yield",
+];
+
+/// Types and root operation types of a schema
+pub(crate) struct Schema<'a> {
+    types: HashMap<&'a str, TypeDefinition<'a, &'a str>>,
+    query: &'a str,
+    mutation: &'a str,
+}
+
+impl<'a> Schema<'a> {
+    pub(crate) fn parse(source: &'a str) -> Result<Self, String> {
+        let document = schema::parse_schema::<&str>(source)
+            .map_err(|error| format!("invalid schema: {}", error.to_string().trim_end()))?;
+
+        let mut schema = Schema {
+            types: HashMap::new(),
+            query: "Query",
+            mutation: "Mutation",
+        };
+        for definition in document.definitions {
+            match definition {
+                schema::Definition::SchemaDefinition(definition) => {
+                    schema.query = definition.query.unwrap_or(schema.query);
+                    schema.mutation = definition.mutation.unwrap_or(schema.mutation);
+                }
+                schema::Definition::TypeDefinition(definition) => {
+                    schema.types.insert(type_name(&definition), definition);
+                }
+                _ => {}
+            }
+        }
+        Ok(schema)
+    }
+
+    fn fields(&self, type_name: &str) -> &[schema::Field<'a, &'a str>] {
+        match self.types.get(type_name) {
+            Some(TypeDefinition::Object(object)) => &object.fields,
+            Some(TypeDefinition::Interface(interface)) => &interface.fields,
+            _ => &[],
+        }
+    }
+}
+
+fn type_name<'a>(definition: &TypeDefinition<'a, &'a str>) -> &'a str {
+    match definition {
+        TypeDefinition::Scalar(scalar) => scalar.name,
+        TypeDefinition::Object(object) => object.name,
+        TypeDefinition::Interface(interface) => interface.name,
+        TypeDefinition::Union(union) => union.name,
+        TypeDefinition::Enum(definition) => definition.name,
+        TypeDefinition::InputObject(input) => input.name,
+    }
+}
+
+/// Field selected by a selection set, with the selections merged by response key
+struct Selected<'a> {
+    key: &'a str,
+    name: &'a str,
+    on_type: &'a str,
+    optional: bool,
+    selection_sets: Vec<&'a SelectionSet<'a, &'a str>>,
+}
+
+/// Generates the types of the operations found in the documents
+pub(crate) struct Generator<'a> {
+    schema: &'a Schema<'a>,
+    documents: &'a [query::Document<'a, &'a str>],
+    fragments: HashMap<&'a str, &'a FragmentDefinition<'a, &'a str>>,
+    enums: BTreeSet<&'a str>,
+    inputs: BTreeSet<&'a str>,
+    items: Vec<TokenStream>,
+}
+
+impl<'a> Generator<'a> {
+    pub(crate) fn new(
+        schema: &'a Schema<'a>,
+        documents: &'a [query::Document<'a, &'a str>],
+    ) -> Self {
+        let fragments = documents
+            .iter()
+            .flat_map(|document| &document.definitions)
+            .filter_map(|definition| match definition {
+                query::Definition::Fragment(fragment) => Some((fragment.name, fragment)),
+                query::Definition::Operation(_) => None,
+            })
+            .collect();
+
+        Self {
+            schema,
+            documents,
+            fragments,
+            enums: BTreeSet::new(),
+            inputs: BTreeSet::new(),
+            items: Vec::new(),
+        }
+    }
+
+    pub(crate) fn generate(mut self) -> Result<TokenStream, String> {
+        for document in self.documents {
+            for definition in &document.definitions {
+                if let query::Definition::Operation(operation) = definition {
+                    self.operation(operation)?;
+                }
+            }
+        }
+
+        // Input objects may reference further input objects and enums
+        let mut generated = BTreeSet::new();
+        while let Some(name) = self.inputs.difference(&generated).next().copied() {
+            generated.insert(name);
+            self.input_object(name)?;
+        }
+        for name in std::mem::take(&mut self.enums) {
+            self.enumeration(name);
+        }
+
+        let items = self.items;
+        Ok(quote!(#(#items)*))
+    }
+
+    fn operation(&mut self, operation: &'a OperationDefinition<'a, &'a str>) -> Result<(), String> {
+        let (name, root, variables, selection_set) = match operation {
+            OperationDefinition::Query(query) => (
+                query.name,
+                self.schema.query,
+                &query.variable_definitions,
+                &query.selection_set,
+            ),
+            OperationDefinition::Mutation(mutation) => (
+                mutation.name,
+                self.schema.mutation,
+                &mutation.variable_definitions,
+                &mutation.selection_set,
+            ),
+            OperationDefinition::Subscription(subscription) => {
+                return Err(format!(
+                    "subscription `{}` is not supported, only queries and mutations are",
+                    subscription.name.unwrap_or_default()
+                ))
+            }
+            OperationDefinition::SelectionSet(_) => return Err("operations must be named".into()),
+        };
+        let name = name.ok_or("operations must be named")?;
+        if !self.schema.types.contains_key(root) {
+            return Err(format!("schema does not define the `{}` type", root));
+        }
+
+        let mut fields = Vec::new();
+        for variable in variables {
+            let field = field_ident(variable.name);
+            let rename = rename(&field, variable.name);
+            let field_type = self.input_type(&variable.var_type)?;
+            fields.push(quote! {
+                #rename
+                pub #field: #field_type,
+            });
+        }
+
+        let struct_name = type_ident(&pascal_case(name));
+        let response = type_ident(&format!("{}Response", pascal_case(name)));
+        let document = self.document(operation);
+        self.items.push(quote! {
+            #[derive(Clone, Debug, PartialEq, ::reqwest_graphql::__private::serde::Serialize)]
+            #[serde(crate = "::reqwest_graphql::__private::serde")]
+            pub struct #struct_name {
+                #(#fields)*
+            }
+
+            impl ::reqwest_graphql::Operation for #struct_name {
+                type Response = #response;
+                const DOCUMENT: &'static str = #document;
+                const OPERATION_NAME: &'static str = #name;
+            }
+        });
+
+        self.selection_struct(&response, &pascal_case(name), root, &[selection_set])
+    }
+
+    /// Operation text followed by the fragments it spreads
+    fn document(&self, operation: &'a OperationDefinition<'a, &'a str>) -> String {
+        let mut used = BTreeSet::new();
+        let mut pending = vec![match operation {
+            OperationDefinition::Query(query) => &query.selection_set,
+            OperationDefinition::Mutation(mutation) => &mutation.selection_set,
+            OperationDefinition::Subscription(subscription) => &subscription.selection_set,
+            OperationDefinition::SelectionSet(selection_set) => selection_set,
+        }];
+        while let Some(selection_set) = pending.pop() {
+            for selection in &selection_set.items {
+                match selection {
+                    Selection::Field(field) => pending.push(&field.selection_set),
+                    Selection::InlineFragment(fragment) => pending.push(&fragment.selection_set),
+                    Selection::FragmentSpread(spread) => {
+                        if let Some(fragment) = self.fragments.get(spread.fragment_name) {
+                            if used.insert(fragment.name) {
+                                pending.push(&fragment.selection_set);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut document = operation.to_string();
+        for name in used {
+            document.push_str(&self.fragments[name].to_string());
+        }
+        document
+    }
+
+    fn collect(
+        &self,
+        parent: &'a str,
+        selection_set: &'a SelectionSet<'a, &'a str>,
+        optional: bool,
+        selected: &mut Vec<Selected<'a>>,
+    ) -> Result<(), String> {
+        for selection in &selection_set.items {
+            match selection {
+                Selection::Field(field) => {
+                    let key = field.alias.unwrap_or(field.name);
+                    match selected.iter_mut().find(|selected| selected.key == key) {
+                        Some(existing) => existing.selection_sets.push(&field.selection_set),
+                        None => selected.push(Selected {
+                            key,
+                            name: field.name,
+                            on_type: parent,
+                            optional,
+                            selection_sets: vec![&field.selection_set],
+                        }),
+                    }
+                }
+                Selection::FragmentSpread(spread) => {
+                    let fragment = self
+                        .fragments
+                        .get(spread.fragment_name)
+                        .ok_or_else(|| format!("unknown fragment `{}`", spread.fragment_name))?;
+                    let TypeCondition::On(on_type) = fragment.type_condition;
+                    let optional = optional || on_type != parent;
+                    self.collect(on_type, &fragment.selection_set, optional, selected)?;
+                }
+                Selection::InlineFragment(fragment) => {
+                    let on_type = match fragment.type_condition {
+                        Some(TypeCondition::On(on_type)) => on_type,
+                        None => parent,
+                    };
+                    let optional = optional || on_type != parent;
+                    self.collect(on_type, &fragment.selection_set, optional, selected)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn selection_struct(
+        &mut self,
+        struct_name: &Ident,
+        prefix: &str,
+        parent: &'a str,
+        selection_sets: &[&'a SelectionSet<'a, &'a str>],
+    ) -> Result<(), String> {
+        let mut selected = Vec::new();
+        for selection_set in selection_sets {
+            self.collect(parent, selection_set, false, &mut selected)?;
+        }
+
+        let mut fields = Vec::new();
+        for selected in selected {
+            let field = field_ident(selected.key);
+            let rename = rename(&field, selected.key);
+
+            let field_type = if selected.name == "__typename" {
+                wrap(
+                    &Type::NonNullType(Box::new(Type::NamedType("String"))),
+                    quote!(String),
+                    selected.optional,
+                )
+            } else {
+                let definition = self
+                    .schema
+                    .fields(selected.on_type)
+                    .iter()
+                    .find(|field| field.name == selected.name)
+                    .ok_or_else(|| {
+                        format!(
+                            "unknown field `{}` on type `{}`",
+                            selected.name, selected.on_type
+                        )
+                    })?;
+                let named = named_type(&definition.field_type);
+
+                let inner = match self.schema.types.get(named) {
+                    Some(
+                        TypeDefinition::Object(_)
+                        | TypeDefinition::Interface(_)
+                        | TypeDefinition::Union(_),
+                    ) => {
+                        let nested = format!("{}{}", prefix, pascal_case(selected.key));
+                        let ident = type_ident(&nested);
+                        self.selection_struct(&ident, &nested, named, &selected.selection_sets)?;
+                        quote!(#ident)
+                    }
+                    _ => self.leaf_type(named)?,
+                };
+                wrap(&definition.field_type, inner, selected.optional)
+            };
+
+            fields.push(quote! {
+                #rename
+                pub #field: #field_type,
+            });
+        }
+
+        self.items.push(quote! {
+            #[derive(Clone, Debug, PartialEq, ::reqwest_graphql::__private::serde::Deserialize)]
+            #[serde(crate = "::reqwest_graphql::__private::serde")]
+            pub struct #struct_name {
+                #(#fields)*
+            }
+        });
+        Ok(())
+    }
+
+    /// Rust type of a scalar or enum
+    fn leaf_type(&mut self, name: &'a str) -> Result<TokenStream, String> {
+        Ok(match name {
+            "Int" => quote!(i64),
+            "Float" => quote!(f64),
+            "String" | "ID" => quote!(String),
+            "Boolean" => quote!(bool),
+            _ => match self.schema.types.get(name) {
+                Some(TypeDefinition::Enum(_)) => {
+                    self.enums.insert(name);
+                    let ident = type_ident(name);
+                    quote!(#ident)
+                }
+                Some(TypeDefinition::Scalar(_)) => quote!(::reqwest_graphql::__private::Value),
+                Some(_) => return Err(format!("`{}` is not a scalar or enum type", name)),
+                None => return Err(format!("unknown type `{}`", name)),
+            },
+        })
+    }
+
+    fn input_type(&mut self, input: &Type<'a, &'a str>) -> Result<TokenStream, String> {
+        let name = named_type(input);
+        let inner = match self.schema.types.get(name) {
+            Some(TypeDefinition::InputObject(_)) => {
+                self.inputs.insert(name);
+                let ident = type_ident(name);
+                quote!(#ident)
+            }
+            _ => self.leaf_type(name)?,
+        };
+        Ok(wrap(input, inner, false))
+    }
+
+    fn input_object(&mut self, name: &'a str) -> Result<(), String> {
+        let input = match self.schema.types.get(name) {
+            Some(TypeDefinition::InputObject(input)) => input,
+            _ => return Err(format!("`{}` is not an input object", name)),
+        };
+
+        let mut fields = Vec::new();
+        for input_field in &input.fields {
+            let field = field_ident(input_field.name);
+            let rename = rename(&field, input_field.name);
+            let field_type = self.input_type(&input_field.value_type)?;
+            fields.push(quote! {
+                #rename
+                pub #field: #field_type,
+            });
+        }
+
+        let ident = type_ident(name);
+        self.items.push(quote! {
+            #[derive(Clone, Debug, PartialEq, ::reqwest_graphql::__private::serde::Serialize)]
+            #[serde(crate = "::reqwest_graphql::__private::serde")]
+            pub struct #ident {
+                #(#fields)*
+            }
+        });
+        Ok(())
+    }
+
+    fn enumeration(&mut self, name: &'a str) {
+        let values = match self.schema.types.get(name) {
+            Some(TypeDefinition::Enum(definition)) => &definition.values,
+            _ => return,
+        };
+
+        let variants = values.iter().map(|value| {
+            let variant = type_ident(&pascal_case(value.name));
+            let name = value.name;
+            quote! {
+                #[serde(rename = #name)]
+                #variant,
+            }
+        });
+
+        let ident = type_ident(name);
+        self.items.push(quote! {
+            #[derive(
+                Clone,
+                Copy,
+                Debug,
+                PartialEq,
+                Eq,
+                Hash,
+                ::reqwest_graphql::__private::serde::Serialize,
+                ::reqwest_graphql::__private::serde::Deserialize
+            )]
+            #[serde(crate = "::reqwest_graphql::__private::serde")]
+            pub enum #ident {
+                #(#variants)*
+            }
+        });
+    }
+}
+
+fn named_type<'a>(field_type: &Type<'a, &'a str>) -> &'a str {
+    match field_type {
+        Type::NamedType(name) => name,
+        Type::ListType(inner) | Type::NonNullType(inner) => named_type(inner),
+    }
+}
+
+/// Wraps the named type in `Vec` and `Option` following the GraphQL type
+fn wrap<'a>(field_type: &Type<'a, &'a str>, named: TokenStream, nullable: bool) -> TokenStream {
+    let (inner, non_null) = match field_type {
+        Type::NonNullType(inner) => (&**inner, true),
+        other => (other, false),
+    };
+    let wrapped = match inner {
+        Type::ListType(item) => {
+            let item = wrap(item, named, false);
+            quote!(Vec<#item>)
+        }
+        _ => named,
+    };
+    if non_null && !nullable {
+        wrapped
+    } else {
+        quote!(Option<#wrapped>)
+    }
+}
+
+fn rename(field: &Ident, name: &str) -> TokenStream {
+    if field.to_string().trim_start_matches("r#") == name {
+        quote!()
+    } else {
+        quote!(#[serde(rename = #name)])
+    }
+}
+
+fn field_ident(name: &str) -> Ident {
+    let mut snake = String::new();
+    let mut previous_lower = false;
+    for c in name.trim_start_matches('_').chars() {
+        if c.is_uppercase() && previous_lower {
+            snake.push('_');
+        }
+        previous_lower = c.is_lowercase() || c.is_ascii_digit();
+        snake.extend(c.to_lowercase());
+    }
+
+    match snake.as_str() {
+        "" => Ident::new("field", Span::call_site()),
+        "self" | "super" | "crate" => Ident::new(&format!("{}_", snake), Span::call_site()),
+        keyword if KEYWORDS.contains(&keyword) => Ident::new_raw(keyword, Span::call_site()),
+        _ => Ident::new(&snake, Span::call_site()),
+    }
+}
+
+fn type_ident(name: &str) -> Ident {
+    match name {
+        "Self" => Ident::new("Self_", Span::call_site()),
+        _ => Ident::new(name, Span::call_site()),
+    }
+}
+
+/// Capitalizes each `_` separated part, lowercasing parts which are all uppercase
+fn pascal_case(name: &str) -> String {
+    name.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            let first = chars.next().unwrap_or_default().to_uppercase();
+            if part.chars().all(|c| !c.is_lowercase()) {
+                format!("{}{}", first, chars.as_str().to_lowercase())
+            } else {
+                format!("{}{}", first, chars.as_str())
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,172 @@
+//! Procedural macros of reqwest-graphql, use them through its `macros` feature
+
+mod codegen;
+
+use graphql_parser::query::{parse_query, Definition, OperationDefinition};
+use proc_macro::TokenStream;
+use quote::quote;
+use std::path::PathBuf;
+use syn::punctuated::Punctuated;
+use syn::{parse_macro_input, LitStr, Token};
+
+/// Checks the syntax of a GraphQL document at compile time and expands to it as `&'static str`
+///
+/// ```rust,ignore
+///let query = gql!("query Viewer { viewer { name } }");
+/// ```
+#[proc_macro]
+pub fn gql(input: TokenStream) -> TokenStream {
+    let literal = parse_macro_input!(input as LitStr);
+
+    match operation_names(&literal) {
+        Ok(_) => quote!(#literal).into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Like `gql!`, but expands to the document together with the name of its operation
+///
+/// The document must contain exactly one operation and it must be named.
+///
+/// ```rust,ignore
+///let (query, operation_name) = gql_operation!("query Viewer { viewer { name } }");
+/// ```
+#[proc_macro]
+pub fn gql_operation(input: TokenStream) -> TokenStream {
+    let literal = parse_macro_input!(input as LitStr);
+
+    let names = match operation_names(&literal) {
+        Ok(names) => names,
+        Err(error) => return error.to_compile_error().into(),
+    };
+    match names.as_slice() {
+        [Some(name)] => quote!((#literal, #name)).into(),
+        [None] => syn::Error::new(literal.span(), "GraphQL operation has no name")
+            .to_compile_error()
+            .into(),
+        _ => syn::Error::new(
+            literal.span(),
+            format!(
+                "GraphQL document must contain exactly one operation, found {}",
+                names.len()
+            ),
+        )
+        .to_compile_error()
+        .into(),
+    }
+}
+
+/// Generates typed variables and responses for the operations of `.graphql` files
+///
+/// The first path is the schema in SDL, the others contain operations and fragments. Paths
+/// are relative to the crate root. For each operation `Name` this generates:
+///
+/// * `Name`, holding the variables and implementing `reqwest_graphql::Operation`
+/// * `NameResponse` and one struct per nested selection, e.g. `NameUser`
+/// * one enum or struct per schema enum and input object used
+///
+/// Fields selected through fragments on another type are optional. Custom scalars are
+/// `serde_json::Value`.
+///
+/// ```rust,ignore
+///graphql_operations!("schema.graphql", "src/queries.graphql");
+///
+///let data = client.execute(UserById { id: "1".into() }).await?;
+/// ```
+#[proc_macro]
+pub fn graphql_operations(input: TokenStream) -> TokenStream {
+    let paths = parse_macro_input!(input with Punctuated::<LitStr, Token![,]>::parse_terminated);
+
+    match generate(&paths.into_iter().collect::<Vec<_>>()) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn generate(paths: &[LitStr]) -> syn::Result<proc_macro2::TokenStream> {
+    let (schema_path, document_paths) = match paths {
+        [schema, documents @ ..] if !documents.is_empty() => (schema, documents),
+        _ => {
+            return Err(syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "expected a schema path followed by document paths",
+            ))
+        }
+    };
+
+    let root = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default());
+    let read = |literal: &LitStr| {
+        let path = root.join(literal.value());
+        std::fs::read_to_string(&path)
+            .map(|source| (path.display().to_string(), source))
+            .map_err(|error| {
+                syn::Error::new(
+                    literal.span(),
+                    format!("cannot read {}: {}", path.display(), error),
+                )
+            })
+    };
+
+    let (schema_file, schema_source) = read(schema_path)?;
+    let schema = codegen::Schema::parse(&schema_source)
+        .map_err(|error| syn::Error::new(schema_path.span(), error))?;
+
+    let mut files = vec![schema_file];
+    let mut sources = Vec::new();
+    for path in document_paths {
+        let (file, source) = read(path)?;
+        files.push(file);
+        sources.push(source);
+    }
+    let documents = sources
+        .iter()
+        .zip(document_paths)
+        .map(|(source, path)| {
+            parse_query::<&str>(source).map_err(|error| {
+                syn::Error::new(
+                    path.span(),
+                    format!("invalid GraphQL document: {}", error.to_string().trim_end()),
+                )
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let items = codegen::Generator::new(&schema, &documents)
+        .generate()
+        .map_err(|error| syn::Error::new(proc_macro2::Span::call_site(), error))?;
+
+    // Rebuilds the calling crate when one of the files changes
+    Ok(quote! {
+        #(const _: &str = include_str!(#files);)*
+        #items
+    })
+}
+
+/// Parses the document, returning the names of its operations
+fn operation_names(literal: &LitStr) -> syn::Result<Vec<Option<String>>> {
+    let document = literal.value();
+    let parsed = parse_query::<&str>(&document).map_err(|error| {
+        syn::Error::new(
+            literal.span(),
+            format!("invalid GraphQL document: {}", error.to_string().trim_end()),
+        )
+    })?;
+
+    Ok(parsed
+        .definitions
+        .iter()
+        .filter_map(|definition| match definition {
+            Definition::Operation(OperationDefinition::SelectionSet(_)) => Some(None),
+            Definition::Operation(OperationDefinition::Query(query)) => {
+                Some(query.name.map(String::from))
+            }
+            Definition::Operation(OperationDefinition::Mutation(mutation)) => {
+                Some(mutation.name.map(String::from))
+            }
+            Definition::Operation(OperationDefinition::Subscription(subscription)) => {
+                Some(subscription.name.map(String::from))
+            }
+            Definition::Fragment(_) => None,
+        })
+        .collect())
+}
@@ -0,0 +1,232 @@
+use crate::client::rebuild_response;
+use crate::error::GraphQLError;
+use crate::middleware::{Middleware, Next};
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use reqwest::{Request, Response, StatusCode};
+use serde_json::Value;
+use std::future::Future;
+use tokio::sync::Mutex;
+
+/// Source of the bearer token sent with every request
+///
+/// The provider is asked for a token before each HTTP request, including retries, so it
+/// can cache the token and refresh it when it expires. Async closures returning
+/// `Result<String, GraphQLError>` implement it.
+///
+/// ```rust
+///use reqwest_graphql::{Client, GraphQLError};
+///
+///let client = Client::builder()
+///    .endpoint("https://example.com/graphql")
+///    .token_provider(|| async { Ok::<_, GraphQLError>("<some_token>".to_string()) })
+///    .build()
+///    .unwrap();
+/// ```
+#[async_trait]
+pub trait TokenProvider: Send + Sync + 'static {
+    async fn token(&self) -> Result<String, GraphQLError>;
+}
+
+#[async_trait]
+impl<F, Fut> TokenProvider for F
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<String, GraphQLError>> + Send,
+{
+    async fn token(&self) -> Result<String, GraphQLError> {
+        self().await
+    }
+}
+
+/// Sets the `Authorization` header from a [`TokenProvider`]
+pub(crate) struct BearerAuth<P>(pub(crate) P);
+
+#[async_trait]
+impl<P: TokenProvider> Middleware for BearerAuth<P> {
+    async fn handle(&self, mut request: Request, next: Next<'_>) -> Result<Response, GraphQLError> {
+        let token = self.0.token().await?;
+        request.headers_mut().insert(AUTHORIZATION, bearer(&token)?);
+        next.run(request).await
+    }
+}
+
+/// Source of headers computed for every request, e.g. rotating credentials or the tenant of
+/// the current call
+///
+/// The provider is asked for the headers before each HTTP request, including retries. They
+/// replace the client headers with the same name. Async closures returning
+/// `Result<HeaderMap, GraphQLError>` implement it.
+///
+/// ```rust
+///use reqwest::header::HeaderMap;
+///use reqwest_graphql::{Client, GraphQLError};
+///
+///let client = Client::builder()
+///    .endpoint("https://example.com/graphql")
+///    .header_provider(|| async {
+///        let mut headers = HeaderMap::new();
+///        headers.insert("x-tenant", "acme".parse().unwrap());
+///        Ok::<_, GraphQLError>(headers)
+///    })
+///    .build()
+///    .unwrap();
+/// ```
+#[async_trait]
+pub trait HeaderProvider: Send + Sync + 'static {
+    async fn headers(&self) -> Result<HeaderMap, GraphQLError>;
+}
+
+#[async_trait]
+impl<F, Fut> HeaderProvider for F
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<HeaderMap, GraphQLError>> + Send,
+{
+    async fn headers(&self) -> Result<HeaderMap, GraphQLError> {
+        self().await
+    }
+}
+
+/// Sets the headers from a [`HeaderProvider`]
+pub(crate) struct ProvidedHeaders<P>(pub(crate) P);
+
+#[async_trait]
+impl<P: HeaderProvider> Middleware for ProvidedHeaders<P> {
+    async fn handle(&self, mut request: Request, next: Next<'_>) -> Result<Response, GraphQLError> {
+        let headers = self.0.headers().await?;
+        request.headers_mut().extend(headers);
+        next.run(request).await
+    }
+}
+
+/// `Authorization` value for the token, hidden from debug output
+pub(crate) fn bearer(token: &str) -> Result<HeaderValue, GraphQLError> {
+    let mut value = HeaderValue::from_str(&format!("Bearer {}", token))
+        .map_err(|_| GraphQLError::Other("Invalid bearer token".into()))?;
+    value.set_sensitive(true);
+    Ok(value)
+}
+
+/// `Authorization` value of HTTP basic authentication, hidden from debug output
+pub(crate) fn basic(username: &str, password: Option<&str>) -> HeaderValue {
+    let credentials = format!("{}:{}", username, password.unwrap_or_default());
+    let mut value = HeaderValue::from_str(&format!("Basic {}", BASE64.encode(credentials)))
+        .expect("base64 is a valid header value");
+    value.set_sensitive(true);
+    value
+}
+
+/// Middleware refreshing the bearer token once the server rejects it
+///
+/// A request answered with HTTP 401, or with a GraphQL error whose `extensions.code` is
+/// `UNAUTHENTICATED`, is sent once more with the token returned by the refresh callback.
+/// Later requests use the refreshed token, concurrent rejections share a single refresh.
+///
+/// ```rust
+///use reqwest_graphql::{Client, GraphQLError, TokenRefresh};
+///
+///let client = Client::builder()
+///    .endpoint("https://example.com/graphql")
+///    .middleware(
+///        TokenRefresh::new(|| async { Ok::<_, GraphQLError>("<new_token>".to_string()) })
+///            .token("<current_token>"),
+///    )
+///    .build()
+///    .unwrap();
+/// ```
+pub struct TokenRefresh {
+    refresh: Box<dyn TokenProvider>,
+    token: Mutex<Option<String>>,
+}
+
+impl TokenRefresh {
+    pub fn new<P: TokenProvider>(refresh: P) -> Self {
+        Self {
+            refresh: Box::new(refresh),
+            token: Mutex::new(None),
+        }
+    }
+
+    /// Token sent until the first refresh, otherwise requests keep their own `Authorization`
+    pub fn token(mut self, token: &str) -> Self {
+        self.token = Mutex::new(Some(token.to_string()));
+        self
+    }
+
+    /// Refreshes the token unless another request already replaced the rejected one
+    async fn refreshed(&self, rejected: Option<&str>) -> Result<String, GraphQLError> {
+        let mut token = self.token.lock().await;
+        match token.as_deref() {
+            Some(current) if Some(current) != rejected => Ok(current.to_string()),
+            _ => {
+                let refreshed = self.refresh.token().await?;
+                *token = Some(refreshed.clone());
+                Ok(refreshed)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Middleware for TokenRefresh {
+    async fn handle(&self, mut request: Request, next: Next<'_>) -> Result<Response, GraphQLError> {
+        let token = self.token.lock().await.clone();
+        if let Some(token) = &token {
+            request.headers_mut().insert(AUTHORIZATION, bearer(token)?);
+        }
+        let retry = request.try_clone();
+
+        let response = next.run(request).await?;
+        let response = match unauthenticated(response).await? {
+            Ok(response) => return Ok(response),
+            Err(response) => response,
+        };
+        let mut retry = match retry {
+            Some(retry) => retry,
+            None => return Ok(response),
+        };
+
+        let token = self.refreshed(token.as_deref()).await?;
+        retry.headers_mut().insert(AUTHORIZATION, bearer(&token)?);
+        next.run(retry).await
+    }
+}
+
+/// Splits responses into accepted (`Ok`) and rejected for missing authentication (`Err`)
+async fn unauthenticated(response: Response) -> Result<Result<Response, Response>, GraphQLError> {
+    if response.status() == StatusCode::UNAUTHORIZED {
+        return Ok(Err(response));
+    }
+    if !response.status().is_success() {
+        return Ok(Ok(response));
+    }
+
+    // The body has to be read to find the error code, the response is rebuilt from it
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = response.bytes().await?;
+    let rejected = has_unauthenticated_error(&body);
+
+    let response = rebuild_response(status, headers, body);
+    Ok(if rejected {
+        Err(response)
+    } else {
+        Ok(response)
+    })
+}
+
+fn has_unauthenticated_error(body: &[u8]) -> bool {
+    if !body.windows(15).any(|window| window == b"UNAUTHENTICATED") {
+        return false;
+    }
+    serde_json::from_slice::<Value>(body).is_ok_and(|body| {
+        body["errors"].as_array().is_some_and(|errors| {
+            errors
+                .iter()
+                .any(|error| error["extensions"]["code"] == "UNAUTHENTICATED")
+        })
+    })
+}
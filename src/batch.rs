@@ -0,0 +1,93 @@
+use crate::client::{read_json, GQLClient, GraphQLResponse, RequestOptions};
+use crate::error::GraphQLError;
+use crate::metrics;
+use futures_util::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+impl GQLClient {
+    /// Sends several operations in a single HTTP request
+    ///
+    /// The body is a JSON array of operations and the server is expected to answer
+    /// with an array of responses in the same order. Transport failures fail the whole batch,
+    /// while GraphQL errors are reported per operation.
+    pub async fn query_batch<K, T: Serialize>(
+        &self,
+        operations: &[(&str, T)],
+    ) -> Result<Vec<Result<K, GraphQLError>>, GraphQLError>
+    where
+        K: for<'de> Deserialize<'de>,
+    {
+        #[cfg(feature = "validation")]
+        for (query, _) in operations {
+            self.check_syntax(query)?;
+        }
+
+        let body = operations
+            .iter()
+            .map(|(query, variables)| self.envelope(&self.request_body(query, None, variables)?))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let request = self
+            .client
+            .post(&self.endpoint)
+            .json(&body)
+            .headers(self.header_map.clone());
+
+        let mut raw_response = self
+            .execute_request(
+                request,
+                &RequestOptions {
+                    cacheable: false,
+                    ..self.options("", None)
+                },
+            )
+            .await?;
+        let measured = metrics::take(&mut raw_response);
+        let responses = read_json::<Vec<Value>>(raw_response)
+            .await
+            .and_then(|responses| match responses.len() == operations.len() {
+                true => Ok(responses),
+                false => Err(GraphQLError::Other(format!(
+                    "Expected {} responses in batch, got {}",
+                    operations.len(),
+                    responses.len()
+                ))),
+            });
+        let responses = metrics::count(responses, measured.as_ref())?;
+
+        let results = responses
+            .into_iter()
+            .map(|response| {
+                GraphQLResponse::<K>::from_value(response).and_then(GraphQLResponse::into_result)
+            })
+            .collect::<Vec<_>>();
+        // The batch counts as failed once, by the first of its operations which did
+        let failed = results.iter().find_map(|result| result.as_ref().err());
+        if let (Some(error), Some(measured)) = (failed, &measured) {
+            measured.increment_errors(error.kind());
+        }
+        Ok(results)
+    }
+
+    /// Runs the queries concurrently, with at most `max_concurrency` requests in flight
+    ///
+    /// Each query is a separate HTTP request going through retries and rate limiting,
+    /// results are returned in the order of the queries.
+    pub async fn query_many<'a, K, T, I>(
+        &self,
+        queries: I,
+        max_concurrency: usize,
+    ) -> Vec<Result<K, GraphQLError>>
+    where
+        K: for<'de> Deserialize<'de>,
+        T: Serialize,
+        I: IntoIterator<Item = (&'a str, T)>,
+    {
+        stream::iter(queries)
+            .map(|(query, variables)| self.query_with_vars::<K, T>(query, variables))
+            .buffered(max_concurrency.max(1))
+            .collect()
+            .await
+    }
+}
@@ -0,0 +1,750 @@
+use crate::auth::{basic, bearer, BearerAuth, HeaderProvider, ProvidedHeaders, TokenProvider};
+use crate::cache::ResponseCache;
+use crate::circuit_breaker::{Breaker, CircuitBreaker};
+use crate::client::{BodyHook, GQLClient, USER_AGENT};
+use crate::error::GraphQLError;
+use crate::failover::{Failover, FailoverStrategy};
+use crate::idempotency::IDEMPOTENCY_KEY_HEADER;
+use crate::metrics::MetricsSink;
+use crate::middleware::Middleware;
+use crate::over_http::ErrorDecoder;
+use crate::persisted::PersistedQueries;
+use crate::rate_limit::{RateLimit, RateLimiter};
+use crate::request_id::{random_id, RequestIdGenerator, REQUEST_ID_HEADER};
+use crate::retry::RetryPolicy;
+#[cfg(feature = "subscription")]
+use crate::subscription::{ConnectionInitProvider, SubscriptionProtocol};
+use crate::transport::Transport;
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION},
+    Certificate, ClientBuilder, Identity, NoProxy, Proxy, StatusCode, Url,
+};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Fluent configuration for [`GQLClient`]
+///
+/// The first invalid option is reported by `build`, the ones after it are ignored.
+pub struct GQLClientBuilder {
+    endpoint: Option<String>,
+    failover: Vec<Url>,
+    failover_strategy: FailoverStrategy,
+    header_map: HeaderMap,
+    client_builder: ClientBuilder,
+    proxies: Vec<Proxy>,
+    no_proxy: Option<NoProxy>,
+    retry_policy: Option<RetryPolicy>,
+    timeout: Option<Duration>,
+    hedge_after: Option<Duration>,
+    max_response_size: Option<u64>,
+    get_queries: bool,
+    graphql_over_http: bool,
+    graphql_body: bool,
+    minify_queries: bool,
+    persisted_queries: Option<Arc<PersistedQueries>>,
+    automatic_persisted_queries: bool,
+    body_hook: Option<BodyHook>,
+    response_hook: Option<BodyHook>,
+    error_decoder: Option<ErrorDecoder>,
+    request_id_header: HeaderName,
+    request_id_generator: Option<RequestIdGenerator>,
+    idempotency_key_header: HeaderName,
+    middlewares: Vec<Arc<dyn Middleware>>,
+    /// Headers are added by a provider once the request is sent, after it was looked up
+    provided_headers: bool,
+    metrics: Option<Arc<dyn MetricsSink>>,
+    transport: Option<Arc<dyn Transport>>,
+    cache: Option<ResponseCache>,
+    etags: bool,
+    deduplicate: bool,
+    rate_limit: Option<RateLimit>,
+    circuit_breaker: Option<CircuitBreaker>,
+    #[cfg(feature = "validation")]
+    validate_queries: bool,
+    #[cfg(feature = "subscription")]
+    subscription_protocol: SubscriptionProtocol,
+    #[cfg(feature = "subscription")]
+    subscription_reconnect: Option<RetryPolicy>,
+    #[cfg(feature = "subscription")]
+    subscription_keep_alive: Option<Duration>,
+    #[cfg(feature = "subscription")]
+    connection_init: Option<Arc<dyn ConnectionInitProvider>>,
+    error: Option<GraphQLError>,
+}
+
+impl GQLClientBuilder {
+    pub fn new() -> Self {
+        Self {
+            endpoint: None,
+            failover: Vec::new(),
+            failover_strategy: FailoverStrategy::default(),
+            header_map: HeaderMap::new(),
+            client_builder: ClientBuilder::new().user_agent(USER_AGENT),
+            proxies: Vec::new(),
+            no_proxy: None,
+            retry_policy: None,
+            timeout: None,
+            hedge_after: None,
+            max_response_size: None,
+            get_queries: false,
+            graphql_over_http: false,
+            graphql_body: false,
+            minify_queries: false,
+            persisted_queries: None,
+            automatic_persisted_queries: false,
+            body_hook: None,
+            response_hook: None,
+            error_decoder: None,
+            request_id_header: HeaderName::from_static(REQUEST_ID_HEADER),
+            request_id_generator: None,
+            idempotency_key_header: HeaderName::from_static(IDEMPOTENCY_KEY_HEADER),
+            middlewares: Vec::new(),
+            provided_headers: false,
+            metrics: None,
+            transport: None,
+            cache: None,
+            etags: false,
+            deduplicate: false,
+            rate_limit: None,
+            circuit_breaker: None,
+            #[cfg(feature = "validation")]
+            validate_queries: false,
+            #[cfg(feature = "subscription")]
+            subscription_protocol: SubscriptionProtocol::default(),
+            #[cfg(feature = "subscription")]
+            subscription_reconnect: None,
+            #[cfg(feature = "subscription")]
+            subscription_keep_alive: None,
+            #[cfg(feature = "subscription")]
+            connection_init: None,
+            error: None,
+        }
+    }
+
+    pub fn endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = Some(endpoint.to_string());
+        self
+    }
+
+    /// Endpoints of replicas of the same API, the next one is used when one can't be connected
+    /// to or answers with a 5xx status
+    ///
+    /// Failover runs after the other middlewares. Subscriptions only use the first endpoint.
+    pub fn endpoints(mut self, endpoints: &[&str]) -> Self {
+        self.endpoint = endpoints.first().map(|endpoint| endpoint.to_string());
+        self.failover.clear();
+        for endpoint in endpoints {
+            match Url::parse(endpoint) {
+                Ok(url) => self.failover.push(url),
+                Err(_) => self.fail(format!("Invalid endpoint: {}", endpoint)),
+            }
+        }
+        self
+    }
+
+    pub fn failover_strategy(mut self, strategy: FailoverStrategy) -> Self {
+        self.failover_strategy = strategy;
+        self
+    }
+
+    pub fn header(mut self, key: &str, value: &str) -> Self {
+        match (HeaderName::from_str(key), HeaderValue::from_str(value)) {
+            (Ok(key), Ok(value)) => {
+                self.header_map.insert(key, value);
+            }
+            _ => self.fail(format!("Invalid header: {}", key)),
+        }
+        self
+    }
+
+    /// Sends `Authorization: Bearer <token>` with every request
+    pub fn auth_bearer(mut self, token: &str) -> Self {
+        match bearer(token) {
+            Ok(value) => {
+                self.header_map.insert(AUTHORIZATION, value);
+            }
+            Err(_) => self.fail("Invalid bearer token".into()),
+        }
+        self
+    }
+
+    /// Sends `Authorization: Basic` with the base64 encoded credentials with every request
+    pub fn basic_auth<U, P>(mut self, username: U, password: Option<P>) -> Self
+    where
+        U: std::fmt::Display,
+        P: std::fmt::Display,
+    {
+        let password = password.map(|password| password.to_string());
+        self.header_map.insert(
+            AUTHORIZATION,
+            basic(&username.to_string(), password.as_deref()),
+        );
+        self
+    }
+
+    /// Asks the provider for a bearer token before every request, so it can be refreshed
+    ///
+    /// The provider runs as a middleware at this position of the chain.
+    pub fn token_provider<P: TokenProvider>(self, provider: P) -> Self {
+        self.middleware(BearerAuth(provider))
+    }
+
+    /// Asks the provider for headers before every request, replacing the client headers with
+    /// the same name
+    ///
+    /// The provider runs as a middleware at this position of the chain. As the headers are only
+    /// known once the request is sent, responses are then neither cached, revalidated with
+    /// ETags nor shared between identical queries, so tenants never get each other's data.
+    pub fn header_provider<P: HeaderProvider>(mut self, provider: P) -> Self {
+        self.provided_headers = true;
+        self.middleware(ProvidedHeaders(provider))
+    }
+
+    /// Bounds how long each request may take, timed out queries fail with
+    /// an error whose `is_timeout` returns true
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Bounds how long connecting to the server may take, separately from `timeout`
+    ///
+    /// Requests which couldn't connect in time fail with `GraphQLError::ConnectTimeout`, telling
+    /// an unreachable server apart from a slow query.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.client_builder = self.client_builder.connect_timeout(timeout);
+        self
+    }
+
+    /// Sends a duplicate of queries which haven't been answered after `delay` and uses whichever
+    /// response arrives first, reducing tail latency at the cost of extra load
+    ///
+    /// Mutations and subscriptions are never duplicated.
+    pub fn hedge(mut self, delay: Duration) -> Self {
+        self.hedge_after = Some(delay);
+        self
+    }
+
+    /// Abandons responses larger than `bytes`, failing with `GraphQLError::ResponseTooLarge`
+    /// as soon as their `Content-Length` or the part of the body read exceeds it
+    pub fn max_response_size(mut self, bytes: u64) -> Self {
+        self.max_response_size = Some(bytes);
+        self
+    }
+
+    /// Maximum number of idle connections kept open to each host for reuse, 0 disables reuse
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.client_builder = self.client_builder.pool_max_idle_per_host(max);
+        self
+    }
+
+    /// How long an idle connection is kept open for reuse, 90 seconds by default
+    ///
+    /// Should be shorter than the idle timeout of the server or load balancer, so connections
+    /// they closed aren't reused.
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.client_builder = self.client_builder.pool_idle_timeout(timeout);
+        self
+    }
+
+    /// Enables TCP keepalive probes at this interval, so dead connections are detected and
+    /// middleboxes don't drop idle ones
+    pub fn tcp_keepalive(mut self, interval: Duration) -> Self {
+        self.client_builder = self.client_builder.tcp_keepalive(interval);
+        self
+    }
+
+    /// Only speaks HTTP/1.1, even to servers offering HTTP/2
+    pub fn http1_only(mut self) -> Self {
+        self.client_builder = self.client_builder.http1_only();
+        self
+    }
+
+    /// Speaks HTTP/2 right away instead of negotiating it, so requests to a gateway known to
+    /// support it are multiplexed over a single connection, also over plain HTTP
+    pub fn http2_prior_knowledge(mut self) -> Self {
+        self.client_builder = self.client_builder.http2_prior_knowledge();
+        self
+    }
+
+    /// Lets HTTP/2 flow control grow its windows with the bandwidth of the connection, instead
+    /// of capping each stream at the default 64KiB window
+    pub fn http2_adaptive_window(mut self, enabled: bool) -> Self {
+        self.client_builder = self.client_builder.http2_adaptive_window(enabled);
+        self
+    }
+
+    /// Sends HTTP/2 pings at this interval to keep idle connections to the gateway open
+    pub fn http2_keep_alive_interval(mut self, interval: Duration) -> Self {
+        self.client_builder = self
+            .client_builder
+            .http2_keep_alive_interval(interval)
+            .http2_keep_alive_while_idle(true);
+        self
+    }
+
+    /// Speaks HTTP/3 over QUIC right away, the endpoint must be an `https` URL
+    ///
+    /// HTTP/3 support of reqwest is unstable, building with the `http3` feature requires the
+    /// `RUSTFLAGS='--cfg reqwest_unstable'` environment variable. HTTP/3 runs over rustls, so it
+    /// can't be combined with a client identity, which selects native-tls.
+    #[cfg(feature = "http3")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http3")))]
+    pub fn http3_prior_knowledge(mut self) -> Self {
+        self.client_builder = self.client_builder.http3_prior_knowledge();
+        self
+    }
+
+    /// Replaces the default `User-Agent`, `reqwest-graphql/<version>`
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.client_builder = self.client_builder.user_agent(user_agent);
+        self
+    }
+
+    /// Sends every request through the proxy
+    ///
+    /// Without an explicit proxy, the `HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY` and `NO_PROXY`
+    /// environment variables are used.
+    pub fn proxy(self, url: &str) -> Self {
+        self.add_proxy(Proxy::all(url))
+    }
+
+    /// Sends plain HTTP requests through the proxy
+    pub fn http_proxy(self, url: &str) -> Self {
+        self.add_proxy(Proxy::http(url))
+    }
+
+    /// Sends HTTPS requests through the proxy
+    pub fn https_proxy(self, url: &str) -> Self {
+        self.add_proxy(Proxy::https(url))
+    }
+
+    /// Sends every request through a SOCKS5 proxy, `socks5h://` URLs resolve host names
+    /// on the proxy
+    #[cfg(feature = "socks")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "socks")))]
+    pub fn socks5_proxy(mut self, url: &str, credentials: Option<(&str, &str)>) -> Self {
+        if !url.starts_with("socks5://") && !url.starts_with("socks5h://") {
+            self.fail(format!("Invalid SOCKS5 proxy URL: {}", url));
+            return self;
+        }
+
+        self.add_proxy(Proxy::all(url).map(|proxy| match credentials {
+            Some((username, password)) => proxy.basic_auth(username, password),
+            None => proxy,
+        }))
+    }
+
+    /// Hosts which bypass the configured proxies, a comma separated list in the `NO_PROXY` format
+    pub fn no_proxy_hosts(mut self, hosts: &str) -> Self {
+        self.no_proxy = NoProxy::from_string(hosts);
+        self
+    }
+
+    /// Connects directly, ignoring the proxy environment variables
+    pub fn no_proxy(mut self) -> Self {
+        self.client_builder = self.client_builder.no_proxy();
+        self
+    }
+
+    /// Stores cookies set by the server and sends them with subsequent requests,
+    /// for APIs relying on session cookies
+    #[cfg(feature = "cookies")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cookies")))]
+    pub fn cookie_store(mut self, enabled: bool) -> Self {
+        self.client_builder = self.client_builder.cookie_store(enabled);
+        self
+    }
+
+    /// Trusts an additional root certificate, PEM or DER encoded, e.g. of a private CA
+    pub fn add_root_certificate(mut self, certificate: &[u8]) -> Self {
+        match Certificate::from_pem(certificate).or_else(|_| Certificate::from_der(certificate)) {
+            Ok(certificate) => {
+                self.client_builder = self.client_builder.add_root_certificate(certificate);
+            }
+            Err(_) => self.fail("Invalid root certificate".into()),
+        }
+        self
+    }
+
+    /// Authenticates with the client certificate of a DER encoded PKCS#12 archive (mTLS)
+    pub fn identity_pkcs12(self, archive: &[u8], password: &str) -> Self {
+        self.identity(Identity::from_pkcs12_der(archive, password))
+    }
+
+    /// Authenticates with a PEM certificate chain and PKCS#8 PEM private key (mTLS)
+    pub fn identity_pem(self, certificate: &[u8], key: &[u8]) -> Self {
+        self.identity(Identity::from_pkcs8_pem(certificate, key))
+    }
+
+    /// Skips certificate validation, only meant for development against self-signed servers
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.client_builder = self.client_builder.danger_accept_invalid_certs(accept);
+        self
+    }
+
+    /// Sends queries as GET requests with URL parameters so they can be cached by HTTP caches,
+    /// mutations are always sent as POST
+    pub fn use_get_for_queries(mut self, enabled: bool) -> Self {
+        self.get_queries = enabled;
+        self
+    }
+
+    /// Sends the bare document with `Content-Type: application/graphql` for servers rejecting
+    /// the JSON envelope
+    ///
+    /// Only operations without variables or operation name can be sent this way, the others
+    /// still use JSON. Queries sent as GET aren't affected.
+    pub fn use_graphql_content_type(mut self, enabled: bool) -> Self {
+        self.graphql_body = enabled;
+        self
+    }
+
+    /// Strips comments and collapses whitespace in documents before they are sent, shrinking
+    /// queries included from pretty-printed `.graphql` files
+    ///
+    /// Strings and block strings are left untouched. Applies to batches, uploads and
+    /// subscriptions too.
+    pub fn minify_queries(mut self, enabled: bool) -> Self {
+        self.minify_queries = enabled;
+        self
+    }
+
+    /// Sends the SHA-256 hash of each operation instead of its document, and the document only
+    /// when the server answers that it doesn't know the hash yet
+    ///
+    /// Combined with `use_get_for_queries`, queries are GET requests whose URL only holds the
+    /// hash and variables, so CDNs can cache their responses. Batches, uploads and
+    /// subscriptions always send their documents.
+    pub fn automatic_persisted_queries(mut self, enabled: bool) -> Self {
+        self.automatic_persisted_queries = enabled;
+        self
+    }
+
+    /// Sends only the persisted query hash of each operation, never its document, for servers
+    /// which reject operations outside of an allowlist
+    ///
+    /// Operations are looked up by their operation name, or the name of the single operation
+    /// of their document. Those missing from the manifest fail without being sent.
+    pub fn persisted_queries(mut self, manifest: PersistedQueries) -> Self {
+        self.persisted_queries = Some(Arc::new(manifest));
+        self
+    }
+
+    /// Modifies the JSON envelope of every operation before it is sent, e.g. to add
+    /// `extensions.clientInfo` or keys a server expects next to `query` and `variables`
+    ///
+    /// The hook sees each operation of a batch and upload, as well as subscriptions. Queries sent
+    /// as GET get the resulting fields as URL parameters. Documents sent as
+    /// `application/graphql` have no envelope and aren't passed to it.
+    pub fn on_request_body<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&mut serde_json::Value) + Send + Sync + 'static,
+    {
+        self.body_hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Modifies the JSON of every response before it is read, e.g. to unwrap the
+    /// `{"result": {...}}` envelope of a gateway so it isn't reported as an unconventional error
+    ///
+    /// The hook runs before the status code is interpreted, and its result is what the typed,
+    /// raw and batch queries read. Responses which aren't JSON are passed on unchanged, and
+    /// streamed queries then only start reading once the whole body has been received.
+    pub fn on_response_body<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&mut serde_json::Value) + Send + Sync + 'static,
+    {
+        self.response_hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Maps the responses which aren't GraphQL responses, such as HTML error pages or the
+    /// custom JSON errors of a gateway, to the error returned by the decoder
+    ///
+    /// The decoder gets the status, headers and body of those responses whatever their status.
+    /// When it returns None they are handled as usual, e.g. as `GraphQLError::Http`.
+    pub fn error_decoder<F>(mut self, decoder: F) -> Self
+    where
+        F: Fn(StatusCode, &HeaderMap, &str) -> Option<GraphQLError> + Send + Sync + 'static,
+    {
+        self.error_decoder = Some(Arc::new(decoder));
+        self
+    }
+
+    /// Sends a random ID with every request, in the `x-request-id` header unless
+    /// `request_id_header` names another one
+    ///
+    /// The ID is also recorded in the tracing span of the request, and errors carry it as
+    /// `GraphQLError::WithRequestId`, to find the request in the server logs.
+    pub fn request_ids(mut self, enabled: bool) -> Self {
+        self.request_id_generator = enabled.then(|| Arc::new(random_id) as RequestIdGenerator);
+        self
+    }
+
+    /// Sends the ID returned by the generator with every request, like `request_ids`
+    pub fn request_id_generator<F>(mut self, generator: F) -> Self
+    where
+        F: Fn() -> String + Send + Sync + 'static,
+    {
+        self.request_id_generator = Some(Arc::new(generator));
+        self
+    }
+
+    /// Header carrying the request ID, `x-request-id` by default
+    pub fn request_id_header(mut self, name: &str) -> Self {
+        match HeaderName::from_str(name) {
+            Ok(name) => self.request_id_header = name,
+            Err(_) => self.fail(format!("Invalid header: {}", name)),
+        }
+        self
+    }
+
+    /// Header carrying the idempotency key of mutations, `Idempotency-Key` by default
+    pub fn idempotency_key_header(mut self, name: &str) -> Self {
+        match HeaderName::from_str(name) {
+            Ok(name) => self.idempotency_key_header = name,
+            Err(_) => self.fail(format!("Invalid header: {}", name)),
+        }
+        self
+    }
+
+    /// Follows the GraphQL-over-HTTP spec strictly
+    ///
+    /// `application/graphql-response+json` is requested and the status code is interpreted
+    /// according to the media type of the response: non-2xx responses fail with the GraphQL
+    /// errors they carry or `GraphQLError::Http`, and unexpected media types are rejected.
+    pub fn graphql_over_http(mut self, enabled: bool) -> Self {
+        self.graphql_over_http = enabled;
+        self
+    }
+
+    /// Appends a middleware to the chain, the first registered one runs first
+    pub fn middleware<M: Middleware>(mut self, middleware: M) -> Self {
+        self.middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    /// Reports the count, errors and latency of every operation to the sink
+    pub fn metrics<S: MetricsSink>(mut self, sink: S) -> Self {
+        self.metrics = Some(Arc::new(sink));
+        self
+    }
+
+    /// Sends requests through the transport instead of the reqwest client
+    ///
+    /// Options configuring the reqwest client, such as proxies or TLS settings, only apply to
+    /// the default transport.
+    pub fn transport<T: Transport>(mut self, transport: T) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Connects to the server through a Unix domain socket, the endpoint only provides the
+    /// HTTP path and `Host` header, e.g. `http://localhost/graphql`
+    #[cfg(all(unix, feature = "unix-socket"))]
+    #[cfg_attr(docsrs, doc(cfg(all(unix, feature = "unix-socket"))))]
+    pub fn unix_socket<P: AsRef<std::path::Path>>(self, path: P) -> Self {
+        self.transport(crate::unix_socket::UnixSocket::new(path))
+    }
+
+    /// Serves repeated queries from an in-memory cache, mutations always hit the network
+    pub fn cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Remembers the `ETag` of query responses and sends `If-None-Match` on the next identical
+    /// query, a `304 Not Modified` answer is served from the remembered body
+    pub fn etags(mut self, enabled: bool) -> Self {
+        self.etags = enabled;
+        self
+    }
+
+    /// Queries identical to one already in flight, including variables and headers, wait for
+    /// its response instead of sending another request
+    pub fn deduplicate(mut self, enabled: bool) -> Self {
+        self.deduplicate = enabled;
+        self
+    }
+
+    /// Limits the rate of requests sent by the client, across all concurrent calls
+    pub fn rate_limit(mut self, limit: RateLimit) -> Self {
+        match limit.is_valid() {
+            true => self.rate_limit = Some(limit),
+            false => self.fail(format!("Invalid rate limit: {:?}", limit)),
+        }
+        self
+    }
+
+    /// Fails requests immediately while the endpoint keeps failing, shared by all clones
+    pub fn circuit_breaker(mut self, circuit_breaker: CircuitBreaker) -> Self {
+        self.circuit_breaker = Some(circuit_breaker);
+        self
+    }
+
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// Parses every document before sending it, syntax errors fail with `GraphQLError::Syntax`
+    /// without contacting the server
+    #[cfg(feature = "validation")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "validation")))]
+    pub fn validate_queries(mut self, enabled: bool) -> Self {
+        self.validate_queries = enabled;
+        self
+    }
+
+    #[cfg(feature = "subscription")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "subscription")))]
+    pub fn subscription_protocol(mut self, protocol: SubscriptionProtocol) -> Self {
+        self.subscription_protocol = protocol;
+        self
+    }
+
+    /// Reconnects WebSocket subscriptions when the connection drops, waiting for the backoff
+    /// of the policy before each attempt and sending the subscription again
+    ///
+    /// The subscription fails once `max_attempts` reconnections in a row have failed.
+    /// `Subscription::events` reports each reconnection with `SubscriptionEvent::Reconnected`.
+    #[cfg(feature = "subscription")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "subscription")))]
+    pub fn subscription_reconnect(mut self, policy: RetryPolicy) -> Self {
+        self.subscription_reconnect = Some(policy);
+        self
+    }
+
+    /// Considers a WebSocket dead when nothing arrives from the server within the window, be it
+    /// data or a `ping`/`ka` keep-alive message
+    ///
+    /// The subscription then reconnects when `subscription_reconnect` is set, and fails with
+    /// `GraphQLError::Timeout` otherwise. Without it, AppSync connections use the timeout the
+    /// server announces.
+    #[cfg(feature = "subscription")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "subscription")))]
+    pub fn subscription_keep_alive(mut self, window: Duration) -> Self {
+        self.subscription_keep_alive = Some(window);
+        self
+    }
+
+    /// Payload of the `connection_init` message opening WebSocket subscriptions, e.g.
+    /// `json!({ "authToken": token })`, sent instead of HTTP headers by graphql-ws servers
+    #[cfg(feature = "subscription")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "subscription")))]
+    pub fn connection_init_payload<T: serde::Serialize>(mut self, payload: T) -> Self {
+        match serde_json::to_value(payload) {
+            Ok(payload) => self.connection_init = Some(Arc::new(payload)),
+            Err(_) => self.fail("Failed to serialize connection_init payload".into()),
+        }
+        self
+    }
+
+    /// Asks the provider for the `connection_init` payload each time a WebSocket is opened,
+    /// so tokens can be refreshed between reconnections
+    #[cfg(feature = "subscription")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "subscription")))]
+    pub fn connection_init_provider<P: ConnectionInitProvider>(mut self, provider: P) -> Self {
+        self.connection_init = Some(Arc::new(provider));
+        self
+    }
+
+    pub fn build(self) -> Result<GQLClient, GraphQLError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
+        let endpoint = self
+            .endpoint
+            .ok_or_else(|| GraphQLError::Other("Endpoint is not set".into()))?;
+        let mut client_builder = self.client_builder;
+        for proxy in self.proxies {
+            client_builder = client_builder.proxy(proxy.no_proxy(self.no_proxy.clone()));
+        }
+        let client = client_builder.build()?;
+
+        let mut middlewares = self.middlewares;
+        if self.failover.len() > 1 {
+            middlewares.push(Arc::new(Failover::new(
+                self.failover,
+                self.failover_strategy,
+            )));
+        }
+
+        Ok(GQLClient {
+            retry_policy: self.retry_policy,
+            timeout: self.timeout,
+            hedge_after: self.hedge_after,
+            max_response_size: self.max_response_size,
+            get_queries: self.get_queries,
+            graphql_over_http: self.graphql_over_http,
+            graphql_body: self.graphql_body,
+            minify_queries: self.minify_queries,
+            persisted_queries: self.persisted_queries,
+            automatic_persisted_queries: self.automatic_persisted_queries,
+            body_hook: self.body_hook,
+            response_hook: self.response_hook,
+            error_decoder: self.error_decoder,
+            request_id_header: self.request_id_header,
+            request_id_generator: self.request_id_generator,
+            idempotency_key_header: self.idempotency_key_header,
+            middlewares,
+            metrics: self.metrics,
+            transport: self.transport.unwrap_or_else(|| Arc::new(client.clone())),
+            cache: self.cache.filter(|_| !self.provided_headers),
+            etags: (self.etags && !self.provided_headers).then(Arc::default),
+            in_flight: (self.deduplicate && !self.provided_headers).then(Arc::default),
+            rate_limiter: self
+                .rate_limit
+                .map(|limit| Arc::new(RateLimiter::new(limit))),
+            breaker: self
+                .circuit_breaker
+                .map(|config| Arc::new(Breaker::new(config))),
+            #[cfg(feature = "validation")]
+            validate_queries: self.validate_queries,
+            #[cfg(feature = "subscription")]
+            subscription_protocol: self.subscription_protocol,
+            #[cfg(feature = "subscription")]
+            subscription_reconnect: self.subscription_reconnect,
+            #[cfg(feature = "subscription")]
+            subscription_keep_alive: self.subscription_keep_alive,
+            #[cfg(feature = "subscription")]
+            connection_init: self.connection_init,
+            ..GQLClient::from_parts(&endpoint, self.header_map, client)
+        })
+    }
+
+    /// Identities are native-tls ones, which is pinned as the TLS backend in case another
+    /// feature such as `http3` enables rustls too
+    fn identity(mut self, identity: reqwest::Result<Identity>) -> Self {
+        match identity {
+            Ok(identity) => {
+                self.client_builder = self.client_builder.use_native_tls().identity(identity);
+            }
+            Err(_) => self.fail("Invalid client identity".into()),
+        }
+        self
+    }
+
+    fn add_proxy(mut self, proxy: reqwest::Result<Proxy>) -> Self {
+        match proxy {
+            Ok(proxy) => self.proxies.push(proxy),
+            Err(_) => self.fail("Invalid proxy URL".into()),
+        }
+        self
+    }
+
+    fn fail(&mut self, message: String) {
+        if self.error.is_none() {
+            self.error = Some(GraphQLError::Other(message));
+        }
+    }
+}
+
+impl Default for GQLClientBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,432 @@
+use crate::client::{GQLClient, GraphQLResponse, RequestOptions};
+use crate::error::GraphQLError;
+use crate::hash::request_key;
+use crate::middleware::{Middleware, Next};
+use crate::transport::Transport;
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ETAG, IF_NONE_MATCH};
+use reqwest::{Request, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Response cache for queries
+///
+/// Successful responses of queries are kept for `ttl` and served without hitting the network.
+/// Entries are keyed by the request URL, headers and body, so only calls with the same query,
+/// variables and headers, e.g. the same `Authorization`, share an entry. Mutations and batches
+/// are never cached.
+///
+/// Responses are kept in a [`MemoryStore`] unless another [`CacheStore`] is set.
+#[derive(Clone)]
+pub struct ResponseCache {
+    ttl: Duration,
+    stale_while_revalidate: Duration,
+    store: Arc<dyn CacheStore>,
+    on_revalidate: Option<Arc<dyn Fn(Revalidated) + Send + Sync>>,
+    revalidating: Arc<Mutex<HashSet<String>>>,
+}
+
+impl ResponseCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            stale_while_revalidate: Duration::ZERO,
+            store: Arc::new(MemoryStore::new(1000)),
+            on_revalidate: None,
+            revalidating: Arc::default(),
+        }
+    }
+
+    /// Keeps at most `max_entries` responses in memory, the least recently used one is evicted first
+    pub fn max_entries(self, max_entries: usize) -> Self {
+        self.store(MemoryStore::new(max_entries))
+    }
+
+    /// Replaces the in-memory store, e.g. with a persistent one
+    pub fn store<S: CacheStore>(mut self, store: S) -> Self {
+        self.store = Arc::new(store);
+        self
+    }
+
+    /// Keeps serving responses for `window` after their TTL elapsed,
+    /// while a fresh one is fetched in the background
+    pub fn stale_while_revalidate(mut self, window: Duration) -> Self {
+        self.stale_while_revalidate = window;
+        self
+    }
+
+    /// Called with the fresh response once a stale one has been revalidated
+    pub fn on_revalidate<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(Revalidated) + Send + Sync + 'static,
+    {
+        self.on_revalidate = Some(Arc::new(callback));
+        self
+    }
+
+    /// Cached response and whether it is stale
+    pub(crate) async fn get(&self, key: &str) -> Option<(Response, bool)> {
+        let cached = self.store.get(key).await?;
+        let age = SystemTime::now()
+            .duration_since(cached.stored_at)
+            .unwrap_or_default();
+
+        if age >= self.ttl + self.stale_while_revalidate {
+            return None;
+        }
+        Some((cached.into_response(), age >= self.ttl))
+    }
+
+    /// Refreshes a stale entry in the background, unless it is already being refreshed
+    pub(crate) fn revalidate(
+        &self,
+        key: String,
+        query: &str,
+        request: Request,
+        transport: Arc<dyn Transport>,
+        middlewares: Vec<Arc<dyn Middleware>>,
+    ) {
+        if !self.revalidating.lock().unwrap().insert(key.clone()) {
+            return;
+        }
+
+        let cache = self.clone();
+        let query = query.to_string();
+        tokio::spawn(async move {
+            if let Ok(response) = Next::new(&*transport, &middlewares).run(request).await {
+                let status = response.status();
+                let headers = response.headers().clone();
+
+                if let Ok(body) = response.bytes().await {
+                    let cached = cache.set(&key, status, &headers, &body).await;
+                    if let (Some(response), Some(callback)) = (cached, &cache.on_revalidate) {
+                        callback(Revalidated { query, response });
+                    }
+                }
+            }
+            cache.revalidating.lock().unwrap().remove(&key);
+        });
+    }
+
+    /// Caches the response if it succeeded without GraphQL errors
+    async fn set(
+        &self,
+        key: &str,
+        status: StatusCode,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Option<CachedResponse> {
+        let cached = CachedResponse::new(status, headers, body)?;
+        self.store
+            .set(key, cached.clone(), self.ttl + self.stale_while_revalidate)
+            .await;
+        Some(cached)
+    }
+}
+
+impl GQLClient {
+    /// Sends a query through the response cache and the remembered ETags
+    pub(crate) async fn send_cached(
+        &self,
+        key: String,
+        mut request: Request,
+        options: &RequestOptions<'_>,
+    ) -> Result<Response, GraphQLError> {
+        if let Some(cache) = &self.cache {
+            if let Some((response, stale)) = cache.get(&key).await {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(stale, "serving GraphQL response from cache");
+
+                if stale {
+                    let transport = self.transport.clone();
+                    let middlewares = self.middlewares.clone();
+                    cache.revalidate(key, options.query, request, transport, middlewares);
+                }
+                return Ok(response);
+            }
+        }
+
+        let tagged = self.etags.as_ref().and_then(|etags| etags.get(&key));
+        if let Some(etag) = tagged.as_ref().and_then(CachedResponse::etag) {
+            request.headers_mut().insert(IF_NONE_MATCH, etag);
+        }
+
+        let response = self.send_with_retries(request, options).await?;
+        let response = match tagged {
+            Some(tagged) if response.status() == StatusCode::NOT_MODIFIED => tagged.into_response(),
+            _ => response,
+        };
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.bytes().await?;
+
+        if let Some(etags) = &self.etags {
+            etags.set(&key, status, &headers, &body);
+        }
+        if let Some(cache) = &self.cache {
+            cache.set(&key, status, &headers, &body).await;
+        }
+
+        let mut response = http::Response::new(body);
+        *response.status_mut() = status;
+        *response.headers_mut() = headers;
+        Ok(Response::from(response))
+    }
+
+    /// Drops the cached response of the query, so the next call hits the network
+    pub async fn invalidate_query<T: Serialize>(
+        &self,
+        query: &str,
+        variables: T,
+    ) -> Result<(), GraphQLError> {
+        let cache = match &self.cache {
+            Some(cache) => cache,
+            None => return Ok(()),
+        };
+
+        let request = self.request_for(query, None, variables)?.build()?;
+        if let Some(key) = request_key(&request) {
+            cache.store.invalidate(&key).await;
+        }
+        Ok(())
+    }
+}
+
+/// Fresh response of a query whose stale cached response was served
+pub struct Revalidated {
+    pub query: String,
+    pub response: CachedResponse,
+}
+
+impl Revalidated {
+    /// Deserializes the data of the fresh response
+    pub fn data<K>(&self) -> Result<K, GraphQLError>
+    where
+        K: for<'de> Deserialize<'de>,
+    {
+        GraphQLResponse::from_body(self.response.body.clone())?.into_result()
+    }
+}
+
+/// Storage backend of a [`ResponseCache`]
+///
+/// Stores are expected to swallow their own failures, a failed `get` is treated as a miss.
+#[async_trait]
+pub trait CacheStore: Send + Sync + 'static {
+    async fn get(&self, key: &str) -> Option<CachedResponse>;
+
+    /// Stores the response, it must not be returned by `get` once `ttl` elapsed
+    async fn set(&self, key: &str, response: CachedResponse, ttl: Duration);
+
+    async fn invalidate(&self, key: &str);
+}
+
+#[async_trait]
+impl<S: CacheStore> CacheStore for Arc<S> {
+    async fn get(&self, key: &str) -> Option<CachedResponse> {
+        S::get(self, key).await
+    }
+
+    async fn set(&self, key: &str, response: CachedResponse, ttl: Duration) {
+        S::set(self, key, response, ttl).await
+    }
+
+    async fn invalidate(&self, key: &str) {
+        S::invalidate(self, key).await
+    }
+}
+
+/// HTTP response kept by a [`CacheStore`]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    pub stored_at: SystemTime,
+}
+
+impl CachedResponse {
+    /// None unless the response succeeded without GraphQL errors
+    fn new(status: StatusCode, headers: &HeaderMap, body: &[u8]) -> Option<Self> {
+        let json: Value = serde_json::from_slice(body).ok()?;
+        if !status.is_success() || json.get("errors").is_some() {
+            return None;
+        }
+
+        let headers = headers
+            .iter()
+            .filter_map(|(key, value)| Some((key.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+
+        Some(Self {
+            status: status.as_u16(),
+            headers,
+            body: String::from_utf8(body.to_vec()).ok()?,
+            stored_at: SystemTime::now(),
+        })
+    }
+
+    fn etag(&self) -> Option<HeaderValue> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(ETAG.as_str()))
+            .and_then(|(_, value)| HeaderValue::from_str(value).ok())
+    }
+
+    fn into_response(self) -> Response {
+        let mut response = http::Response::new(self.body);
+        *response.status_mut() = StatusCode::from_u16(self.status).unwrap_or(StatusCode::OK);
+        for (key, value) in self.headers {
+            if let (Ok(key), Ok(value)) = (HeaderName::try_from(key), HeaderValue::try_from(value))
+            {
+                response.headers_mut().append(key, value);
+            }
+        }
+        Response::from(response)
+    }
+}
+
+/// In-memory LRU store
+pub struct MemoryStore {
+    max_entries: usize,
+    entries: Mutex<Lru<Entry>>,
+}
+
+impl MemoryStore {
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            max_entries: max_entries.max(1),
+            entries: Mutex::new(Lru::default()),
+        }
+    }
+
+    /// Removes every cached response
+    pub fn clear(&self) {
+        *self.entries.lock().unwrap() = Lru::default();
+    }
+}
+
+#[async_trait]
+impl CacheStore for MemoryStore {
+    async fn get(&self, key: &str) -> Option<CachedResponse> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get(key)?;
+
+        if entry.expires_at > Instant::now() {
+            return Some(entry.response.clone());
+        }
+        entries.remove(key);
+        None
+    }
+
+    async fn set(&self, key: &str, response: CachedResponse, ttl: Duration) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.insert(
+            key,
+            Entry {
+                response,
+                expires_at: Instant::now() + ttl,
+            },
+        );
+        entries.truncate(self.max_entries);
+    }
+
+    async fn invalidate(&self, key: &str) {
+        self.entries.lock().unwrap().remove(key);
+    }
+}
+
+struct Entry {
+    response: CachedResponse,
+    expires_at: Instant,
+}
+
+/// Responses remembered by their `ETag`
+pub(crate) struct ETags {
+    entries: Mutex<Lru<CachedResponse>>,
+}
+
+impl ETags {
+    const MAX_ENTRIES: usize = 1000;
+
+    fn get(&self, key: &str) -> Option<CachedResponse> {
+        self.entries.lock().unwrap().get(key).cloned()
+    }
+
+    /// Remembers the response if it is cacheable and carries an `ETag`
+    fn set(&self, key: &str, status: StatusCode, headers: &HeaderMap, body: &[u8]) {
+        if !headers.contains_key(ETAG) {
+            return;
+        }
+        if let Some(cached) = CachedResponse::new(status, headers, body) {
+            let mut entries = self.entries.lock().unwrap();
+            entries.insert(key, cached);
+            entries.truncate(Self::MAX_ENTRIES);
+        }
+    }
+}
+
+impl Default for ETags {
+    fn default() -> Self {
+        Self {
+            entries: Mutex::new(Lru::default()),
+        }
+    }
+}
+
+/// Entries ordered by last use
+struct Lru<V> {
+    entries: HashMap<String, (V, u64)>,
+    order: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl<V> Default for Lru<V> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+}
+
+impl<V> Lru<V> {
+    fn get(&mut self, key: &str) -> Option<&V> {
+        self.tick += 1;
+        let (entry, used) = self.entries.get_mut(key)?;
+        self.order.remove(used);
+        self.order.insert(self.tick, key.to_string());
+        *used = self.tick;
+        Some(entry)
+    }
+
+    fn insert(&mut self, key: &str, entry: V) {
+        self.tick += 1;
+        if let Some((_, used)) = self.entries.insert(key.to_string(), (entry, self.tick)) {
+            self.order.remove(&used);
+        }
+        self.order.insert(self.tick, key.to_string());
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some((_, used)) = self.entries.remove(key) {
+            self.order.remove(&used);
+        }
+    }
+
+    /// Evicts the least recently used entries until at most `len` remain
+    fn truncate(&mut self, len: usize) {
+        while self.entries.len() > len {
+            match self.order.pop_first() {
+                Some((_, key)) => {
+                    self.entries.remove(&key);
+                }
+                None => break,
+            }
+        }
+    }
+}
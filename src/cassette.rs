@@ -0,0 +1,252 @@
+use crate::error::GraphQLError;
+use crate::hash::sha256;
+use crate::middleware::{Middleware, Next};
+use crate::mock::{normalize, received};
+use async_trait::async_trait;
+use reqwest::header::{
+    HeaderName, HeaderValue, CONTENT_ENCODING, CONTENT_LENGTH, TRANSFER_ENCODING,
+};
+use reqwest::{Request, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+/// Whether a `Cassette` sends requests to the server
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Replays recorded responses, requests which weren't recorded yet are sent and recorded
+    #[default]
+    Auto,
+    /// Sends every request and records the responses, replacing earlier recordings
+    Record,
+    /// Only replays recorded responses, requests which weren't recorded fail
+    Replay,
+}
+
+/// Middleware recording responses to a JSON cassette file and replaying them on later runs
+///
+/// Interactions are keyed by a hash of the query, variables and operation name, so tests
+/// recorded once against a real server run offline and deterministically afterwards. Request
+/// headers aren't recorded, keeping credentials out of the cassette. Batches are keyed by their
+/// body, while uploads, whose body is streamed, are always sent.
+///
+/// ```rust,no_run
+///use reqwest_graphql::{Cassette, Client};
+///
+///let client = Client::builder()
+///    .endpoint("https://example.com/graphql")
+///    .middleware(Cassette::new("tests/cassettes/posts.json"))
+///    .build()
+///    .unwrap();
+/// ```
+pub struct Cassette {
+    path: PathBuf,
+    mode: CassetteMode,
+    interactions: Mutex<Option<Vec<Interaction>>>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct File {
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct Interaction {
+    key: String,
+    request: Recorded,
+    response: RecordedResponse,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct Recorded {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    query: String,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    variables: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    operation_name: Option<String>,
+    /// Body of requests which aren't a single operation, such as batches
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct RecordedResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl Cassette {
+    /// Cassette stored at `path`, created along with its directory on the first recording
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            mode: CassetteMode::default(),
+            interactions: Mutex::new(None),
+        }
+    }
+
+    /// Whether requests are replayed, recorded or both, `Auto` by default
+    pub fn mode(mut self, mode: CassetteMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Recorded interactions, read from the file on first use
+    fn interactions(&self) -> Result<MutexGuard<'_, Option<Vec<Interaction>>>, GraphQLError> {
+        let mut interactions = self
+            .interactions
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        if interactions.is_none() {
+            let file = match std::fs::read_to_string(&self.path) {
+                Ok(contents) => serde_json::from_str::<File>(&contents).map_err(|error| {
+                    GraphQLError::Other(format!(
+                        "Invalid cassette {}: {}",
+                        self.path.display(),
+                        error
+                    ))
+                })?,
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => File::default(),
+                Err(error) => return Err(self.io_error(error)),
+            };
+            *interactions = Some(file.interactions);
+        }
+
+        Ok(interactions)
+    }
+
+    fn record(&self, interaction: Interaction) -> Result<(), GraphQLError> {
+        let mut interactions = self.interactions()?;
+        let interactions = interactions.get_or_insert_with(Vec::new);
+        interactions.retain(|recorded| recorded.key != interaction.key);
+        interactions.push(interaction);
+
+        let file = File {
+            interactions: interactions.clone(),
+        };
+        let contents = serde_json::to_string_pretty(&file)
+            .map_err(|error| GraphQLError::Other(error.to_string()))?;
+        if let Some(directory) = self.path.parent() {
+            std::fs::create_dir_all(directory).map_err(|error| self.io_error(error))?;
+        }
+        std::fs::write(&self.path, contents + "\n").map_err(|error| self.io_error(error))
+    }
+
+    fn io_error(&self, error: std::io::Error) -> GraphQLError {
+        GraphQLError::Other(format!("Cassette {}: {}", self.path.display(), error))
+    }
+}
+
+#[async_trait]
+impl Middleware for Cassette {
+    async fn handle(&self, request: Request, next: Next<'_>) -> Result<Response, GraphQLError> {
+        let recorded = match received(&request) {
+            Ok(operation) => Recorded {
+                query: operation.query,
+                variables: operation.variables,
+                operation_name: operation.operation_name,
+                body: None,
+            },
+            Err(_) => match request.body().and_then(|body| body.as_bytes()) {
+                Some(body) => Recorded {
+                    query: String::new(),
+                    variables: Value::Null,
+                    operation_name: None,
+                    body: Some(String::from_utf8_lossy(body).into_owned()),
+                },
+                // Streamed bodies, such as uploads, can't be keyed and are always sent
+                None => return next.run(request).await,
+            },
+        };
+        let key = key(&recorded);
+
+        if self.mode != CassetteMode::Record {
+            let replayed = self
+                .interactions()?
+                .iter()
+                .flatten()
+                .find_map(|interaction| {
+                    (interaction.key == key).then(|| interaction.response.clone())
+                });
+            match replayed {
+                Some(response) => return Ok(response.into_response()),
+                None if self.mode == CassetteMode::Replay => {
+                    return Err(GraphQLError::Other(format!(
+                        "No interaction recorded in {} for query `{}`",
+                        self.path.display(),
+                        match &recorded.body {
+                            Some(body) => body.clone(),
+                            None => normalize(&recorded.query),
+                        }
+                    )))
+                }
+                None => {}
+            }
+        }
+
+        let response = next.run(request).await?;
+        let status = response.status().as_u16();
+        // The body is stored decoded, its framing headers wouldn't match on replay
+        let headers = response
+            .headers()
+            .iter()
+            .filter(|(name, _)| {
+                ![CONTENT_LENGTH, CONTENT_ENCODING, TRANSFER_ENCODING].contains(name)
+            })
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        let body = response.text().await?;
+
+        let response = RecordedResponse {
+            status,
+            headers,
+            body,
+        };
+        self.record(Interaction {
+            key,
+            request: recorded,
+            response: response.clone(),
+        })?;
+        Ok(response.into_response())
+    }
+}
+
+impl RecordedResponse {
+    fn into_response(self) -> Response {
+        let mut response = http::Response::new(self.body);
+        *response.status_mut() = StatusCode::from_u16(self.status).unwrap_or(StatusCode::OK);
+        for (key, value) in self.headers {
+            if let (Ok(key), Ok(value)) = (HeaderName::try_from(key), HeaderValue::try_from(value))
+            {
+                response.headers_mut().append(key, value);
+            }
+        }
+        Response::from(response)
+    }
+}
+
+/// Stable hash of the operation, independent of the endpoint and the query formatting
+///
+/// Requests which aren't a single operation are keyed by their body.
+fn key(recorded: &Recorded) -> String {
+    if let Some(body) = &recorded.body {
+        return sha256(&[body.as_bytes()]);
+    }
+    sha256(&[
+        normalize(&recorded.query).as_bytes(),
+        b"\n",
+        recorded.variables.to_string().as_bytes(),
+        b"\n",
+        recorded
+            .operation_name
+            .as_deref()
+            .unwrap_or_default()
+            .as_bytes(),
+    ])
+}
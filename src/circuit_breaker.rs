@@ -0,0 +1,127 @@
+use crate::error::GraphQLError;
+use reqwest::Response;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Stops sending requests to an endpoint which keeps failing
+///
+/// After `failure_threshold` consecutive failures the circuit opens and requests fail
+/// immediately with `GraphQLError::CircuitOpen`. Once `cool_down` has elapsed a single probe
+/// request is let through, closing the circuit if it succeeds and reopening it otherwise.
+/// Network errors, timeouts and 5xx responses count as failures, each retry attempt included.
+#[derive(Clone, Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cool_down: Duration,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            cool_down: Duration::from_secs(30),
+        }
+    }
+
+    /// How long the circuit stays open before probing the endpoint again
+    pub fn cool_down(mut self, cool_down: Duration) -> Self {
+        self.cool_down = cool_down;
+        self
+    }
+}
+
+enum State {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    HalfOpen { probing: bool },
+}
+
+/// Shared state of a [`CircuitBreaker`]
+pub(crate) struct Breaker {
+    config: CircuitBreaker,
+    state: Mutex<State>,
+}
+
+impl Breaker {
+    pub(crate) fn new(config: CircuitBreaker) -> Self {
+        Self {
+            config,
+            state: Mutex::new(State::Closed { failures: 0 }),
+        }
+    }
+
+    /// Admits a request, or fails fast while the circuit is open
+    pub(crate) fn acquire(&self) -> Result<Permit<'_>, GraphQLError> {
+        let mut state = self.state.lock().unwrap();
+        let probe = match *state {
+            State::Closed { .. } => false,
+            State::Open { until } if Instant::now() < until => {
+                return Err(GraphQLError::CircuitOpen)
+            }
+            State::HalfOpen { probing: true } => return Err(GraphQLError::CircuitOpen),
+            State::Open { .. } | State::HalfOpen { probing: false } => {
+                *state = State::HalfOpen { probing: true };
+                true
+            }
+        };
+
+        Ok(Permit {
+            breaker: self,
+            probe,
+            recorded: false,
+        })
+    }
+
+    fn record(&self, success: bool) {
+        let mut state = self.state.lock().unwrap();
+        if success {
+            *state = State::Closed { failures: 0 };
+            return;
+        }
+
+        let open = State::Open {
+            until: Instant::now() + self.config.cool_down,
+        };
+        match &mut *state {
+            State::Closed { failures } => {
+                *failures += 1;
+                if *failures >= self.config.failure_threshold {
+                    *state = open;
+                }
+            }
+            State::HalfOpen { .. } => *state = open,
+            State::Open { .. } => {}
+        }
+    }
+}
+
+/// Admission of a single request, which reports its outcome to the breaker
+pub(crate) struct Permit<'a> {
+    breaker: &'a Breaker,
+    probe: bool,
+    recorded: bool,
+}
+
+impl Permit<'_> {
+    pub(crate) fn record(mut self, result: &Result<Response, GraphQLError>) {
+        let success = match result {
+            Ok(response) => !response.status().is_server_error(),
+            Err(
+                GraphQLError::Network(_) | GraphQLError::Timeout | GraphQLError::ConnectTimeout,
+            ) => false,
+            // Failures of the client side, such as a middleware, say nothing about the endpoint
+            Err(_) => return,
+        };
+        self.breaker.record(success);
+        self.recorded = true;
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        // A cancelled or inconclusive probe lets the next request probe instead
+        if self.probe && !self.recorded {
+            *self.breaker.state.lock().unwrap() = State::HalfOpen { probing: false };
+        }
+    }
+}
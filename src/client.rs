@@ -1,58 +1,359 @@
+use crate::builder::GQLClientBuilder;
+use crate::cache::{ETags, ResponseCache};
+use crate::circuit_breaker::Breaker;
+use crate::dedup::InFlight;
+use crate::document::{minify, operation_type, operations, OperationType};
 use crate::error::{GraphQLError, GraphQLErrorMessage};
+use crate::hash::request_key;
+use crate::idempotency::IDEMPOTENCY_KEY_HEADER;
+use crate::incremental::{is_incremental, merge_body, Decoder, ACCEPT_INCREMENTAL};
+use crate::metrics::{self, MetricsSink};
+use crate::middleware::{Middleware, Next};
+use crate::over_http::{self, ErrorDecoder, ACCEPT_GRAPHQL_RESPONSE};
+use crate::persisted::PersistedQueries;
+use crate::rate_limit::RateLimiter;
+use crate::request::GQLRequestBuilder;
+use crate::request_id::{self, RequestIdGenerator, REQUEST_ID_HEADER};
+use crate::response::GQLResponse;
+use crate::retry::RetryPolicy;
+use crate::shutdown::Lifecycle;
+use crate::size_limit::limit_size;
+#[cfg(feature = "subscription")]
+use crate::subscription::{ConnectionInitProvider, Multiplexer, SubscriptionProtocol};
+use crate::transport::Transport;
+use futures_util::future::{select, Either};
 use reqwest::{
-    header::{HeaderMap, HeaderName, HeaderValue},
-    Client,
+    header::{HeaderMap, HeaderName, HeaderValue, ACCEPT, CONTENT_LENGTH, CONTENT_TYPE},
+    Client, Request, RequestBuilder, Response, StatusCode,
 };
 use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use serde_json::Value;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
 
-pub struct GQLClient<'a> {
-    endpoint: &'a str,
-    header_map: HeaderMap,
+/// GraphQL client, `Send + Sync + 'static` so it can be kept in application state
+///
+/// Clones are cheap and share the connection pool, middlewares, cache and rate limit.
+#[derive(Clone)]
+pub struct GQLClient {
+    pub(crate) endpoint: String,
+    pub(crate) header_map: HeaderMap,
+    pub(crate) client: Client,
+    pub(crate) retry_policy: Option<RetryPolicy>,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) hedge_after: Option<Duration>,
+    pub(crate) max_response_size: Option<u64>,
+    pub(crate) get_queries: bool,
+    pub(crate) graphql_over_http: bool,
+    pub(crate) graphql_body: bool,
+    pub(crate) minify_queries: bool,
+    pub(crate) persisted_queries: Option<Arc<PersistedQueries>>,
+    pub(crate) automatic_persisted_queries: bool,
+    pub(crate) body_hook: Option<BodyHook>,
+    pub(crate) response_hook: Option<BodyHook>,
+    pub(crate) error_decoder: Option<ErrorDecoder>,
+    pub(crate) request_id_header: HeaderName,
+    /// Generates an ID for the requests which weren't given one
+    pub(crate) request_id_generator: Option<RequestIdGenerator>,
+    pub(crate) idempotency_key_header: HeaderName,
+    pub(crate) middlewares: Vec<Arc<dyn Middleware>>,
+    pub(crate) metrics: Option<Arc<dyn MetricsSink>>,
+    pub(crate) transport: Arc<dyn Transport>,
+    pub(crate) cache: Option<ResponseCache>,
+    pub(crate) etags: Option<Arc<ETags>>,
+    pub(crate) rate_limiter: Option<Arc<RateLimiter>>,
+    pub(crate) breaker: Option<Arc<Breaker>>,
+    pub(crate) in_flight: Option<Arc<InFlight>>,
+    pub(crate) lifecycle: Arc<Lifecycle>,
+    /// Requests are admitted by their caller, as loaders do for their queued batches
+    pub(crate) admitted: bool,
+    #[cfg(feature = "validation")]
+    pub(crate) validate_queries: bool,
+    #[cfg(feature = "subscription")]
+    pub(crate) subscription_protocol: SubscriptionProtocol,
+    /// Payload of the `connection_init` message opening subscriptions
+    #[cfg(feature = "subscription")]
+    pub(crate) connection_init: Option<Arc<dyn ConnectionInitProvider>>,
+    #[cfg(feature = "subscription")]
+    pub(crate) subscription_reconnect: Option<RetryPolicy>,
+    #[cfg(feature = "subscription")]
+    pub(crate) subscription_keep_alive: Option<Duration>,
+    /// WebSocket shared by the subscriptions of the client and its clones
+    #[cfg(feature = "subscription")]
+    pub(crate) multiplexer: Arc<Multiplexer>,
 }
 
+/// Hook modifying a JSON body, the envelope of an operation before it is sent or a response
+/// before it is read
+pub(crate) type BodyHook = Arc<dyn Fn(&mut Value) + Send + Sync>;
+
 #[derive(Serialize)]
-struct RequestBody<'a, T: Serialize> {
-    query: &'a str,
-    variables: T,
+pub(crate) struct RequestBody<'a, T: Serialize> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) query: Option<Cow<'a, str>>,
+    pub(crate) variables: T,
+    #[serde(rename = "operationName", skip_serializing_if = "Option::is_none")]
+    pub(crate) operation_name: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) extensions: Option<Value>,
 }
 
 #[derive(Deserialize, Debug)]
-#[serde(untagged)]
-enum GraphQLResponse<T> {
-    ConventionResponse {
-        data: Option<T>,
-        errors: Option<Vec<GraphQLErrorMessage>>,
-    },
-    UnconventionalResponse(serde_json::Value),
+pub(crate) struct GraphQLResponse<T> {
+    pub(crate) data: Option<T>,
+    pub(crate) errors: Option<Vec<GraphQLErrorMessage>>,
+    pub(crate) extensions: Option<Value>,
 }
 
-impl<'a> GQLClient<'a> {
-    pub fn new(endpoint: &'a str) -> Self {
-        Self {
-            endpoint,
-            header_map: HeaderMap::new(),
+/// Settings applied while executing a single request
+pub(crate) struct RequestOptions<'r> {
+    pub(crate) query: &'r str,
+    #[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+    pub(crate) operation_name: Option<&'r str>,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) retry_policy: Option<&'r RetryPolicy>,
+    /// Whether the response may be served from and stored in the cache
+    pub(crate) cacheable: bool,
+    /// ID given to the request by the caller
+    pub(crate) request_id: Option<&'r str>,
+}
+
+impl RequestOptions<'_> {
+    /// Explicit operation name, or the name of the first operation in the document
+    pub(crate) fn name(&self) -> Option<String> {
+        match self.operation_name {
+            Some(operation_name) => Some(operation_name.to_string()),
+            None => operations(self.query)
+                .into_iter()
+                .next()
+                .and_then(|operation| operation.name),
+        }
+    }
+}
+
+impl<T> GraphQLResponse<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    pub(crate) fn from_body(raw_body: String) -> Result<Self, GraphQLError> {
+        let mut deserializer = serde_json::Deserializer::from_str(&raw_body);
+
+        match serde_path_to_error::deserialize::<_, Self>(&mut deserializer) {
+            Ok(response) if response.data.is_some() || response.errors.is_some() => Ok(response),
+            // Valid JSON which doesn't follow the spec is reported as is
+            result => match serde_json::from_str::<Value>(&raw_body) {
+                Ok(value) if !is_conventional(&value) => Err(unconventional(value)),
+                _ => result.map_err(|error| GraphQLError::deserialize(error, raw_body)),
+            },
+        }
+    }
+
+    pub(crate) fn from_value(value: Value) -> Result<Self, GraphQLError> {
+        if !is_conventional(&value) {
+            return Err(unconventional(value));
+        }
+
+        serde_path_to_error::deserialize(&value)
+            .map_err(|error| GraphQLError::deserialize(error, value.to_string()))
+    }
+}
+
+impl<T> GraphQLResponse<T> {
+    pub(crate) fn into_result(self) -> Result<T, GraphQLError> {
+        match (self.data, self.errors) {
+            (_, Some(errors)) => Err(GraphQLError::from_json(errors)),
+            (Some(data), None) => Ok(data),
+            (None, None) => Err(GraphQLError::Other(
+                "Response contains neither data nor errors".into(),
+            )),
         }
     }
 
-    pub fn new_with_headers(endpoint: &'a str, headers: HashMap<&str, &str>) -> Self {
-        let mut header_map = HeaderMap::new();
+    pub(crate) fn into_partial(
+        self,
+    ) -> Result<(Option<T>, Vec<GraphQLErrorMessage>), GraphQLError> {
+        Ok((self.data, self.errors.unwrap_or_default()))
+    }
+}
 
-        for (str_key, str_value) in headers {
-            let key = HeaderName::from_str(str_key).unwrap();
-            let val = HeaderValue::from_str(str_value).unwrap();
+/// Whether the variables serialize to anything but null or an empty object
+fn has_variables<T: Serialize>(body: &RequestBody<T>) -> Result<bool, GraphQLError> {
+    let variables = serde_json::to_value(&body.variables)
+        .map_err(|_| GraphQLError::Other("Failed to serialize variables".into()))?;
 
-            header_map.insert(key, val);
+    Ok(match variables {
+        Value::Null => false,
+        Value::Object(variables) => !variables.is_empty(),
+        _ => true,
+    })
+}
+
+/// URL parameters of an operation sent as GET, values other than strings are JSON encoded
+fn query_params(body: Value) -> Vec<(String, String)> {
+    let mut fields = match body {
+        Value::Object(fields) => fields,
+        _ => return Vec::new(),
+    };
+
+    // The standard parameters come first, in the order of the GraphQL-over-HTTP spec
+    let mut params = Vec::new();
+    for name in ["query", "variables", "operationName", "extensions"] {
+        if let Some(value) = fields.remove(name) {
+            params.push((name.to_string(), value));
         }
+    }
+    params.extend(fields);
+
+    params
+        .into_iter()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(name, value)| match value {
+            Value::String(value) => (name, value),
+            value => (name, value.to_string()),
+        })
+        .collect()
+}
+
+fn is_conventional(value: &Value) -> bool {
+    value
+        .as_object()
+        .is_some_and(|object| object.contains_key("data") || object.contains_key("errors"))
+}
+
+fn unconventional(value: Value) -> GraphQLError {
+    GraphQLError::from_json(vec![GraphQLErrorMessage::UnconventionalError(value)])
+}
+
+/// `User-Agent` sent unless another one is configured, some APIs reject requests without one
+pub(crate) const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// Header map of the headers, failing with the names of the invalid ones if any
+fn header_map(headers: HashMap<&str, &str>) -> Result<HeaderMap, GraphQLError> {
+    let mut header_map = HeaderMap::new();
+    let mut invalid = Vec::new();
+
+    for (str_key, str_value) in headers {
+        match (
+            HeaderName::from_str(str_key),
+            HeaderValue::from_str(str_value),
+        ) {
+            (Ok(key), Ok(val)) => {
+                header_map.insert(key, val);
+            }
+            _ => invalid.push(str_key),
+        }
+    }
+
+    if !invalid.is_empty() {
+        invalid.sort_unstable();
+        return Err(GraphQLError::Other(format!(
+            "Invalid header: {}",
+            invalid.join(", ")
+        )));
+    }
+    Ok(header_map)
+}
+
+/// reqwest client sending the default `User-Agent`
+fn http_client() -> Client {
+    // Like `Client::new`, only fails when the TLS backend can't be initialized
+    Client::builder()
+        .user_agent(USER_AGENT)
+        .build()
+        .expect("TLS backend cannot be initialized")
+}
+
+impl GQLClient {
+    pub fn new(endpoint: &str) -> Self {
+        Self::from_parts(endpoint, HeaderMap::new(), http_client())
+    }
+
+    #[deprecated(note = "panics on invalid headers, use try_new_with_headers instead")]
+    pub fn new_with_headers(endpoint: &str, headers: HashMap<&str, &str>) -> Self {
+        Self::try_new_with_headers(endpoint, headers).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Client sending the headers with every request, failing with the names of the invalid
+    /// headers if any
+    pub fn try_new_with_headers(
+        endpoint: &str,
+        headers: HashMap<&str, &str>,
+    ) -> Result<Self, GraphQLError> {
+        let header_map = header_map(headers)?;
+        Ok(Self::from_parts(endpoint, header_map, http_client()))
+    }
+
+    /// Client sending its requests through the reqwest client, which keeps its own
+    /// `User-Agent` instead of the default one
+    pub fn with_client(endpoint: &str, client: Client) -> Self {
+        Self::from_parts(endpoint, HeaderMap::new(), client)
+    }
+
+    pub fn builder() -> GQLClientBuilder {
+        GQLClientBuilder::new()
+    }
+
+    /// Starts a single request which can override the client-wide defaults
+    pub fn request<'a>(&'a self, query: &'a str) -> GQLRequestBuilder<'a> {
+        GQLRequestBuilder::new(self, query)
+    }
 
+    pub(crate) fn from_parts(endpoint: &str, header_map: HeaderMap, client: Client) -> Self {
         Self {
-            endpoint,
+            endpoint: endpoint.to_string(),
             header_map,
+            transport: Arc::new(client.clone()),
+            client,
+            retry_policy: None,
+            timeout: None,
+            hedge_after: None,
+            max_response_size: None,
+            get_queries: false,
+            graphql_over_http: false,
+            graphql_body: false,
+            minify_queries: false,
+            persisted_queries: None,
+            automatic_persisted_queries: false,
+            body_hook: None,
+            response_hook: None,
+            error_decoder: None,
+            request_id_header: HeaderName::from_static(REQUEST_ID_HEADER),
+            request_id_generator: None,
+            idempotency_key_header: HeaderName::from_static(IDEMPOTENCY_KEY_HEADER),
+            middlewares: Vec::new(),
+            metrics: None,
+            cache: None,
+            etags: None,
+            rate_limiter: None,
+            breaker: None,
+            in_flight: None,
+            lifecycle: Arc::default(),
+            admitted: false,
+            #[cfg(feature = "validation")]
+            validate_queries: false,
+            #[cfg(feature = "subscription")]
+            subscription_protocol: SubscriptionProtocol::default(),
+            #[cfg(feature = "subscription")]
+            connection_init: None,
+            #[cfg(feature = "subscription")]
+            subscription_reconnect: None,
+            #[cfg(feature = "subscription")]
+            subscription_keep_alive: None,
+            #[cfg(feature = "subscription")]
+            multiplexer: Arc::default(),
         }
     }
 
-    pub async fn query<K>(&self, query: &'a str) -> Result<K, GraphQLError>
+    /// Response cache configured on the builder
+    pub fn cache(&self) -> Option<&ResponseCache> {
+        self.cache.as_ref()
+    }
+
+    pub async fn query<K>(&self, query: &str) -> Result<K, GraphQLError>
     where
         K: for<'de> Deserialize<'de>,
     {
@@ -61,35 +362,606 @@ impl<'a> GQLClient<'a> {
 
     pub async fn query_with_vars<K, T: Serialize>(
         &self,
-        query: &'a str,
+        query: &str,
         variables: T,
     ) -> Result<K, GraphQLError>
     where
         K: for<'de> Deserialize<'de>,
     {
-        let client = Client::new();
-        let body = RequestBody { query, variables };
-
-        let request = client
-            .post(self.endpoint)
-            .json(&body)
-            .headers(self.header_map.clone());
-
-        let raw_response = request.send().await?;
-        let json_response = raw_response.json::<GraphQLResponse<K>>().await;
-
-        // Check whether JSON is parsed successfully
-        match json_response {
-            Ok(GraphQLResponse::ConventionResponse { data, errors: None }) => Ok(data.unwrap()),
-            Ok(GraphQLResponse::ConventionResponse {
-                errors: Some(errors),
-                ..
-            }) => Err(GraphQLError::from_json(errors)),
-            Ok(GraphQLResponse::UnconventionalResponse(value)) => Err(GraphQLError {
-                message: "Couldn't parse the result.".into(),
-                json: Some(vec![GraphQLErrorMessage::UnconventionalError(value)]),
-            }),
-            Err(_e) => Err(GraphQLError::from_str("Failed to parse response").unwrap()),
+        self.run(query, None, variables)
+            .await
+            .map(GQLResponse::into_data)
+    }
+
+    /// Like `query_with_vars`, for variables which are already a JSON value
+    ///
+    /// Fails without sending the request unless the value is an object.
+    pub async fn query_with_json_vars<K>(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> Result<K, GraphQLError>
+    where
+        K: for<'de> Deserialize<'de>,
+    {
+        if !variables.is_object() {
+            return Err(GraphQLError::Other(format!(
+                "variables must be a JSON object, got {}",
+                variables
+            )));
         }
+        self.query_with_vars(query, variables).await
+    }
+
+    /// Like `query_with_vars`, but also returns the HTTP status and response headers
+    pub async fn query_with_meta<K, T: Serialize>(
+        &self,
+        query: &str,
+        variables: T,
+    ) -> Result<GQLResponse<K>, GraphQLError>
+    where
+        K: for<'de> Deserialize<'de>,
+    {
+        self.run(query, None, variables).await
+    }
+
+    /// Executes the named operation of a document containing several operations
+    pub async fn query_with_operation<K, T: Serialize>(
+        &self,
+        query: &str,
+        operation_name: &str,
+        variables: T,
+    ) -> Result<K, GraphQLError>
+    where
+        K: for<'de> Deserialize<'de>,
+    {
+        self.run(query, Some(operation_name), variables)
+            .await
+            .map(GQLResponse::into_data)
+    }
+
+    /// Executes a mutation, which is never served from the cache, deduplicated, hedged or retried
+    ///
+    /// A mutation failing midway may still have been applied, sending it again could apply it
+    /// twice. `mutate_idempotent` sends it with an idempotency key, which makes retrying safe.
+    pub async fn mutate<K, T: Serialize>(
+        &self,
+        mutation: &str,
+        variables: T,
+    ) -> Result<K, GraphQLError>
+    where
+        K: for<'de> Deserialize<'de>,
+    {
+        let request = self.request_for(mutation, None, variables)?;
+        let raw_response = self
+            .execute_request(
+                request,
+                &RequestOptions {
+                    retry_policy: None,
+                    cacheable: false,
+                    ..self.options(mutation, None)
+                },
+            )
+            .await?;
+
+        parse_response_with_meta(raw_response)
+            .await
+            .map(GQLResponse::into_data)
+    }
+
+    /// Like `query_with_vars`, also sending the headers with this request only
+    ///
+    /// They are added on top of the client headers, replacing those with the same name, e.g. for
+    /// a per-user token or tenant. Fails without sending the request if a header is invalid.
+    /// Responses are only cached and deduplicated with calls sending the same headers.
+    pub async fn query_with_vars_and_headers<K, T: Serialize>(
+        &self,
+        query: &str,
+        variables: T,
+        headers: HashMap<&str, &str>,
+    ) -> Result<K, GraphQLError>
+    where
+        K: for<'de> Deserialize<'de>,
+    {
+        let request = self
+            .request_for(query, None, variables)?
+            .headers(header_map(headers)?);
+        let raw_response = self
+            .execute_request(request, &self.options(query, None))
+            .await?;
+
+        parse_response_with_meta(raw_response)
+            .await
+            .map(GQLResponse::into_data)
+    }
+
+    /// Returns the data even when the response also contains errors
+    ///
+    /// The GraphQL spec allows partial results, where `data` holds every field that
+    /// could be resolved and `errors` describes the rest.
+    pub async fn query_partial<K, T: Serialize>(
+        &self,
+        query: &str,
+        variables: T,
+    ) -> Result<(Option<K>, Vec<GraphQLErrorMessage>), GraphQLError>
+    where
+        K: for<'de> Deserialize<'de>,
+    {
+        let request = self.request_for(query, None, variables)?;
+        let raw_response = self
+            .execute_request(request, &self.options(query, None))
+            .await?;
+
+        read_response::<K>(raw_response).await?.into_partial()
     }
+
+    /// Returns the whole response document without deserializing it into a type
+    ///
+    /// GraphQL errors are left in the returned `errors` field instead of failing the call,
+    /// which suits proxies and explorers passing responses on as received.
+    pub async fn query_raw<T: Serialize>(
+        &self,
+        query: &str,
+        variables: T,
+    ) -> Result<Value, GraphQLError> {
+        let request = self.request_for(query, None, variables)?;
+        let mut raw_response = self
+            .execute_request(request, &self.options(query, None))
+            .await?;
+
+        let measured = metrics::take(&mut raw_response);
+        let response = metrics::count(read_json::<Value>(raw_response).await, measured.as_ref())?;
+        if let Some(measured) = measured {
+            let errors = response.get("errors").and_then(Value::as_array);
+            measured.graphql_errors(errors.map(Vec::as_slice));
+        }
+        Ok(response)
+    }
+
+    /// Returns the `data` field exactly as the server sent it
+    ///
+    /// The data is only checked to be valid JSON and copied out of the body, without building
+    /// a value tree or typed structure, for gateways forwarding it unchanged.
+    pub async fn query_raw_data<T: Serialize>(
+        &self,
+        query: &str,
+        variables: T,
+    ) -> Result<Box<RawValue>, GraphQLError> {
+        self.query_with_vars(query, variables).await
+    }
+
+    async fn run<K, T: Serialize>(
+        &self,
+        query: &str,
+        operation_name: Option<&str>,
+        variables: T,
+    ) -> Result<GQLResponse<K>, GraphQLError>
+    where
+        K: for<'de> Deserialize<'de>,
+    {
+        let request = self.request_for(query, operation_name, variables)?;
+        let raw_response = self
+            .execute_request(request, &self.options(query, operation_name))
+            .await?;
+        parse_response_with_meta(raw_response).await
+    }
+
+    /// Builds the HTTP request for an operation, queries are sent as GET when enabled
+    pub(crate) fn request_for<T: Serialize>(
+        &self,
+        query: &str,
+        operation_name: Option<&str>,
+        variables: T,
+    ) -> Result<RequestBuilder, GraphQLError> {
+        #[cfg(feature = "validation")]
+        self.check_syntax(query)?;
+
+        let mut body = self.request_body(query, operation_name, variables)?;
+        self.hash_document(&mut body);
+
+        let request =
+            if self.get_queries && operation_type(query, operation_name) == OperationType::Query {
+                let body = self.envelope(&body)?;
+                self.client.get(&self.endpoint).query(&query_params(body))
+            } else if self.graphql_body
+                && body.query.is_some()
+                && body.operation_name.is_none()
+                && !has_variables(&body)?
+            {
+                self.client
+                    .post(&self.endpoint)
+                    .header(CONTENT_TYPE, "application/graphql")
+                    .body(body.query.unwrap_or_default().into_owned())
+            } else if self.body_hook.is_some() {
+                self.client
+                    .post(&self.endpoint)
+                    .json(&self.envelope(&body)?)
+            } else {
+                self.client.post(&self.endpoint).json(&body)
+            };
+
+        // Without it servers answer `@defer` and `@stream` queries in a single response
+        let request = if is_incremental(query) {
+            request.header(ACCEPT, ACCEPT_INCREMENTAL)
+        } else {
+            request
+        };
+
+        Ok(request.headers(self.header_map.clone()))
+    }
+
+    /// Document as sent, minified when enabled
+    pub(crate) fn document<'q>(&self, query: &'q str) -> Cow<'q, str> {
+        if self.minify_queries {
+            Cow::Owned(minify(query))
+        } else {
+            Cow::Borrowed(query)
+        }
+    }
+
+    /// JSON envelope of an operation, as modified by the body hook
+    pub(crate) fn envelope<B: Serialize>(&self, body: &B) -> Result<Value, GraphQLError> {
+        let mut body = serde_json::to_value(body)
+            .map_err(|_| GraphQLError::Other("Failed to serialize variables".into()))?;
+        if let Some(hook) = &self.body_hook {
+            hook(&mut body);
+        }
+        Ok(body)
+    }
+
+    /// Client-wide request options
+    pub(crate) fn options<'r>(
+        &'r self,
+        query: &'r str,
+        operation_name: Option<&'r str>,
+    ) -> RequestOptions<'r> {
+        RequestOptions {
+            query,
+            operation_name,
+            timeout: self.timeout,
+            retry_policy: self.retry_policy.as_ref(),
+            cacheable: operation_type(query, operation_name) == OperationType::Query,
+            request_id: None,
+        }
+    }
+
+    /// Sends the request and records it in a `gql.query` span when tracing is enabled
+    pub(crate) async fn execute_request(
+        &self,
+        request: RequestBuilder,
+        options: &RequestOptions<'_>,
+    ) -> Result<Response, GraphQLError> {
+        let work = match self.admitted {
+            true => None,
+            false => Some(self.lifecycle.enter()?),
+        };
+        let start = std::time::Instant::now();
+        // The same ID is sent with every attempt of the request
+        let request_id = match options.request_id {
+            Some(request_id) => Some(request_id.to_string()),
+            None => self
+                .request_id_generator
+                .as_ref()
+                .map(|generate| generate()),
+        };
+
+        #[cfg(feature = "tracing")]
+        let result = {
+            use tracing::Instrument;
+
+            let span = tracing::info_span!(
+                "gql.query",
+                operation_name = options.name().as_deref(),
+                endpoint = self.endpoint,
+                request_id = request_id.as_deref(),
+                duration_ms = tracing::field::Empty,
+                status = tracing::field::Empty,
+                error = tracing::field::Empty,
+            );
+            let result = self
+                .execute_with_retries(request, options, request_id.as_deref())
+                .instrument(span.clone())
+                .await;
+
+            span.record("duration_ms", start.elapsed().as_millis() as u64);
+            match &result {
+                Ok(response) => span.record("status", response.status().as_u16()),
+                Err(error) => span.record("error", tracing::field::display(error.message())),
+            };
+
+            result
+        };
+
+        #[cfg(not(feature = "tracing"))]
+        let result = self
+            .execute_with_retries(request, options, request_id.as_deref())
+            .await;
+
+        // The shutdown also waits for the body of the response to be read
+        let result = match work {
+            Some(work) => result.map(|response| work.hold(response)),
+            None => result,
+        };
+        let result = match request_id {
+            Some(request_id) => request_id::correlate(result, &request_id),
+            None => result,
+        };
+        match &self.metrics {
+            Some(sink) => metrics::record(sink, options.name(), start, result),
+            None => result,
+        }
+    }
+
+    /// Sends the request, retrying transient failures according to the retry policy
+    async fn execute_with_retries(
+        &self,
+        mut request: RequestBuilder,
+        options: &RequestOptions<'_>,
+        request_id: Option<&str>,
+    ) -> Result<Response, GraphQLError> {
+        if let Some(timeout) = options.timeout {
+            request = request.timeout(timeout);
+        }
+        let mut request = request.build()?;
+        // Computed before the request ID and trace context differing between calls are added
+        let key = options.cacheable.then(|| request_key(&request)).flatten();
+        let in_flight = self.in_flight.as_ref().zip(key.clone());
+        if let Some(request_id) = request_id {
+            request_id::attach(&mut request, &self.request_id_header, request_id)?;
+        }
+
+        #[cfg(feature = "opentelemetry")]
+        crate::propagation::inject_context(request.headers_mut());
+
+        if self.graphql_over_http {
+            request
+                .headers_mut()
+                .entry(ACCEPT)
+                .or_insert(HeaderValue::from_static(ACCEPT_GRAPHQL_RESPONSE));
+        }
+
+        // Kept to send the document if the server doesn't know the hash of the query yet
+        let hashed = match self.automatic_persisted_queries && self.persisted_queries.is_none() {
+            true => request.try_clone(),
+            false => None,
+        };
+
+        let response = match in_flight {
+            Some((in_flight, in_flight_key)) => {
+                in_flight
+                    .run(in_flight_key, request, |request| {
+                        self.send(request, options, key)
+                    })
+                    .await?
+            }
+            None => self.send(request, options, key).await?,
+        };
+        let response = self.register_document(response, hashed, options).await?;
+        let response = match &self.response_hook {
+            Some(hook) => transform_response(response, hook).await?,
+            None => response,
+        };
+        let response = match &self.error_decoder {
+            Some(decoder) => over_http::decode_error(response, decoder).await?,
+            None => response,
+        };
+
+        if self.graphql_over_http {
+            over_http::interpret(response).await
+        } else {
+            over_http::check_status(response).await
+        }
+    }
+
+    /// Sends the request through the cache when enabled and the request has a cache key
+    pub(crate) async fn send(
+        &self,
+        request: Request,
+        options: &RequestOptions<'_>,
+        key: Option<String>,
+    ) -> Result<Response, GraphQLError> {
+        let cached = self.cache.is_some() || self.etags.is_some();
+        match key.filter(|_| cached) {
+            Some(key) => self.send_cached(key, request, options).await,
+            None => self.send_with_retries(request, options).await,
+        }
+    }
+
+    pub(crate) async fn send_with_retries(
+        &self,
+        mut request: Request,
+        options: &RequestOptions<'_>,
+    ) -> Result<Response, GraphQLError> {
+        let policy = match options.retry_policy {
+            Some(policy) => policy,
+            None => return self.hedged(request, options).await,
+        };
+
+        let mut attempt = 1;
+        loop {
+            // Streaming bodies can't be cloned, those requests are sent only once
+            let retry = request
+                .try_clone()
+                .filter(|_| attempt < policy.max_attempts());
+            let result = self.hedged(request, options).await;
+            let (result, retry) = match retry {
+                Some(retry) => match policy.check(result).await {
+                    (result, true) => (result, Some(retry)),
+                    (result, false) => (result, None),
+                },
+                None => (result, None),
+            };
+
+            match retry {
+                Some(retry) => {
+                    let delay = policy.delay(attempt, &result);
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(
+                        attempt,
+                        delay_ms = delay.as_millis() as u64,
+                        "retrying GraphQL request"
+                    );
+
+                    tokio::time::sleep(delay).await;
+                    request = retry;
+                    attempt += 1;
+                }
+                None => return result,
+            }
+        }
+    }
+
+    /// Sends a duplicate of slow queries, answering with whichever response arrives first
+    ///
+    /// The other request is cancelled, unless the first one to complete failed.
+    async fn hedged(
+        &self,
+        request: Request,
+        options: &RequestOptions<'_>,
+    ) -> Result<Response, GraphQLError> {
+        // Only queries can be sent twice safely
+        let hedge = match self.hedge_after {
+            Some(hedge_after) if options.cacheable => {
+                request.try_clone().map(|hedge| (hedge_after, hedge))
+            }
+            _ => None,
+        };
+        let (hedge_after, hedge) = match hedge {
+            Some(hedge) => hedge,
+            None => return self.dispatch(request).await,
+        };
+
+        let mut first = Box::pin(self.dispatch(request));
+        let delay = Box::pin(tokio::time::sleep(hedge_after));
+        if let Either::Left((result, _)) = select(&mut first, delay).await {
+            return result;
+        }
+
+        let second = Box::pin(self.dispatch(hedge));
+        match select(first, second).await {
+            Either::Left((Ok(response), _)) | Either::Right((Ok(response), _)) => Ok(response),
+            Either::Left((Err(_), other)) | Either::Right((Err(_), other)) => other.await,
+        }
+    }
+
+    /// Sends the request through the middleware chain
+    async fn dispatch(&self, request: Request) -> Result<Response, GraphQLError> {
+        let permit = match &self.breaker {
+            Some(breaker) => Some(breaker.acquire()?),
+            None => None,
+        };
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire().await;
+        }
+
+        let result = Next::new(&*self.transport, &self.middlewares)
+            .run(request)
+            .await;
+        if let Some(permit) = permit {
+            permit.record(&result);
+        }
+        match self.max_response_size {
+            Some(limit) => result.and_then(|response| limit_size(response, limit)),
+            None => result,
+        }
+    }
+}
+
+pub(crate) async fn parse_response_with_meta<K>(
+    raw_response: Response,
+) -> Result<GQLResponse<K>, GraphQLError>
+where
+    K: for<'de> Deserialize<'de>,
+{
+    let status = raw_response.status();
+    let headers = raw_response.headers().clone();
+    let request_id = request_id::of(&raw_response);
+    let mut response = read_response::<K>(raw_response).await?;
+    let extensions = response.extensions.take();
+    let data = request_id::tag(response.into_result(), request_id)?;
+
+    Ok(GQLResponse {
+        data,
+        status,
+        headers,
+        extensions,
+    })
+}
+
+pub(crate) async fn read_response<K>(
+    mut raw_response: Response,
+) -> Result<GraphQLResponse<K>, GraphQLError>
+where
+    K: for<'de> Deserialize<'de>,
+{
+    let request_id = request_id::of(&raw_response);
+    let measured = metrics::take(&mut raw_response);
+    let response = async {
+        match Decoder::for_headers(raw_response.headers()) {
+            Some(decoder) => merge_body(decoder, &raw_response.bytes().await?),
+            None => GraphQLResponse::from_body(raw_response.text().await?),
+        }
+    };
+    let response = metrics::count(response.await, measured.as_ref());
+    if let (Ok(response), Some(measured)) = (&response, &measured) {
+        measured.graphql_errors(response.errors.as_deref());
+    }
+    request_id::tag(response, request_id)
+}
+
+/// Response reading from an already received body
+pub(crate) fn rebuild_response<B: Into<reqwest::Body>>(
+    status: StatusCode,
+    headers: HeaderMap,
+    body: B,
+) -> Response {
+    let mut response = http::Response::new(body);
+    *response.status_mut() = status;
+    *response.headers_mut() = headers;
+    Response::from(response)
+}
+
+/// Passes the JSON body of the response through the hook, other bodies are left as they are
+async fn transform_response(response: Response, hook: &BodyHook) -> Result<Response, GraphQLError> {
+    let status = response.status();
+    let mut headers = response.headers().clone();
+    let body = response.bytes().await?;
+
+    let body = match serde_json::from_slice::<Value>(&body) {
+        Ok(mut value) => {
+            hook(&mut value);
+            headers.remove(CONTENT_LENGTH);
+            value.to_string().into_bytes()
+        }
+        Err(_) => body.to_vec(),
+    };
+    Ok(rebuild_response(status, headers, body))
+}
+
+/// Reads the whole body, keeping it in the error if it isn't the expected JSON
+pub(crate) async fn read_json<D>(raw_response: Response) -> Result<D, GraphQLError>
+where
+    D: for<'de> Deserialize<'de>,
+{
+    let request_id = request_id::of(&raw_response);
+    let body = raw_response.text().await.map_err(GraphQLError::from);
+    request_id::tag(body.and_then(from_body), request_id)
+}
+
+pub(crate) fn from_body<D>(raw_body: String) -> Result<D, GraphQLError>
+where
+    D: for<'de> Deserialize<'de>,
+{
+    let mut deserializer = serde_json::Deserializer::from_str(&raw_body);
+
+    serde_path_to_error::deserialize(&mut deserializer)
+        .map_err(|error| GraphQLError::deserialize(error, raw_body))
+}
+
+pub(crate) fn from_value<D>(value: Value) -> Result<D, GraphQLError>
+where
+    D: for<'de> Deserialize<'de>,
+{
+    serde_path_to_error::deserialize(&value)
+        .map_err(|error| GraphQLError::deserialize(error, value.to_string()))
 }
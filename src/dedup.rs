@@ -0,0 +1,94 @@
+use crate::client::rebuild_response;
+use crate::error::GraphQLError;
+use reqwest::header::HeaderMap;
+use reqwest::{Request, Response, StatusCode};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+
+/// Response shared with the calls waiting for an identical request
+struct Shared {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Vec<u8>,
+}
+
+impl Shared {
+    fn response(&self) -> Response {
+        rebuild_response(self.status, self.headers.clone(), self.body.clone())
+    }
+}
+
+type Outcome = Option<Arc<Shared>>;
+
+/// Requests being sent, identical queries wait for their response instead of sending another
+#[derive(Default)]
+pub(crate) struct InFlight {
+    pending: Mutex<HashMap<String, watch::Receiver<Outcome>>>,
+}
+
+impl InFlight {
+    /// Sends the request, or waits for the response of the identical one already in flight
+    ///
+    /// When that request fails, the waiting calls send their own so each gets its error.
+    pub(crate) async fn run<F, Fut>(
+        &self,
+        key: String,
+        request: Request,
+        send: F,
+    ) -> Result<Response, GraphQLError>
+    where
+        F: FnOnce(Request) -> Fut,
+        Fut: Future<Output = Result<Response, GraphQLError>>,
+    {
+        let sender = {
+            let mut pending = self.pending.lock().unwrap();
+            match pending.get(&key) {
+                Some(receiver) => Err(receiver.clone()),
+                None => {
+                    let (sender, receiver) = watch::channel(None);
+                    pending.insert(key.clone(), receiver);
+                    Ok(sender)
+                }
+            }
+        };
+
+        let sender = match sender {
+            Ok(sender) => sender,
+            Err(mut receiver) => {
+                if receiver.changed().await.is_ok() {
+                    if let Some(shared) = receiver.borrow().clone() {
+                        return Ok(shared.response());
+                    }
+                }
+                return send(request).await;
+            }
+        };
+
+        // Removes the entry even when this call is cancelled, waiting calls then send their own
+        let _pending = Pending {
+            in_flight: self,
+            key,
+        };
+        let response = send(request).await?;
+        let shared = Arc::new(Shared {
+            status: response.status(),
+            headers: response.headers().clone(),
+            body: response.bytes().await?.to_vec(),
+        });
+        sender.send_replace(Some(shared.clone()));
+        Ok(shared.response())
+    }
+}
+
+struct Pending<'a> {
+    in_flight: &'a InFlight,
+    key: String,
+}
+
+impl Drop for Pending<'_> {
+    fn drop(&mut self) {
+        self.in_flight.pending.lock().unwrap().remove(&self.key);
+    }
+}
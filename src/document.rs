@@ -0,0 +1,208 @@
+/// Kind of operation defined in a GraphQL document
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum OperationType {
+    Query,
+    Mutation,
+    Subscription,
+}
+
+/// Operation definition found in a document
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Operation {
+    pub(crate) kind: OperationType,
+    pub(crate) name: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Punct(char),
+}
+
+/// Lists the operations of a document
+///
+/// This is a lightweight scan of top-level definitions rather than a full parse:
+/// selection sets, fragments, comments and strings are skipped.
+pub(crate) fn operations(document: &str) -> Vec<Operation> {
+    let tokens = tokenize(document);
+    let mut operations = Vec::new();
+    let mut i = 0;
+
+    while i < tokens.len() {
+        match &tokens[i] {
+            // Query shorthand
+            Token::Punct('{') => {
+                operations.push(Operation {
+                    kind: OperationType::Query,
+                    name: None,
+                });
+                i = skip_selection_set(&tokens, i);
+            }
+            Token::Word(word) => {
+                let kind = match word.as_str() {
+                    "query" => Some(OperationType::Query),
+                    "mutation" => Some(OperationType::Mutation),
+                    "subscription" => Some(OperationType::Subscription),
+                    _ => None,
+                };
+                let name = match tokens.get(i + 1) {
+                    Some(Token::Word(name)) => Some(name.clone()),
+                    _ => None,
+                };
+
+                // Variable definitions may contain object default values
+                let mut parens = 0;
+                i += 1;
+                while i < tokens.len() {
+                    match tokens[i] {
+                        Token::Punct('(') => parens += 1,
+                        Token::Punct(')') => parens -= 1,
+                        Token::Punct('{') if parens == 0 => break,
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                if let Some(kind) = kind {
+                    operations.push(Operation { kind, name });
+                }
+                i = skip_selection_set(&tokens, i);
+            }
+            Token::Punct(_) => i += 1,
+        }
+    }
+
+    operations
+}
+
+/// Type of the operation which will be executed, the named one or else the first one
+pub(crate) fn operation_type(document: &str, operation_name: Option<&str>) -> OperationType {
+    let operations = operations(document);
+
+    operations
+        .iter()
+        .find(|operation| operation_name.is_some() && operation.name.as_deref() == operation_name)
+        .or_else(|| operations.first())
+        .map_or(OperationType::Query, |operation| operation.kind)
+}
+
+/// Returns the index after the selection set starting at `start`
+fn skip_selection_set(tokens: &[Token], start: usize) -> usize {
+    let mut depth = 0;
+
+    for (i, token) in tokens.iter().enumerate().skip(start) {
+        match token {
+            Token::Punct('{') => depth += 1,
+            Token::Punct('}') => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    tokens.len()
+}
+
+/// Strips comments and insignificant whitespace and commas from a document
+///
+/// Strings and block strings are kept verbatim, a single space is left only where two names or
+/// numbers would otherwise merge.
+pub(crate) fn minify(document: &str) -> String {
+    let chars: Vec<char> = document.chars().collect();
+    let mut minified = String::with_capacity(document.len());
+    let mut separated = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '#' {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            separated = true;
+        } else if c.is_whitespace() || c == ',' || c == '\u{feff}' {
+            separated = true;
+            i += 1;
+        } else if c == '"' {
+            let end = skip_string(&chars, i);
+            minified.extend(&chars[i..end]);
+            separated = false;
+            i = end;
+        } else {
+            let word = |c: char| c.is_alphanumeric() || c == '_';
+            if separated && minified.ends_with(word) && word(c) {
+                minified.push(' ');
+            }
+            minified.push(c);
+            separated = false;
+            i += 1;
+        }
+    }
+
+    minified
+}
+
+fn tokenize(document: &str) -> Vec<Token> {
+    let chars: Vec<char> = document.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '#' {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '"' {
+            i = skip_string(&chars, i);
+        } else if c.is_alphanumeric() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Word(chars[start..i].iter().collect()));
+        } else {
+            if !c.is_whitespace() && c != ',' {
+                tokens.push(Token::Punct(c));
+            }
+            i += 1;
+        }
+    }
+
+    tokens
+}
+
+/// Returns the index after the string (or block string) starting at `start`
+fn skip_string(chars: &[char], start: usize) -> usize {
+    let block = chars.get(start + 1) == Some(&'"') && chars.get(start + 2) == Some(&'"');
+
+    if block {
+        let mut i = start + 3;
+        while i < chars.len() {
+            if chars[i] == '\\' {
+                i += 2;
+            } else if chars[i..].starts_with(&['"', '"', '"']) {
+                return i + 3;
+            } else {
+                i += 1;
+            }
+        }
+        return chars.len();
+    }
+
+    let mut i = start + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            '"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+
+    chars.len()
+}
@@ -1,17 +1,66 @@
-use reqwest::Error;
+use reqwest::{Error, StatusCode};
 use serde::Deserialize;
 use std::convert::Infallible;
 use std::fmt;
 use std::fmt::Formatter;
 use std::str::FromStr;
 
-pub struct GraphQLError {
-    pub message: String,
-    pub json: Option<Vec<GraphQLErrorMessage>>,
+/// Error returned by every client operation
+#[non_exhaustive]
+pub enum GraphQLError {
+    /// The request couldn't be sent or the response couldn't be read
+    Network(reqwest::Error),
+    /// The server answered with a non-success HTTP status and no GraphQL response
+    ///
+    /// `body` holds what the server returned, e.g. the error page of a proxy, truncated to a
+    /// few kilobytes.
+    Http { status: StatusCode, body: String },
+    /// The server answered with GraphQL errors
+    GraphQL(Vec<GraphQLErrorMessage>),
+    /// The response body couldn't be deserialized
+    ///
+    /// `path` points at the offending value (e.g. `data.user.id`) and `raw_body`
+    /// holds what the server returned, truncated to a few kilobytes.
+    Deserialize {
+        source: serde_json::Error,
+        path: String,
+        raw_body: String,
+    },
+    /// The request didn't complete within the configured timeout
+    Timeout,
+    /// The connection to the server couldn't be established within the configured connect
+    /// timeout, the request was never sent
+    ConnectTimeout,
+    /// The request was cancelled through its cancellation token
+    Cancelled,
+    /// The request wasn't sent because the circuit breaker is open
+    CircuitOpen,
+    /// The request wasn't sent because the client was shut down
+    Shutdown,
+    /// The response was larger than the configured maximum of `limit` bytes, it was abandoned
+    /// instead of being read further
+    ResponseTooLarge { limit: u64 },
+    /// The error of a request sent with a request ID, to correlate it with the server logs
+    ///
+    /// The `is_*` helpers, `json` and `codes` look through it at the error itself.
+    WithRequestId {
+        request_id: String,
+        error: Box<GraphQLError>,
+    },
+    /// The subscription WebSocket failed
+    #[cfg(feature = "subscription")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "subscription")))]
+    WebSocket(Box<tokio_tungstenite::tungstenite::Error>),
+    /// The document was rejected by client-side validation before being sent
+    #[cfg(feature = "validation")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "validation")))]
+    Syntax(crate::validation::SyntaxError),
+    /// Invalid configuration, variables or protocol messages
+    Other(String),
 }
 
 // https://spec.graphql.org/June2018/#sec-Errors
-#[derive(Deserialize, Debug)]
+#[derive(Clone, Deserialize, Debug)]
 #[serde(untagged)]
 pub enum GraphQLErrorMessage {
     ConventionalError {
@@ -30,62 +79,337 @@ impl GraphQLErrorMessage {
             Self::UnconventionalError(value) => value.to_string(),
         }
     }
+
+    /// Code of the error from `extensions.code`, as set by Apollo and most other servers
+    pub fn code(&self) -> Option<ErrorCode> {
+        let extensions = match self {
+            Self::ConventionalError { extensions, .. } => extensions.as_ref()?,
+            Self::UnconventionalError(value) => value.get("extensions")?,
+        };
+        extensions.get("code")?.as_str().map(ErrorCode::from)
+    }
+
+    pub fn has_code<C: Into<ErrorCode>>(&self, code: C) -> bool {
+        self.code() == Some(code.into())
+    }
+}
+
+/// Error codes of the Apollo conventions, other codes are kept as they are
+///
+/// https://www.apollographql.com/docs/apollo-server/data/errors#built-in-error-codes
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    Unauthenticated,
+    Forbidden,
+    BadUserInput,
+    BadRequest,
+    GraphQLParseFailed,
+    GraphQLValidationFailed,
+    PersistedQueryNotFound,
+    PersistedQueryNotSupported,
+    OperationResolutionFailure,
+    InternalServerError,
+    Other(String),
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Unauthenticated => "UNAUTHENTICATED",
+            Self::Forbidden => "FORBIDDEN",
+            Self::BadUserInput => "BAD_USER_INPUT",
+            Self::BadRequest => "BAD_REQUEST",
+            Self::GraphQLParseFailed => "GRAPHQL_PARSE_FAILED",
+            Self::GraphQLValidationFailed => "GRAPHQL_VALIDATION_FAILED",
+            Self::PersistedQueryNotFound => "PERSISTED_QUERY_NOT_FOUND",
+            Self::PersistedQueryNotSupported => "PERSISTED_QUERY_NOT_SUPPORTED",
+            Self::OperationResolutionFailure => "OPERATION_RESOLUTION_FAILURE",
+            Self::InternalServerError => "INTERNAL_SERVER_ERROR",
+            Self::Other(code) => code,
+        }
+    }
+}
+
+impl From<&str> for ErrorCode {
+    fn from(code: &str) -> Self {
+        match code {
+            "UNAUTHENTICATED" => Self::Unauthenticated,
+            "FORBIDDEN" => Self::Forbidden,
+            "BAD_USER_INPUT" => Self::BadUserInput,
+            "BAD_REQUEST" => Self::BadRequest,
+            "GRAPHQL_PARSE_FAILED" => Self::GraphQLParseFailed,
+            "GRAPHQL_VALIDATION_FAILED" => Self::GraphQLValidationFailed,
+            "PERSISTED_QUERY_NOT_FOUND" => Self::PersistedQueryNotFound,
+            "PERSISTED_QUERY_NOT_SUPPORTED" => Self::PersistedQueryNotSupported,
+            "OPERATION_RESOLUTION_FAILURE" => Self::OperationResolutionFailure,
+            "INTERNAL_SERVER_ERROR" => Self::InternalServerError,
+            code => Self::Other(code.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Clone, Deserialize, Debug)]
 pub struct GraphQLErrorLocation {
     pub line: u32,
     pub column: u32,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Clone, Deserialize, Debug)]
 #[serde(untagged)]
 pub enum GraphQLErrorPathParam {
     String(String),
     Number(u32),
 }
 
+/// Keeps bodies stored in errors and logs to a few kilobytes
+pub(crate) fn truncate(mut body: String) -> String {
+    if body.len() > MAX_RAW_BODY_LENGTH {
+        let mut end = MAX_RAW_BODY_LENGTH;
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        body.truncate(end);
+        body.push_str("...");
+    }
+    body
+}
+
 impl FromStr for GraphQLError {
     type Err = Infallible;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self {
-            message: String::from(s),
-            json: None,
-        })
+        Ok(Self::Other(String::from(s)))
+    }
+}
+
+impl std::error::Error for GraphQLError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Network(error) => Some(error),
+            Self::Deserialize { source, .. } => Some(source),
+            Self::WithRequestId { error, .. } => error.source(),
+            #[cfg(feature = "subscription")]
+            Self::WebSocket(error) => Some(error),
+            #[cfg(feature = "validation")]
+            Self::Syntax(error) => Some(error),
+            _ => None,
+        }
     }
 }
 
-impl std::error::Error for GraphQLError {}
+const MAX_RAW_BODY_LENGTH: usize = 4096;
 
 impl GraphQLError {
+    pub(crate) fn deserialize(
+        error: serde_path_to_error::Error<serde_json::Error>,
+        raw_body: String,
+    ) -> Self {
+        Self::Deserialize {
+            path: error.path().to_string(),
+            source: error.into_inner(),
+            raw_body: truncate(raw_body),
+        }
+    }
+
+    pub(crate) fn http(status: StatusCode, body: String) -> Self {
+        Self::Http {
+            status,
+            body: truncate(body),
+        }
+    }
+
+    /// Copy of the error for another caller
+    ///
+    /// Errors of reqwest and tungstenite can't be copied, `Network` and `WebSocket` errors become
+    /// `Other` with their message, so their `kind` is `other`.
+    pub(crate) fn duplicate(&self) -> Self {
+        match self {
+            Self::Http { status, body } => Self::Http {
+                status: *status,
+                body: body.clone(),
+            },
+            Self::GraphQL(errors) => Self::GraphQL(errors.clone()),
+            Self::Deserialize {
+                source,
+                path,
+                raw_body,
+            } => Self::Deserialize {
+                source: serde::de::Error::custom(source),
+                path: path.clone(),
+                raw_body: raw_body.clone(),
+            },
+            Self::Timeout => Self::Timeout,
+            Self::ConnectTimeout => Self::ConnectTimeout,
+            Self::Cancelled => Self::Cancelled,
+            Self::CircuitOpen => Self::CircuitOpen,
+            Self::Shutdown => Self::Shutdown,
+            Self::ResponseTooLarge { limit } => Self::ResponseTooLarge { limit: *limit },
+            Self::WithRequestId { request_id, error } => Self::WithRequestId {
+                request_id: request_id.clone(),
+                error: Box::new(error.duplicate()),
+            },
+            #[cfg(feature = "validation")]
+            Self::Syntax(error) => Self::Syntax(error.clone()),
+            error => Self::Other(error.message()),
+        }
+    }
+
     pub fn from_json(json: Vec<GraphQLErrorMessage>) -> Self {
-        Self {
-            message: String::from("Look at json field for more details"),
-            json: Some(json),
+        Self::GraphQL(json)
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            Self::Network(error) => error.to_string(),
+            Self::Http { status, .. } => format!("HTTP status {}", status),
+            Self::GraphQL(_) => String::from("Look at json field for more details"),
+            Self::Deserialize { source, path, .. } => {
+                format!("Failed to parse response at {}: {}", path, source)
+            }
+            Self::Timeout => String::from("Request timed out"),
+            Self::ConnectTimeout => String::from("Connecting to the server timed out"),
+            Self::Cancelled => String::from("Request was cancelled"),
+            Self::CircuitOpen => String::from("Circuit breaker is open"),
+            Self::Shutdown => String::from("Client is shut down"),
+            Self::ResponseTooLarge { limit } => {
+                format!("Response exceeds the limit of {} bytes", limit)
+            }
+            Self::WithRequestId { request_id, error } => {
+                format!("{} (request ID {})", error.message(), request_id)
+            }
+            #[cfg(feature = "subscription")]
+            Self::WebSocket(error) => error.to_string(),
+            #[cfg(feature = "validation")]
+            Self::Syntax(error) => error.to_string(),
+            Self::Other(message) => message.clone(),
+        }
+    }
+
+    pub fn json(&self) -> Option<&Vec<GraphQLErrorMessage>> {
+        match self.inner() {
+            Self::GraphQL(json) => Some(json),
+            _ => None,
+        }
+    }
+
+    /// Codes of the GraphQL errors, in the order of the errors
+    pub fn codes(&self) -> Vec<ErrorCode> {
+        self.json()
+            .into_iter()
+            .flatten()
+            .filter_map(GraphQLErrorMessage::code)
+            .collect()
+    }
+
+    /// Whether any of the GraphQL errors has the code, e.g. `error.has_code("UNAUTHENTICATED")`
+    pub fn has_code<C: Into<ErrorCode>>(&self, code: C) -> bool {
+        let code = code.into();
+        self.json()
+            .into_iter()
+            .flatten()
+            .any(|error| error.code().as_ref() == Some(&code))
+    }
+
+    /// Whether the request was aborted because the configured timeout or connect timeout elapsed
+    pub fn is_timeout(&self) -> bool {
+        matches!(self.inner(), Self::Timeout | Self::ConnectTimeout)
+    }
+
+    /// Whether the server couldn't be reached within the connect timeout, as opposed to a
+    /// request which was sent but not answered in time
+    pub fn is_connect_timeout(&self) -> bool {
+        matches!(self.inner(), Self::ConnectTimeout)
+    }
+
+    /// Whether the request was aborted through its cancellation token
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self.inner(), Self::Cancelled)
+    }
+
+    /// Whether the request failed fast because the endpoint kept failing
+    pub fn is_circuit_open(&self) -> bool {
+        matches!(self.inner(), Self::CircuitOpen)
+    }
+
+    /// Whether the request was rejected because the client was shut down
+    pub fn is_shutdown(&self) -> bool {
+        matches!(self.inner(), Self::Shutdown)
+    }
+
+    /// Whether the response was abandoned for exceeding the maximum response size
+    pub fn is_response_too_large(&self) -> bool {
+        matches!(self.inner(), Self::ResponseTooLarge { .. })
+    }
+
+    /// Short name of the kind of error, e.g. `timeout` or `graphql`, to label metrics
+    pub fn kind(&self) -> &'static str {
+        match self.inner() {
+            Self::Network(_) => "network",
+            Self::Http { .. } => "http",
+            Self::GraphQL(_) => "graphql",
+            Self::Deserialize { .. } => "deserialize",
+            Self::Timeout => "timeout",
+            Self::ConnectTimeout => "connect_timeout",
+            Self::Cancelled => "cancelled",
+            Self::CircuitOpen => "circuit_open",
+            Self::Shutdown => "shutdown",
+            Self::ResponseTooLarge { .. } => "response_too_large",
+            Self::WithRequestId { error, .. } => error.kind(),
+            #[cfg(feature = "subscription")]
+            Self::WebSocket(_) => "websocket",
+            #[cfg(feature = "validation")]
+            Self::Syntax(_) => "syntax",
+            Self::Other(_) => "other",
+        }
+    }
+
+    /// ID of the request which failed, when request IDs are sent
+    pub fn request_id(&self) -> Option<&str> {
+        match self {
+            Self::WithRequestId { request_id, .. } => Some(request_id),
+            _ => None,
         }
     }
 
-    pub fn message(&self) -> &str {
-        &self.message
+    /// The error itself, without the request ID
+    pub fn inner(&self) -> &Self {
+        match self {
+            Self::WithRequestId { error, .. } => error,
+            error => error,
+        }
     }
 
-    pub fn json(&self) -> &Option<Vec<GraphQLErrorMessage>> {
-        &self.json
+    pub(crate) fn with_request_id(self, request_id: &str) -> Self {
+        match self {
+            Self::WithRequestId { .. } => self,
+            error => Self::WithRequestId {
+                request_id: request_id.to_string(),
+                error: Box::new(error),
+            },
+        }
     }
 }
 
 fn format(err: &GraphQLError, f: &mut Formatter<'_>) -> fmt::Result {
     // Print the main error message
-    writeln!(f, "\nGQLClient Error: {}", err.message)?;
+    writeln!(f, "\nGQLClient Error: {}", err.message())?;
 
-    // Check if query errors have been received
-    if err.json.is_none() {
-        return Ok(());
+    if let GraphQLError::Deserialize { raw_body, .. } = err.inner() {
+        writeln!(f, "Body: {}", raw_body)?;
     }
 
-    let errors = err.json.as_ref();
+    // Check if query errors have been received
+    let errors = match err.json() {
+        Some(errors) => errors,
+        None => return Ok(()),
+    };
 
-    for err in errors.unwrap() {
+    for err in errors {
         writeln!(f, "Message: {}", err.message())?;
     }
 
@@ -106,9 +430,28 @@ impl fmt::Debug for GraphQLError {
 
 impl std::convert::From<reqwest::Error> for GraphQLError {
     fn from(error: Error) -> Self {
-        Self {
-            message: error.to_string(),
-            json: None,
+        if error.is_timeout() && error.is_connect() {
+            Self::ConnectTimeout
+        } else if error.is_timeout() {
+            Self::Timeout
+        } else if let Some(limit) = crate::size_limit::exceeded(&error) {
+            Self::ResponseTooLarge { limit }
+        } else {
+            Self::Network(error)
         }
     }
 }
+
+#[cfg(feature = "subscription")]
+impl std::convert::From<tokio_tungstenite::tungstenite::Error> for GraphQLError {
+    fn from(error: tokio_tungstenite::tungstenite::Error) -> Self {
+        Self::WebSocket(Box::new(error))
+    }
+}
+
+#[cfg(feature = "validation")]
+impl std::convert::From<crate::validation::SyntaxError> for GraphQLError {
+    fn from(error: crate::validation::SyntaxError) -> Self {
+        Self::Syntax(error)
+    }
+}
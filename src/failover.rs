@@ -0,0 +1,84 @@
+use crate::error::GraphQLError;
+use crate::middleware::{Middleware, Next};
+use async_trait::async_trait;
+use reqwest::{Request, Response, Url};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Order in which the endpoints of a failover list are tried
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FailoverStrategy {
+    /// Start from the endpoint which answered last, so a dead one is only tried again once
+    /// the others fail too
+    #[default]
+    Sticky,
+    /// Always start from the first endpoint, going back to it as soon as it recovers
+    Priority,
+}
+
+/// Sends the request to the next endpoint when one is unreachable or answers with a 5xx status
+pub(crate) struct Failover {
+    endpoints: Vec<Url>,
+    strategy: FailoverStrategy,
+    current: AtomicUsize,
+}
+
+impl Failover {
+    pub(crate) fn new(endpoints: Vec<Url>, strategy: FailoverStrategy) -> Self {
+        Self {
+            endpoints,
+            strategy,
+            current: AtomicUsize::new(0),
+        }
+    }
+
+    /// URL of the endpoint, keeping the query parameters of queries sent as GET
+    fn url(&self, index: usize, request: &Request) -> Url {
+        let mut url = self.endpoints[index].clone();
+        url.set_query(request.url().query());
+        url
+    }
+}
+
+#[async_trait]
+impl Middleware for Failover {
+    async fn handle(&self, mut request: Request, next: Next<'_>) -> Result<Response, GraphQLError> {
+        let start = match self.strategy {
+            FailoverStrategy::Sticky => self.current.load(Ordering::Relaxed),
+            FailoverStrategy::Priority => 0,
+        };
+
+        let mut attempt = 0;
+        loop {
+            let index = (start + attempt) % self.endpoints.len();
+            *request.url_mut() = self.url(index, &request);
+
+            // Streaming bodies can't be cloned, those requests are sent only once
+            let retry = request
+                .try_clone()
+                .filter(|_| attempt + 1 < self.endpoints.len());
+            let result = next.run(request).await;
+
+            match retry {
+                Some(retry) if unavailable(&result) => {
+                    request = retry;
+                    attempt += 1;
+                }
+                _ => {
+                    if !unavailable(&result) {
+                        self.current.store(index, Ordering::Relaxed);
+                    }
+                    return result;
+                }
+            }
+        }
+    }
+}
+
+fn unavailable(result: &Result<Response, GraphQLError>) -> bool {
+    match result {
+        Ok(response) => response.status().is_server_error(),
+        Err(GraphQLError::Network(error)) => error.is_connect(),
+        Err(GraphQLError::ConnectTimeout) => true,
+        Err(_) => false,
+    }
+}
@@ -0,0 +1,58 @@
+use crate::client::GQLClient;
+use reqwest::Request;
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+
+impl GQLClient {
+    /// SHA-256 of the document as lowercase hex, the hash automatic persisted queries use
+    ///
+    /// The document is hashed as is, so it must be byte for byte the one registered with the
+    /// server.
+    pub fn query_hash(document: &str) -> String {
+        sha256(&[document.as_bytes()])
+    }
+}
+
+/// Key of the request covering its method, URL, headers and body, None if the body is streamed
+///
+/// The response cache and request deduplication use it, so requests differing in a header such
+/// as `Authorization` never share a response. It is stable across processes, so persistent
+/// cache stores remain valid after a restart.
+pub(crate) fn request_key(request: &Request) -> Option<String> {
+    let body = match request.body() {
+        Some(body) => body.as_bytes()?,
+        None => &[],
+    };
+
+    let mut headers = Vec::new();
+    for (name, value) in request.headers() {
+        headers.extend_from_slice(b"\n");
+        headers.extend_from_slice(name.as_str().as_bytes());
+        headers.extend_from_slice(b": ");
+        headers.extend_from_slice(value.as_bytes());
+    }
+    Some(sha256(&[
+        request.method().as_str().as_bytes(),
+        b" ",
+        request.url().as_str().as_bytes(),
+        &headers,
+        b"\n\n",
+        body,
+    ]))
+}
+
+/// SHA-256 of the concatenated parts as lowercase hex
+pub(crate) fn sha256(parts: &[&[u8]]) -> String {
+    let mut hash = Sha256::new();
+    for part in parts {
+        hash.update(part);
+    }
+    hex(&hash.finalize())
+}
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+    })
+}
@@ -0,0 +1,76 @@
+use crate::builder::GQLClientBuilder;
+use crate::client::GQLClient;
+use crate::error::GraphQLError;
+
+/// Builder of a client for the Hasura GraphQL Engine, returned by `GQLClient::hasura`
+///
+/// The `x-hasura-*` headers are sent with every request and, as Hasura reads them from the
+/// `connection_init` payload, when opening subscriptions. This includes streaming
+/// subscriptions (`<table>_stream`), which are consumed like any other subscription.
+pub struct HasuraClientBuilder {
+    builder: GQLClientBuilder,
+}
+
+impl GQLClient {
+    pub fn hasura(endpoint: &str) -> HasuraClientBuilder {
+        HasuraClientBuilder {
+            builder: GQLClientBuilder::new().endpoint(endpoint),
+        }
+    }
+}
+
+impl HasuraClientBuilder {
+    /// Sends `x-hasura-admin-secret`, granting admin access unless a role is set
+    pub fn admin_secret(self, secret: &str) -> Self {
+        self.session_variable("admin-secret", secret)
+    }
+
+    /// Sends `x-hasura-role`, so requests are checked against the permissions of the role
+    pub fn role(self, role: &str) -> Self {
+        self.session_variable("role", role)
+    }
+
+    pub fn user_id(self, id: &str) -> Self {
+        self.session_variable("user-id", id)
+    }
+
+    /// Sends a session variable, the `x-hasura-` prefix is added when missing
+    pub fn session_variable(mut self, name: &str, value: &str) -> Self {
+        let name = if name.to_ascii_lowercase().starts_with("x-hasura-") {
+            name.to_string()
+        } else {
+            format!("x-hasura-{}", name)
+        };
+        self.builder = self.builder.header(&name, value);
+        self
+    }
+
+    /// Applies further options of the general client builder
+    pub fn configure<F>(mut self, configure: F) -> Self
+    where
+        F: FnOnce(GQLClientBuilder) -> GQLClientBuilder,
+    {
+        self.builder = configure(self.builder);
+        self
+    }
+
+    pub fn build(self) -> Result<GQLClient, GraphQLError> {
+        #[cfg_attr(not(feature = "subscription"), allow(unused_mut))]
+        let mut client = self.builder.build()?;
+
+        // Hasura reads the headers from the connection_init payload, unless one was configured
+        #[cfg(feature = "subscription")]
+        if client.connection_init.is_none() {
+            let headers = client
+                .header_map
+                .iter()
+                .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.into())))
+                .collect::<serde_json::Map<_, _>>();
+            client.connection_init = Some(std::sync::Arc::new(
+                serde_json::json!({ "headers": headers }),
+            ));
+        }
+
+        Ok(client)
+    }
+}
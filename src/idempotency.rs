@@ -0,0 +1,43 @@
+use crate::client::{parse_response_with_meta, GQLClient};
+use crate::error::GraphQLError;
+use crate::request_id::random_id;
+use crate::response::GQLResponse;
+use reqwest::header::HeaderValue;
+use serde::{Deserialize, Serialize};
+
+/// Header carrying the idempotency key unless another one is configured
+pub(crate) const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+pub(crate) fn key_value(key: &str) -> Result<HeaderValue, GraphQLError> {
+    HeaderValue::from_str(key)
+        .map_err(|_| GraphQLError::Other(format!("Invalid idempotency key: {}", key)))
+}
+
+impl GQLClient {
+    /// Sends the mutation with a generated idempotency key
+    ///
+    /// Unlike `mutate`, the mutation is retried according to the retry policy. The key stays
+    /// the same when the request is retried, so servers supporting idempotency keys apply the
+    /// mutation only once. `RequestBuilder::idempotency_key` passes a key of
+    /// the caller instead, e.g. to retry the mutation later on.
+    pub async fn mutate_idempotent<K, T: Serialize>(
+        &self,
+        mutation: &str,
+        variables: T,
+    ) -> Result<K, GraphQLError>
+    where
+        K: for<'de> Deserialize<'de>,
+    {
+        let request = self.request_for(mutation, None, variables)?.header(
+            self.idempotency_key_header.clone(),
+            key_value(&random_id())?,
+        );
+        let raw_response = self
+            .execute_request(request, &self.options(mutation, None))
+            .await?;
+
+        parse_response_with_meta(raw_response)
+            .await
+            .map(GQLResponse::into_data)
+    }
+}
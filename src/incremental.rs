@@ -0,0 +1,531 @@
+use crate::client::{
+    from_body, from_value, read_response, GQLClient, GraphQLResponse, RequestOptions,
+};
+use crate::error::{GraphQLError, GraphQLErrorMessage};
+use crate::metrics;
+use futures_util::stream::{self, Stream, StreamExt};
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use reqwest::Response;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+
+/// `Accept` header of documents using `@defer` or `@stream`
+pub(crate) const ACCEPT_INCREMENTAL: &str = "multipart/mixed;deferSpec=20220824, application/json";
+
+/// Whether the server has to be told that multipart responses are understood
+pub(crate) fn is_incremental(query: &str) -> bool {
+    query.contains("@defer") || query.contains("@stream")
+}
+
+/// Payload of an incremental response
+///
+/// The first patch holds the initial result with an empty path. The following ones hold
+/// deferred fragments (`data`) or streamed list items (`items`) for the location at `path`.
+#[derive(Debug)]
+pub struct Patch {
+    /// Location of the payload in the response data, field names and list indices
+    pub path: Vec<Value>,
+    /// Label of the `@defer` or `@stream` directive
+    pub label: Option<String>,
+    pub data: Option<Value>,
+    pub items: Option<Vec<Value>>,
+    pub errors: Option<Vec<GraphQLErrorMessage>>,
+    pub extensions: Option<Value>,
+    /// Whether more payloads follow this one
+    pub has_next: bool,
+}
+
+impl Patch {
+    /// Merges the payload into the data received so far
+    ///
+    /// Deferred fields are deep-merged into the object at `path` and streamed items are
+    /// appended to the list at, or containing, `path`.
+    pub fn apply(&self, target: &mut Value) {
+        if let Some(data) = &self.data {
+            if self.path.is_empty() && target.is_null() {
+                *target = data.clone();
+            } else if let Some(object) = locate(target, &self.path) {
+                merge(object, data.clone());
+            }
+        }
+
+        if let Some(items) = &self.items {
+            // Older servers point at the index of the first item, newer ones at the list
+            let list = match locate(target, &self.path) {
+                Some(Value::Array(_)) => locate(target, &self.path),
+                _ => self
+                    .path
+                    .split_last()
+                    .and_then(|(_, parent)| locate(target, parent)),
+            };
+            if let Some(Value::Array(list)) = list {
+                list.extend(items.iter().cloned());
+            }
+        }
+    }
+}
+
+fn locate<'v>(mut value: &'v mut Value, path: &[Value]) -> Option<&'v mut Value> {
+    for segment in path {
+        value = match (value, segment) {
+            (Value::Object(object), Value::String(key)) => object.get_mut(key)?,
+            (Value::Array(list), Value::Number(index)) => list.get_mut(index.as_u64()? as usize)?,
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+fn merge(target: &mut Value, patch: Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                match target.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target, patch) => *target = patch,
+    }
+}
+
+/// Part of a multipart response, in any of the incremental delivery formats
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Part {
+    data: Option<Value>,
+    errors: Option<Vec<GraphQLErrorMessage>>,
+    extensions: Option<Value>,
+    /// Subsequent payloads of the original proposal carry their location directly
+    path: Option<Vec<Value>>,
+    label: Option<String>,
+    items: Option<Vec<Value>>,
+    incremental: Option<Vec<Incremental>>,
+    pending: Option<Vec<Pending>>,
+    completed: Option<Vec<Completed>>,
+    has_next: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Incremental {
+    data: Option<Value>,
+    items: Option<Vec<Value>>,
+    errors: Option<Vec<GraphQLErrorMessage>>,
+    extensions: Option<Value>,
+    path: Option<Vec<Value>>,
+    label: Option<String>,
+    /// Newer servers refer to an announced `pending` entry instead of repeating the path
+    id: Option<String>,
+    sub_path: Option<Vec<Value>>,
+}
+
+#[derive(Deserialize)]
+struct Pending {
+    id: String,
+    path: Vec<Value>,
+    label: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Completed {
+    id: String,
+    errors: Option<Vec<GraphQLErrorMessage>>,
+}
+
+/// Splits a multipart body into parts and the parts into patches
+pub(crate) struct Decoder {
+    delimiter: Vec<u8>,
+    buffer: Vec<u8>,
+    started: bool,
+    finished: bool,
+    initial: bool,
+    pending: HashMap<String, (Vec<Value>, Option<String>)>,
+}
+
+impl Decoder {
+    /// Decoder of a `multipart/mixed` response, None for any other content type
+    pub(crate) fn for_headers(headers: &HeaderMap) -> Option<Self> {
+        let content_type = headers.get(CONTENT_TYPE)?.to_str().ok()?;
+        if !content_type.trim_start().starts_with("multipart/mixed") {
+            return None;
+        }
+        let boundary = content_type
+            .split(';')
+            .filter_map(|parameter| parameter.trim().strip_prefix("boundary="))
+            .next()
+            .map(|boundary| boundary.trim_matches('"'))
+            .unwrap_or("-");
+
+        Some(Self {
+            // A leading line break is added to the body, so the first delimiter matches too
+            delimiter: format!("\r\n--{}", boundary).into_bytes(),
+            buffer: b"\r\n".to_vec(),
+            started: false,
+            finished: false,
+            initial: true,
+            pending: HashMap::new(),
+        })
+    }
+
+    pub(crate) fn push(&mut self, chunk: &[u8]) {
+        self.buffer.extend_from_slice(chunk);
+    }
+
+    /// Ends the body, servers may omit the closing delimiter
+    pub(crate) fn finish(&mut self) {
+        if !self.finished {
+            let closing = [&self.delimiter[..], b"--"].concat();
+            self.push(&closing);
+        }
+    }
+
+    /// Patches of the next complete part, None when more data is needed
+    pub(crate) fn next(&mut self) -> Result<Option<Vec<Patch>>, GraphQLError> {
+        loop {
+            if self.finished {
+                return Ok(None);
+            }
+            let position = match find(&self.buffer, &self.delimiter) {
+                Some(position) => position,
+                None => return Ok(None),
+            };
+
+            let part = self.buffer[..position].to_vec();
+            let rest = &self.buffer[position + self.delimiter.len()..];
+            if rest.starts_with(b"--") {
+                self.finished = true;
+            } else if rest.len() < 2 {
+                // Can't tell the closing delimiter apart yet
+                return Ok(None);
+            }
+            self.buffer.drain(..position + self.delimiter.len());
+
+            let started = std::mem::replace(&mut self.started, true);
+            if !started {
+                continue;
+            }
+            if let Some(patches) = self.part(&part)? {
+                return Ok(Some(patches));
+            }
+        }
+    }
+
+    fn part(&mut self, part: &[u8]) -> Result<Option<Vec<Patch>>, GraphQLError> {
+        // The rest of the delimiter line, then the part headers until a blank line
+        let part = match find(part, b"\r\n") {
+            Some(end) => &part[end + 2..],
+            None => return Ok(None),
+        };
+        let body = if part.starts_with(b"\r\n") {
+            &part[2..]
+        } else {
+            match find(part, b"\r\n\r\n") {
+                Some(end) => &part[end + 4..],
+                None => return Ok(None),
+            }
+        };
+        let body = String::from_utf8_lossy(body);
+        if body.trim().is_empty() {
+            return Ok(None);
+        }
+
+        let part: Part = from_body(body.into_owned())?;
+        let patches = self.patches(part);
+        Ok((!patches.is_empty()).then_some(patches))
+    }
+
+    fn patches(&mut self, part: Part) -> Vec<Patch> {
+        let has_next = part.has_next.unwrap_or(false);
+        for pending in part.pending.into_iter().flatten() {
+            self.pending
+                .insert(pending.id, (pending.path, pending.label));
+        }
+
+        let mut patches = Vec::new();
+        if std::mem::replace(&mut self.initial, false) || part.path.is_some() {
+            patches.push(Patch {
+                path: part.path.unwrap_or_default(),
+                label: part.label,
+                data: part.data,
+                items: part.items,
+                errors: part.errors,
+                extensions: part.extensions,
+                has_next,
+            });
+        } else if part.errors.is_some() {
+            patches.push(Patch {
+                path: Vec::new(),
+                label: None,
+                data: None,
+                items: None,
+                errors: part.errors,
+                extensions: part.extensions,
+                has_next,
+            });
+        }
+
+        for incremental in part.incremental.into_iter().flatten() {
+            let (mut path, label) = match (&incremental.id, incremental.path) {
+                (Some(id), _) => self.pending.get(id).cloned().unwrap_or_default(),
+                (None, path) => (path.unwrap_or_default(), None),
+            };
+            path.extend(incremental.sub_path.unwrap_or_default());
+            patches.push(Patch {
+                path,
+                label: incremental.label.or(label),
+                data: incremental.data,
+                items: incremental.items,
+                errors: incremental.errors,
+                extensions: incremental.extensions,
+                has_next,
+            });
+        }
+
+        for completed in part.completed.into_iter().flatten() {
+            let (path, label) = self.pending.remove(&completed.id).unwrap_or_default();
+            if completed.errors.is_some() {
+                patches.push(Patch {
+                    path,
+                    label,
+                    data: None,
+                    items: None,
+                    errors: completed.errors,
+                    extensions: None,
+                    has_next,
+                });
+            }
+        }
+
+        patches
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Merges a whole multipart body into a single GraphQL response
+pub(crate) fn merge_body<K>(
+    mut decoder: Decoder,
+    body: &[u8],
+) -> Result<GraphQLResponse<K>, GraphQLError>
+where
+    K: for<'de> Deserialize<'de>,
+{
+    decoder.push(body);
+    decoder.finish();
+
+    let mut data = Value::Null;
+    let mut errors = Vec::new();
+    let mut extensions = None;
+    while let Some(patches) = decoder.next()? {
+        for patch in patches {
+            patch.apply(&mut data);
+            errors.extend(patch.errors.into_iter().flatten());
+            extensions = extensions.or(patch.extensions);
+        }
+    }
+
+    Ok(GraphQLResponse {
+        data: match data {
+            Value::Null => None,
+            data => Some(from_value(data)?),
+        },
+        errors: (!errors.is_empty()).then_some(errors),
+        extensions,
+    })
+}
+
+impl GQLClient {
+    /// Streams the payloads of a query using `@defer` or `@stream` as they arrive
+    ///
+    /// Servers answering with a single JSON response yield a single patch. Use
+    /// `Patch::apply` to build up the data, or the regular query methods to get the merged
+    /// result once complete.
+    pub fn query_incremental<'a, T: Serialize>(
+        &'a self,
+        query: &'a str,
+        variables: T,
+    ) -> impl Stream<Item = Result<Patch, GraphQLError>> + 'a {
+        let request = self.request_for(query, None, variables);
+
+        stream::once(async move {
+            let request = request?;
+            let options = RequestOptions {
+                cacheable: false,
+                ..self.options(query, None)
+            };
+            self.execute_request(request, &options).await
+        })
+        .flat_map(|response| {
+            let (state, mut measured) = match response {
+                Ok(mut response) => {
+                    let measured = metrics::take(&mut response);
+                    (Incoming::Response(response), measured)
+                }
+                Err(error) => (Incoming::Failed(Some(error)), None),
+            };
+            stream::unfold(state, Incoming::next).inspect(move |patch| {
+                let kind = match patch {
+                    Err(error) => Some(error.kind()),
+                    Ok(patch)
+                        if patch
+                            .errors
+                            .as_ref()
+                            .is_some_and(|errors| !errors.is_empty()) =>
+                    {
+                        Some("graphql")
+                    }
+                    Ok(_) => None,
+                };
+                // The operation counts as failed once, by the first payload which did
+                if let Some(kind) = kind {
+                    if let Some(measured) = measured.take() {
+                        measured.increment_errors(kind);
+                    }
+                }
+            })
+        })
+    }
+
+    /// Streams the items of the list at `path` as they arrive, e.g. for a field using
+    /// `@stream`
+    ///
+    /// `path` is the dot separated location of the list in the data, e.g. `user.posts`. Items of
+    /// the initial payload come first, followed by the streamed ones. Errors reported by the
+    /// server are yielded as they arrive without ending the stream.
+    pub fn stream_items<'a, N, T: Serialize>(
+        &'a self,
+        query: &'a str,
+        variables: T,
+        path: &'a str,
+    ) -> impl Stream<Item = Result<N, GraphQLError>> + 'a
+    where
+        N: DeserializeOwned + 'a,
+    {
+        self.query_incremental(query, variables)
+            .flat_map(move |patch| stream::iter(items_at(patch, path)))
+    }
+}
+
+/// Items of the list at the dot separated `path` carried by a patch
+fn items_at<N: DeserializeOwned>(
+    patch: Result<Patch, GraphQLError>,
+    path: &str,
+) -> Vec<Result<N, GraphQLError>> {
+    let patch = match patch {
+        Ok(patch) => patch,
+        Err(error) => return vec![Err(error)],
+    };
+    let keys = path.split('.').collect::<Vec<_>>();
+
+    let mut results = Vec::new();
+    if let Some(errors) = patch.errors {
+        results.push(Err(GraphQLError::from_json(errors)));
+    }
+
+    let is_at = |location: &[Value], keys: &[&str]| {
+        location.len() == keys.len()
+            && location
+                .iter()
+                .zip(keys)
+                .all(|(segment, key)| matches(segment, key))
+    };
+    // Streamed items point at the list, or at the index of the first item
+    let streamed = is_at(&patch.path, &keys)
+        || patch
+            .path
+            .split_last()
+            .is_some_and(|(last, parent)| last.is_u64() && is_at(parent, &keys));
+    let items = match (patch.items, patch.data) {
+        (Some(items), _) if streamed => items,
+        (_, Some(data))
+            if patch.path.len() <= keys.len() && is_at(&patch.path, &keys[..patch.path.len()]) =>
+        {
+            match keys[patch.path.len()..]
+                .iter()
+                .try_fold(data, |value, key| match value {
+                    Value::Object(mut object) => object.remove(*key),
+                    _ => None,
+                }) {
+                Some(Value::Array(items)) => items,
+                _ => Vec::new(),
+            }
+        }
+        _ => Vec::new(),
+    };
+
+    results.extend(items.into_iter().map(from_value));
+    results
+}
+
+fn matches(segment: &Value, key: &str) -> bool {
+    match segment {
+        Value::String(segment) => segment == key,
+        Value::Number(index) => index.to_string() == key,
+        _ => false,
+    }
+}
+
+/// Response being read by `query_incremental`
+enum Incoming {
+    Response(Response),
+    Parts(Response, Decoder, VecDeque<Patch>),
+    Failed(Option<GraphQLError>),
+}
+
+impl Incoming {
+    async fn next(self) -> Option<(Result<Patch, GraphQLError>, Self)> {
+        let (mut response, mut decoder, mut queue) = match self {
+            Incoming::Failed(error) => {
+                return error.map(|error| (Err(error), Incoming::Failed(None)))
+            }
+            Incoming::Response(response) => match Decoder::for_headers(response.headers()) {
+                Some(decoder) => (response, decoder, VecDeque::new()),
+                None => {
+                    let result = read_response::<Value>(response)
+                        .await
+                        .map(|response| Patch {
+                            path: Vec::new(),
+                            label: None,
+                            data: response.data,
+                            items: None,
+                            errors: response.errors,
+                            extensions: response.extensions,
+                            has_next: false,
+                        });
+                    return Some((result, Incoming::Failed(None)));
+                }
+            },
+            Incoming::Parts(response, decoder, queue) => (response, decoder, queue),
+        };
+
+        loop {
+            if let Some(patch) = queue.pop_front() {
+                return Some((Ok(patch), Incoming::Parts(response, decoder, queue)));
+            }
+            match decoder.next() {
+                Ok(Some(patches)) => {
+                    queue.extend(patches);
+                    continue;
+                }
+                Ok(None) => {}
+                Err(error) => return Some((Err(error), Incoming::Failed(None))),
+            }
+            match response.chunk().await {
+                Ok(Some(chunk)) => decoder.push(&chunk),
+                Ok(None) if decoder.finished => return None,
+                Ok(None) => decoder.finish(),
+                Err(error) => return Some((Err(error.into()), Incoming::Failed(None))),
+            }
+        }
+    }
+}
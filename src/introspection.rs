@@ -0,0 +1,446 @@
+use crate::client::GQLClient;
+use crate::error::GraphQLError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::{self, Display, Formatter, Write};
+
+const BUILT_IN_SCALARS: [&str; 5] = ["String", "Int", "Float", "Boolean", "ID"];
+const BUILT_IN_DIRECTIVES: [&str; 4] = ["skip", "include", "deprecated", "specifiedBy"];
+const DEFAULT_DEPRECATION_REASON: &str = "No longer supported";
+
+/// Standard introspection query, as sent by GraphQL tooling
+pub const INTROSPECTION_QUERY: &str = r#"
+query IntrospectionQuery {
+  __schema {
+    queryType { name }
+    mutationType { name }
+    subscriptionType { name }
+    types { ...FullType }
+    directives {
+      name
+      description
+      locations
+      args { ...InputValue }
+    }
+  }
+}
+
+fragment FullType on __Type {
+  kind
+  name
+  description
+  fields(includeDeprecated: true) {
+    name
+    description
+    args { ...InputValue }
+    type { ...TypeRef }
+    isDeprecated
+    deprecationReason
+  }
+  inputFields { ...InputValue }
+  interfaces { ...TypeRef }
+  enumValues(includeDeprecated: true) {
+    name
+    description
+    isDeprecated
+    deprecationReason
+  }
+  possibleTypes { ...TypeRef }
+}
+
+fragment InputValue on __InputValue {
+  name
+  description
+  type { ...TypeRef }
+  defaultValue
+}
+
+fragment TypeRef on __Type {
+  kind
+  name
+  ofType {
+    kind
+    name
+    ofType {
+      kind
+      name
+      ofType {
+        kind
+        name
+        ofType {
+          kind
+          name
+          ofType {
+            kind
+            name
+            ofType {
+              kind
+              name
+              ofType {
+                kind
+                name
+              }
+            }
+          }
+        }
+      }
+    }
+  }
+}
+"#;
+
+#[derive(Deserialize)]
+struct IntrospectionData {
+    #[serde(rename = "__schema")]
+    schema: Schema,
+}
+
+/// Schema returned by the introspection query
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Schema {
+    pub query_type: Option<NamedType>,
+    pub mutation_type: Option<NamedType>,
+    pub subscription_type: Option<NamedType>,
+    pub types: Vec<Type>,
+    #[serde(default)]
+    pub directives: Vec<Directive>,
+}
+
+impl Schema {
+    /// Looks up a named type
+    pub fn get_type(&self, name: &str) -> Option<&Type> {
+        self.types
+            .iter()
+            .find(|schema_type| schema_type.name.as_deref() == Some(name))
+    }
+
+    /// Prints the schema in the GraphQL schema definition language
+    ///
+    /// Built-in scalars, directives and introspection types are left out.
+    pub fn to_sdl(&self) -> String {
+        let mut definitions = Vec::new();
+
+        let roots = [
+            ("query", &self.query_type, "Query"),
+            ("mutation", &self.mutation_type, "Mutation"),
+            ("subscription", &self.subscription_type, "Subscription"),
+        ];
+        let custom_roots = roots
+            .iter()
+            .any(|(_, root, default)| matches!(root, Some(root) if root.name != *default));
+        if custom_roots {
+            let mut definition = String::from("schema {\n");
+            for (operation, root, _) in roots {
+                if let Some(root) = root {
+                    let _ = writeln!(definition, "  {}: {}", operation, root.name);
+                }
+            }
+            definition.push('}');
+            definitions.push(definition);
+        }
+
+        for directive in &self.directives {
+            if !BUILT_IN_DIRECTIVES.contains(&directive.name.as_str()) {
+                definitions.push(directive.to_sdl());
+            }
+        }
+
+        for schema_type in &self.types {
+            let name = schema_type.name.as_deref().unwrap_or_default();
+            if !name.starts_with("__") && !BUILT_IN_SCALARS.contains(&name) {
+                definitions.push(schema_type.to_sdl());
+            }
+        }
+
+        definitions.join("\n\n") + "\n"
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamedType {
+    pub name: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TypeKind {
+    Scalar,
+    Object,
+    Interface,
+    Union,
+    Enum,
+    InputObject,
+    List,
+    NonNull,
+}
+
+/// Type defined by the schema
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Type {
+    pub kind: TypeKind,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub fields: Option<Vec<Field>>,
+    pub input_fields: Option<Vec<InputValue>>,
+    pub interfaces: Option<Vec<TypeRef>>,
+    pub enum_values: Option<Vec<EnumValue>>,
+    pub possible_types: Option<Vec<TypeRef>>,
+}
+
+impl Type {
+    /// Looks up a field of an object or interface type
+    pub fn field(&self, name: &str) -> Option<&Field> {
+        self.fields
+            .as_ref()?
+            .iter()
+            .find(|field| field.name == name)
+    }
+
+    fn to_sdl(&self) -> String {
+        let mut sdl = description(&self.description, "");
+        let name = self.name.as_deref().unwrap_or_default();
+
+        match self.kind {
+            TypeKind::Scalar => {
+                let _ = write!(sdl, "scalar {}", name);
+            }
+            TypeKind::Object | TypeKind::Interface => {
+                let keyword = match self.kind {
+                    TypeKind::Object => "type",
+                    _ => "interface",
+                };
+                let _ = write!(sdl, "{} {}", keyword, name);
+
+                let interfaces = type_names(&self.interfaces);
+                if !interfaces.is_empty() {
+                    let _ = write!(sdl, " implements {}", interfaces.join(" & "));
+                }
+
+                let fields = self.fields.iter().flatten().map(Field::to_sdl);
+                sdl.push_str(&block(fields));
+            }
+            TypeKind::Union => {
+                let _ = write!(sdl, "union {}", name);
+
+                let members = type_names(&self.possible_types);
+                if !members.is_empty() {
+                    let _ = write!(sdl, " = {}", members.join(" | "));
+                }
+            }
+            TypeKind::Enum => {
+                let _ = write!(sdl, "enum {}", name);
+
+                let values = self.enum_values.iter().flatten().map(EnumValue::to_sdl);
+                sdl.push_str(&block(values));
+            }
+            TypeKind::InputObject => {
+                let _ = write!(sdl, "input {}", name);
+
+                let fields =
+                    self.input_fields.iter().flatten().map(|field| {
+                        description(&field.description, "  ") + "  " + &field.to_sdl()
+                    });
+                sdl.push_str(&block(fields));
+            }
+            TypeKind::List | TypeKind::NonNull => {}
+        }
+
+        sdl
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Field {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub args: Vec<InputValue>,
+    #[serde(rename = "type")]
+    pub field_type: TypeRef,
+    #[serde(default)]
+    pub is_deprecated: bool,
+    pub deprecation_reason: Option<String>,
+}
+
+impl Field {
+    fn to_sdl(&self) -> String {
+        let mut sdl = description(&self.description, "  ");
+        let _ = write!(
+            sdl,
+            "  {}{}: {}",
+            self.name,
+            arguments(&self.args, "  "),
+            self.field_type
+        );
+        sdl.push_str(&deprecated(self.is_deprecated, &self.deprecation_reason));
+        sdl
+    }
+}
+
+/// Argument or input object field
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InputValue {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(rename = "type")]
+    pub value_type: TypeRef,
+    /// Default value printed as a GraphQL literal
+    pub default_value: Option<String>,
+}
+
+impl InputValue {
+    fn to_sdl(&self) -> String {
+        match &self.default_value {
+            Some(default_value) => {
+                format!("{}: {} = {}", self.name, self.value_type, default_value)
+            }
+            None => format!("{}: {}", self.name, self.value_type),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnumValue {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub is_deprecated: bool,
+    pub deprecation_reason: Option<String>,
+}
+
+impl EnumValue {
+    fn to_sdl(&self) -> String {
+        let mut sdl = description(&self.description, "  ");
+        let _ = write!(sdl, "  {}", self.name);
+        sdl.push_str(&deprecated(self.is_deprecated, &self.deprecation_reason));
+        sdl
+    }
+}
+
+/// Reference to a type, possibly wrapped in lists and non-null markers
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeRef {
+    pub kind: TypeKind,
+    pub name: Option<String>,
+    pub of_type: Option<Box<TypeRef>>,
+}
+
+impl TypeRef {
+    /// Name of the innermost named type
+    pub fn named_type(&self) -> Option<&str> {
+        match &self.of_type {
+            Some(of_type) => of_type.named_type(),
+            None => self.name.as_deref(),
+        }
+    }
+}
+
+/// Prints the reference as in SDL, e.g. `[String!]!`
+impl Display for TypeRef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match (self.kind, &self.of_type) {
+            (TypeKind::NonNull, Some(of_type)) => write!(f, "{}!", of_type),
+            (TypeKind::List, Some(of_type)) => write!(f, "[{}]", of_type),
+            _ => f.write_str(self.name.as_deref().unwrap_or_default()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Directive {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub locations: Vec<String>,
+    #[serde(default)]
+    pub args: Vec<InputValue>,
+}
+
+impl Directive {
+    fn to_sdl(&self) -> String {
+        format!(
+            "{}directive @{}{} on {}",
+            description(&self.description, ""),
+            self.name,
+            arguments(&self.args, ""),
+            self.locations.join(" | ")
+        )
+    }
+}
+
+/// Block string description followed by a new line, empty if there is none
+fn description(description: &Option<String>, indent: &str) -> String {
+    match description.as_deref() {
+        Some(description) if !description.is_empty() => {
+            let description = description.replace(r#"""""#, r#"\""""#);
+            if description.contains('\n') {
+                let lines: Vec<String> = description
+                    .lines()
+                    .map(|line| format!("{}{}", indent, line).trim_end().to_string())
+                    .collect();
+                format!("{0}\"\"\"\n{1}\n{0}\"\"\"\n", indent, lines.join("\n"))
+            } else {
+                format!("{0}\"\"\"{1}\"\"\"\n", indent, description)
+            }
+        }
+        _ => String::new(),
+    }
+}
+
+/// Argument list, one argument per line if any of them is described
+fn arguments(args: &[InputValue], indent: &str) -> String {
+    if args.is_empty() {
+        return String::new();
+    }
+
+    if args.iter().all(|arg| arg.description.is_none()) {
+        let args: Vec<String> = args.iter().map(InputValue::to_sdl).collect();
+        return format!("({})", args.join(", "));
+    }
+
+    let nested = format!("{}  ", indent);
+    let args: Vec<String> = args
+        .iter()
+        .map(|arg| description(&arg.description, &nested) + &nested + &arg.to_sdl())
+        .collect();
+    format!("(\n{}\n{})", args.join("\n"), indent)
+}
+
+fn deprecated(is_deprecated: bool, reason: &Option<String>) -> String {
+    match reason.as_deref() {
+        _ if !is_deprecated => String::new(),
+        Some(reason) if reason != DEFAULT_DEPRECATION_REASON => {
+            format!(" @deprecated(reason: {})", Value::from(reason))
+        }
+        _ => String::from(" @deprecated"),
+    }
+}
+
+fn type_names(types: &Option<Vec<TypeRef>>) -> Vec<String> {
+    types.iter().flatten().map(TypeRef::to_string).collect()
+}
+
+/// Braced block of lines, empty for types without members
+fn block(lines: impl Iterator<Item = String>) -> String {
+    let lines: Vec<String> = lines.collect();
+    if lines.is_empty() {
+        return String::new();
+    }
+    format!(" {{\n{}\n}}", lines.join("\n"))
+}
+
+impl GQLClient {
+    /// Runs the standard introspection query and returns the schema of the endpoint
+    pub async fn introspect(&self) -> Result<Schema, GraphQLError> {
+        self.query::<IntrospectionData>(INTROSPECTION_QUERY)
+            .await
+            .map(|data| data.schema)
+    }
+}
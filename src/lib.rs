@@ -9,6 +9,9 @@
 //!
 //! * Use client.query_with_vars for queries with variables
 //! * There's also a wrapper client.query if there is no need to pass variables
+//! * The vars! macro builds variables without declaring a struct,
+//!   e.g. `vars! { "id": 1, "name": name }`
+//! * Variables which are already a `serde_json::Value` can be passed to client.query_with_json_vars
 //!
 //! ```rust
 //!use reqwest_graphql::Client;
@@ -68,17 +71,663 @@
 //!    let mut headers = HashMap::new();
 //!    headers.insert("authorization", "Bearer <some_token>");
 //!
-//!    let client = Client::new_with_headers(endpoint, headers);
+//!    let client = Client::try_new_with_headers(endpoint, headers)?;
+//!
+//!    Ok(())
+//!}
+//! ```
+//!
+//! # Authentication
+//!
+//! `auth_bearer(token)` on the builder sends a fixed bearer token, and
+//! `basic_auth(user, Some(password))` HTTP basic credentials. For tokens which expire,
+//! `token_provider` takes a `TokenProvider`, e.g. an async closure, which is asked for the
+//! token before every request. Other headers computed per request, such as the tenant or
+//! a rotating API key, come from a `HeaderProvider` passed to `header_provider`, which turns
+//! off the response cache and deduplication since their keys can't cover those headers
+//!
+//! The `TokenRefresh` middleware handles tokens rejected by the server: on HTTP 401 or an
+//! `UNAUTHENTICATED` error it calls an async refresh callback and retries the request once
+//! with the new token
+//!
+//! Client::hasura returns a builder for Hasura which sets the admin secret, role and session
+//! variable headers, for queries as well as subscriptions
+//!
+//! ```rust
+//!use reqwest_graphql::Client;
+//!
+//!let client = Client::hasura("https://example.hasura.app/v1/graphql")
+//!    .admin_secret("<admin_secret>")
+//!    .role("user")
+//!    .user_id("42")
+//!    .build()
+//!    .unwrap();
+//! ```
+//!
+//! With the `sigv4` feature, the `SigV4` middleware signs requests with AWS Signature Version 4
+//! for IAM authenticated AppSync APIs, taking the region and a `CredentialsProvider`
+//!
+//! # Client builder
+//!
+//! Client::builder exposes a fluent API to configure the endpoint, headers and transport options
+//!
+//! ```rust
+//!use reqwest_graphql::Client;
+//!use std::time::Duration;
+//!
+//!#[tokio::main]
+//!async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!    let client = Client::builder()
+//!        .endpoint("https://graphqlzero.almansi.me/api")
+//!        .header("authorization", "Bearer <some_token>")
+//!        .timeout(Duration::from_secs(10))
+//!        .connect_timeout(Duration::from_secs(2))
+//!        .user_agent("my-app/1.0")
+//!        .build()?;
+//!
+//!    Ok(())
+//!}
+//! ```
+//!
+//! `timeout` bounds the whole request while `connect_timeout` only bounds establishing the
+//! connection. Both make `is_timeout` return true, a server which couldn't be reached fails
+//! with GraphQLError::ConnectTimeout rather than GraphQLError::Timeout.
+//!
+//! Requests carry a `reqwest-graphql/<version>` User-Agent unless `user_agent` replaces it, as
+//! some APIs such as GitHub's reject requests without one.
+//!
+//! Proxies from the `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY` environment variables are used
+//! by default, `proxy`, `http_proxy`, `https_proxy` and `no_proxy_hosts` configure them explicitly.
+//! SOCKS5 proxies are supported through `socks5_proxy` with the `socks` feature.
+//! `endpoints` takes several URLs of a replicated API instead of `endpoint`, requests fail over
+//! to the next one on connect errors and 5xx responses, as set by `failover_strategy`.
+//! Services behind a private PKI can be reached by trusting their CA with `add_root_certificate`,
+//! and endpoints requiring mutual TLS by setting a client certificate with `identity_pkcs12`
+//! or `identity_pem`.
+//! With the `cookies` feature, `cookie_store(true)` keeps session cookies between requests.
+//! With the `unix-socket` feature, `unix_socket(path)` reaches servers listening on a Unix
+//! domain socket, such as sidecars or local daemons, the endpoint then only provides the path.
+//! `use_graphql_content_type(true)` sends operations without variables as a bare
+//! `application/graphql` document, for servers which reject the JSON envelope.
+//! `minify_queries(true)` strips comments and collapses whitespace in documents before they
+//! are sent, which keeps pretty-printed queries included with `include_str!` compact on the wire.
+//! `persisted_queries` takes a PersistedQueries manifest, usually read from the JSON file
+//! produced by the build, and sends only the hash of each operation instead of its document, for
+//! servers which only execute an allowlist of operations.
+//! `automatic_persisted_queries(true)` sends the hash first and the document only when the
+//! server doesn't know it yet. Together with `use_get_for_queries(true)`, queries become GET
+//! requests holding only the hash and variables, whose responses CDNs can cache.
+//! `on_request_body` takes a hook which may modify the JSON envelope of every operation before
+//! it is sent, e.g. to add `extensions.clientInfo`, and `on_response_body` the JSON of every
+//! response before it is read, e.g. to unwrap the non-standard envelope of a gateway.
+//! `graphql_over_http(true)` follows the GraphQL-over-HTTP spec strictly, requesting
+//! `application/graphql-response+json` and interpreting status codes by media type.
+//!
+//! Connection reuse is tuned with `pool_max_idle_per_host`, `pool_idle_timeout` and
+//! `tcp_keepalive`.
+//! `http2_prior_knowledge()` speaks HTTP/2 without negotiating it, multiplexing the requests
+//! to a gateway over a single connection, and `http2_adaptive_window(true)` sizes the flow
+//! control windows to the bandwidth. The `http3` feature adds `http3_prior_knowledge()`, which
+//! needs `RUSTFLAGS='--cfg reqwest_unstable'` as HTTP/3 is still unstable in reqwest.
+//!
+//! The `gzip`, `brotli` and `deflate` features (or `compression` for all of them) advertise
+//! the encodings in `Accept-Encoding` and transparently decompress responses.
+//! zstd is not supported by reqwest 0.11.
+//!
+//! # Response metadata
+//!
+//! client.query_with_meta returns the data together with the HTTP status, response headers
+//! and the top-level extensions object, e.g. for rate-limit or query cost accounting
+//!
+//! ```rust,no_run
+//!use reqwest_graphql::Client;
+//!use serde::Deserialize;
+//!
+//!#[derive(Deserialize)]
+//!pub struct Data {
+//!    viewer: String
+//!}
+//!
+//!#[tokio::main]
+//!async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!    let client = Client::new("https://example.com/graphql");
+//!    let response = client.query_with_meta::<Data, ()>("{ viewer }", ()).await?;
+//!
+//!    println!("{:?}", response.headers.get("x-ratelimit-remaining"));
+//!    println!("{} {}", response.status, response.data.viewer);
+//!
+//!    Ok(())
+//!}
+//! ```
+//!
+//! When the shape of the response isn't known at compile time, client.query_raw returns the
+//! whole response document as a serde_json::Value, errors included. client.query_raw_data
+//! returns only the data as a RawValue, which is copied from the body without being parsed
+//! into a value, for gateways passing it on unchanged.
+//!
+//! Very large results can be read with client.query_streamed, which deserializes the body
+//! as it arrives instead of buffering all of it first
+//!
+//! # Per-request options
+//!
+//! client.request starts a single request with its own variables, extra headers,
+//! timeout and retry policy, without constructing a new client
+//!
+//! ```rust,no_run
+//!use reqwest_graphql::Client;
+//!use serde::Deserialize;
+//!use std::time::Duration;
+//!
+//!#[derive(Deserialize)]
+//!pub struct Data {
+//!    user: User
+//!}
+//!
+//!#[derive(Deserialize)]
+//!pub struct User {
+//!    name: String
+//!}
+//!
+//!#[tokio::main]
+//!async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!    let client = Client::new("https://graphqlzero.almansi.me/api");
+//!
+//!    let data = client
+//!        .request("query ($id: ID!) { user(id: $id) { name } }")
+//!        .var("id", 1)
+//!        .header("x-tenant", "a")
+//!        .timeout(Duration::from_secs(5))
+//!        .send::<Data>()
+//!        .await?;
+//!
+//!    println!("{}", data.user.name);
+//!
+//!    Ok(())
+//!}
+//! ```
+//!
+//! When only the headers differ between calls, such as a per-user token or the locale,
+//! client.query_with_vars_and_headers takes them next to the variables.
+//!
+//! Passing a CancellationToken with `cancellation_token` allows aborting the request from
+//! elsewhere, e.g. when its result became outdated. It then fails with `GraphQLError::Cancelled`.
+//!
+//! # Retries
+//!
+//! A RetryPolicy set on the builder retries connect errors, timeouts and 429/502/503/504
+//! responses with exponential backoff and jitter, waiting for the `Retry-After` delay of rate
+//! limited responses
+//!
+//! ```rust
+//!use reqwest_graphql::{Client, RetryPolicy};
+//!use std::time::Duration;
+//!
+//!#[tokio::main]
+//!async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!    let client = Client::builder()
+//!        .endpoint("https://graphqlzero.almansi.me/api")
+//!        .retry(RetryPolicy::new(5).base_delay(Duration::from_millis(200)))
+//!        .build()?;
+//!
+//!    Ok(())
+//!}
+//! ```
+//!
+//! GraphQL errors are only retried when selected with `retry_on_codes`, e.g.
+//! `retry_on_codes([ErrorCode::InternalServerError])`, or with a `retry_if` predicate.
+//!
+//! client.mutate executes mutations without ever retrying them, as a mutation failing midway
+//! may have been applied already. Retried mutations are safe against servers supporting
+//! idempotency keys: client.mutate_idempotent sends a generated key in the `Idempotency-Key`
+//! header, and `idempotency_key(key)` on a single request a key of the caller. The key stays
+//! the same across the retries.
+//!
+//! A CircuitBreaker set with `circuit_breaker` stops contacting an endpoint after a number of
+//! consecutive failures, requests then fail immediately with `GraphQLError::CircuitOpen` until
+//! a probe request succeeds after the cool-down
+//!
+//! ```rust
+//!use reqwest_graphql::{CircuitBreaker, Client};
+//!use std::time::Duration;
+//!
+//!#[tokio::main]
+//!async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!    let client = Client::builder()
+//!        .endpoint("https://graphqlzero.almansi.me/api")
+//!        .circuit_breaker(CircuitBreaker::new(5).cool_down(Duration::from_secs(10)))
+//!        .build()?;
+//!
+//!    Ok(())
+//!}
+//! ```
+//!
+//! Against backends with erratic latency, `hedge(delay)` sends a second copy of queries which
+//! haven't been answered after the delay and uses whichever response arrives first.
+//! Mutations are never duplicated.
+//!
+//! # Rate limiting
+//!
+//! A RateLimit set on the builder spaces the requests of the client to a maximum rate,
+//! allowing short bursts, which helps staying under server-side quotas
+//!
+//! ```rust
+//!use reqwest_graphql::{Client, RateLimit};
+//!
+//!#[tokio::main]
+//!async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!    let client = Client::builder()
+//!        .endpoint("https://graphqlzero.almansi.me/api")
+//!        .rate_limit(RateLimit::new(10.0).burst(20))
+//!        .build()?;
+//!
+//!    Ok(())
+//!}
+//! ```
+//!
+//! # Caching
+//!
+//! A ResponseCache keeps successful query responses in memory for a TTL and evicts the least
+//! recently used ones once `max_entries` is reached. Mutations are never cached, and calls
+//! only share an entry when they send the same headers, e.g. the same `Authorization`.
+//! Implement CacheStore to keep responses elsewhere, e.g. in Redis or on disk.
+//! With `stale_while_revalidate`, expired responses are still served for a while
+//! and refreshed in the background, `on_revalidate` receives the fresh ones.
+//! Independently of the cache, `etags(true)` on the builder sends `If-None-Match` for
+//! queries answered with an `ETag` before, reusing the remembered body on `304 Not Modified`.
+//! `deduplicate(true)` coalesces identical queries sent concurrently, e.g. by independent
+//! tasks, into a single request whose response they all receive.
+//! Both key requests by SHA-256, and `Client::query_hash` exposes the SHA-256 hex of a document
+//! used by automatic persisted queries, e.g. to build a persisted query allowlist.
+//!
+//! ```rust
+//!use reqwest_graphql::{Client, ResponseCache};
+//!use std::time::Duration;
+//!
+//!#[tokio::main]
+//!async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!    let client = Client::builder()
+//!        .endpoint("https://graphqlzero.almansi.me/api")
+//!        .cache(ResponseCache::new(Duration::from_secs(30)).max_entries(100))
+//!        .build()?;
+//!
+//!    Ok(())
+//!}
+//! ```
+//!
+//! # Middleware
+//!
+//! Middlewares registered on the builder wrap every HTTP request, which makes it possible
+//! to implement logging, metrics, auth refresh or request mutation without forking the crate.
+//! See the Middleware trait for an example.
+//!
+//! Requests leaving the middleware chain are sent by the reqwest client, unless another
+//! Transport is set on the builder, e.g. for Unix sockets or a different HTTP stack.
+//!
+//! # Tracing
+//!
+//! With the `tracing` feature enabled, every query is recorded in a `gql.query` span carrying
+//! the operation name, endpoint, duration and the HTTP status or error, and each retry emits
+//! a warning event with the attempt number and delay.
+//!
+//! The RequestLogger middleware logs every request with its operation name, status and
+//! duration, and optionally its headers and bodies. Credentials are redacted, along with the
+//! headers and variables named with `redact_header` and `redact_variable`.
+//!
+//! The `opentelemetry` feature additionally injects the trace context of that span
+//! (`traceparent`, `tracestate`) into the request headers through the global propagator,
+//! so server-side traces are linked to the client span. This requires a
+//! `tracing-opentelemetry` layer to be installed.
+//!
+//! `request_ids(true)` on the builder sends a random ID with every request in the
+//! `x-request-id` header (or the one set with `request_id_header`), `request_id_generator`
+//! takes a custom generator and `request_id` on a single request passes an existing ID. The
+//! ID is recorded in the `gql.query` span, and errors carry it as GraphQLError::WithRequestId
+//! so they can be matched with the server logs.
+//!
+//! `metrics` on the builder takes a MetricsSink, which receives the count, errors and latency
+//! of every operation tagged with its operation name, e.g. to feed Prometheus counters and
+//! histograms. Errors are labelled with GraphQLError::kind.
+//!
+//! # Custom reqwest client
+//!
+//! Client exposes with_client function to bring your own reqwest::Client,
+//! for example to configure proxies, timeouts or TLS
+//!
+//! ```rust
+//!use reqwest_graphql::Client;
+//!use std::time::Duration;
+//!
+//!#[tokio::main]
+//!async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!    let endpoint = "https://graphqlzero.almansi.me/api";
+//!    let http_client = reqwest::Client::builder()
+//!        .timeout(Duration::from_secs(10))
+//!        .build()?;
+//!
+//!    let client = Client::with_client(endpoint, http_client);
+//!
+//!    Ok(())
+//!}
+//! ```
+//!
+//! `build_request` returns the reqwest::RequestBuilder of an operation without sending it, to
+//! inspect it or hand it to another HTTP pipeline. Conversely, `send_request` sends a
+//! reqwest::Request built elsewhere through the middlewares, retries and response parsing of
+//! the client.
+//!
+//! # Batching
+//!
+//! client.query_batch sends several operations in one HTTP request to servers with batching
+//! enabled (Apollo Server, Hasura) and returns one result per operation
+//!
+//! ```rust,no_run
+//!use reqwest_graphql::Client;
+//!use serde::{Deserialize, Serialize};
+//!
+//!#[derive(Deserialize)]
+//!pub struct Data {
+//!    user: User
+//!}
+//!
+//!#[derive(Deserialize)]
+//!pub struct User {
+//!    name: String
+//!}
+//!
+//!#[derive(Serialize)]
+//!pub struct Vars {
+//!    id: u32
+//!}
+//!
+//!#[tokio::main]
+//!async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!    let client = Client::new("https://example.com/graphql");
+//!    let query = "query ($id: ID!) { user(id: $id) { name } }";
+//!
+//!    let results = client
+//!        .query_batch::<Data, Vars>(&[(query, Vars { id: 1 }), (query, Vars { id: 2 })])
+//!        .await?;
+//!
+//!    for result in results {
+//!        println!("{}", result?.user.name);
+//!    }
+//!
+//!    Ok(())
+//!}
+//! ```
+//!
+//! When the server doesn't support batching, `query_many` sends the queries as separate
+//! requests with bounded concurrency, returning the results in the same order.
+//!
+//! To avoid N+1 requests, `client.loader(query, path)` returns a Loader collecting the keys
+//! loaded within a few milliseconds into a single query taking them as an `ids` list, then
+//! matching the returned items back to each caller by their `id` field.
+//!
+//! # Pagination
+//!
+//! client.paginate streams the nodes of a Relay connection, passing the `endCursor` of each
+//! page as the `$after` variable of the next request
+//!
+//! ```rust,no_run
+//!use futures_util::StreamExt;
+//!use reqwest_graphql::Client;
+//!use serde::Deserialize;
+//!
+//!#[derive(Deserialize)]
+//!pub struct Post {
+//!    title: String
+//!}
+//!
+//!#[tokio::main]
+//!async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!    let client = Client::new("https://example.com/graphql");
+//!    let query = r#"
+//!        query ($after: String) {
+//!            viewer {
+//!                posts(first: 50, after: $after) {
+//!                    nodes { title }
+//!                    pageInfo { hasNextPage endCursor }
+//!                }
+//!            }
+//!        }
+//!    "#;
+//!
+//!    let mut posts = Box::pin(client.paginate::<Post, ()>(query, (), "viewer.posts"));
+//!    while let Some(post) = posts.next().await {
+//!        println!("{}", post?.title);
+//!    }
+//!
+//!    Ok(())
+//!}
+//! ```
+//!
+//! APIs paginated by offset are read with client.paginate_offset, which sets the variables named
+//! by an `OffsetPagination` and streams whole pages. A closure extracts the items of each page
+//! and the total count, if any.
+//!
+//! # Deferred responses
+//!
+//! Documents using `@defer` or `@stream` ask for `multipart/mixed` responses. The regular query
+//! methods merge every payload into the final result, client.query_incremental streams them as
+//! they arrive instead
+//!
+//! ```rust,no_run
+//!use futures_util::StreamExt;
+//!use reqwest_graphql::Client;
+//!use serde_json::Value;
+//!
+//!#[tokio::main]
+//!async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!    let client = Client::new("https://example.com/graphql");
+//!    let query = "{ viewer { id ... @defer { posts { title } } } }";
+//!
+//!    let mut data = Value::Null;
+//!    let mut patches = Box::pin(client.query_incremental(query, ()));
+//!    while let Some(patch) = patches.next().await {
+//!        let patch = patch?;
+//!        patch.apply(&mut data);
+//!        println!("{}", data);
+//!    }
+//!
+//!    Ok(())
+//!}
+//! ```
+//!
+//! client.stream_items yields the items of a `@stream`ed list as they arrive, given the dot
+//! separated location of the list such as `viewer.posts`.
+//!
+//! # File uploads
+//!
+//! client.query_with_files sends Upload scalars using the GraphQL multipart request spec.
+//! Every file is paired with its path in the operation, e.g. "variables.file"
+//!
+//! ```rust,no_run
+//!use reqwest_graphql::{Client, Upload};
+//!use serde::{Deserialize, Serialize};
+//!
+//!#[derive(Deserialize)]
+//!pub struct Data {
+//!    upload: bool
+//!}
+//!
+//!#[derive(Serialize)]
+//!pub struct Vars {
+//!    file: Option<()>
+//!}
+//!
+//!#[tokio::main]
+//!async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!    let client = Client::new("https://example.com/graphql");
+//!    let query = "mutation ($file: Upload!) { upload(file: $file) }";
+//!    let file = Upload::new("notes.txt", "hello").mime("text/plain");
+//!
+//!    let data = client
+//!        .query_with_files::<Data, Vars>(query, Vars { file: None }, vec![("variables.file", file)])
+//!        .await?;
+//!
+//!    Ok(())
+//!}
+//! ```
+//!
+//! Files too large to be loaded in memory are streamed with `Upload::from_reader`, e.g. from a
+//! `tokio::fs::File`, or `Upload::from_stream`. Large files can report their progress with
+//! `Upload::on_progress`, which is called with the bytes sent so far and the size of the file as
+//! it is streamed.
+//!
+//! # Validation
+//!
+//! With the `validation` feature, client.validate parses a document locally and
+//! `validate_queries(true)` on the builder rejects invalid documents with `GraphQLError::Syntax`
+//! before they are sent
+//!
+//! Given an introspected schema, client.validate_against_schema also checks that the fields,
+//! arguments and variable types of the document exist, returning every problem found
+//!
+//! With the `macros` feature, `gql!("...")` checks the syntax of a document at compile time
+//! and expands to it as `&'static str`. `gql_operation!` also extracts the operation name,
+//! for use with client.query_with_operation
+//!
+//! ```rust,ignore
+//!use reqwest_graphql::{gql, gql_operation};
+//!
+//!let query = gql!("{ viewer { name } }");
+//!let (query, operation_name) = gql_operation!("query Viewer { viewer { name } }");
+//! ```
+//!
+//! # Code generation
+//!
+//! With the `macros` feature, `graphql_operations!` reads a schema in SDL and `.graphql` files
+//! at compile time and generates the variables and response types of each operation.
+//! client.execute takes the variables and returns the typed response
+//!
+//! ```rust,ignore
+//!use reqwest_graphql::{graphql_operations, Client};
+//!
+//!// query UserById($id: ID!) { user(id: $id) { id name } }
+//!graphql_operations!("schema.graphql", "src/queries.graphql");
+//!
+//!#[tokio::main]
+//!async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!    let client = Client::new("https://example.com/graphql");
+//!    let data = client.execute(UserById { id: "1".into() }).await?;
+//!
+//!    println!("{:?}", data.user.map(|user| user.name));
+//!
+//!    Ok(())
+//!}
+//! ```
+//!
+//! # Introspection
+//!
+//! client.introspect runs the standard introspection query and returns the typed schema,
+//! see the introspection module for its types
+//!
+//! ```rust,no_run
+//!use reqwest_graphql::Client;
+//!
+//!#[tokio::main]
+//!async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!    let client = Client::new("https://graphqlzero.almansi.me/api");
+//!    let schema = client.introspect().await?;
+//!
+//!    // Save the schema for code generators
+//!    std::fs::write("schema.graphql", schema.to_sdl())?;
+//!
+//!    Ok(())
+//!}
+//! ```
+//!
+//! # Subscriptions
+//!
+//! With the default subscription feature, client.subscribe opens a WebSocket
+//! speaking the graphql-transport-ws protocol and returns a Stream of results.
+//! The subscriptions of a client and its clones share a single WebSocket, which is closed once
+//! the last of them ends. Dropping the stream completes its subscription on the server.
+//! The WebSocket endpoint is derived from the HTTP endpoint (http -> ws, https -> wss).
+//! Servers speaking the legacy subscriptions-transport-ws protocol are supported by
+//! selecting SubscriptionProtocol::GraphQLWs on the client builder, and AWS AppSync by
+//! selecting SubscriptionProtocol::AppSync, which passes the client headers (API key or
+//! `Authorization`) the way its real-time endpoint expects.
+//!
+//! Where WebSockets aren't available, e.g. behind restrictive proxies, SubscriptionProtocol::Sse
+//! subscribes over Server-Sent Events as in the graphql-sse protocol, with one HTTP request per
+//! subscription. SubscriptionProtocol::SseSingleConnection uses its single connection mode
+//! instead. Both go through the client middlewares.
+//!
+//! Servers authenticating WebSocket connections through the `connection_init` message rather
+//! than HTTP headers get their payload from connection_init_payload on the builder, or from
+//! connection_init_provider, which is called on every connection so tokens can be refreshed.
+//!
+//! With a RetryPolicy set through subscription_reconnect on the builder, a WebSocket which
+//! drops is reopened with exponential backoff and its subscriptions sent again, transparently
+//! for the stream. subscription.events() yields SubscriptionEvent::Reconnected alongside the
+//! data to know when events may have been missed. A connection on which nothing arrives
+//! within the window set with subscription_keep_alive, not even a keep-alive message, is
+//! considered dropped the same way instead of hanging.
+//!
+//! ```rust,no_run
+//!use futures_util::StreamExt;
+//!use reqwest_graphql::Client;
+//!use serde::Deserialize;
+//!
+//!#[derive(Deserialize)]
+//!pub struct Data {
+//!    message_added: String
+//!}
+//!
+//!#[tokio::main]
+//!async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!    let client = Client::new("https://example.com/graphql");
+//!    let mut subscription = client.subscribe::<Data, ()>("subscription { message_added }", ());
+//!
+//!    while let Some(data) = subscription.next().await {
+//!        println!("{}", data?.message_added);
+//!    }
+//!
+//!    Ok(())
+//!}
+//! ```
+//!
+//! # Shutdown
+//!
+//! client.shutdown(deadline) lets a service terminate cleanly: requests and subscriptions
+//! started afterwards fail with GraphQLError::Shutdown, the subscriptions are completed and
+//! their WebSocket closed, batches queued in loaders are sent, and it resolves once the
+//! requests in flight have completed, or fails with GraphQLError::Timeout after the deadline.
+//!
+//! ```rust,no_run
+//!use reqwest_graphql::Client;
+//!use std::time::Duration;
+//!
+//!#[tokio::main]
+//!async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!    let client = Client::new("https://example.com/graphql");
+//!
+//!    // ...
+//!
+//!    client.shutdown(Duration::from_secs(10)).await?;
 //!
 //!    Ok(())
 //!}
 //! ```
 //!
 //! # Error handling
-//! There are two types of errors that can possibly occur. HTTP related errors (for example, authentication problem)
-//! or GraphQL query errors in JSON response.
-//! Debug, Display implementation of GraphQLError struct properly displays those error messages.
+//! GraphQLError is an enum distinguishing network failures, non-success HTTP statuses,
+//! GraphQL errors in the JSON response, deserialization failures (with the raw body) and timeouts.
+//! Non-2xx responses without a GraphQL body, such as the error page of a proxy, fail with
+//! GraphQLError::Http holding the status and body instead of a deserialization failure.
+//! Debug, Display implementation of GraphQLError properly displays those error messages.
 //! Additionally, you can also look at JSON content for more detailed output by calling err.json()
+//! or match on the variants. The `extensions.code` of GraphQL errors is available as an
+//! ErrorCode, e.g. `err.has_code(ErrorCode::Unauthenticated)` or `err.has_code("RATE_LIMITED")`
+//!
+//! For gateways answering with their own error bodies, such as HTML pages or custom JSON,
+//! `error_decoder` on the builder maps the status, headers and body of responses which aren't
+//! GraphQL responses to the GraphQLError to return.
+//!
+//! `max_response_size(bytes)` on the builder guards against oversized or runaway responses:
+//! they are abandoned with GraphQLError::ResponseTooLarge once their `Content-Length` or the
+//! part of the body read exceeds the limit, instead of being buffered in memory.
 //!
 //! ```rust
 //!use reqwest_graphql::Client;
@@ -123,10 +772,124 @@
 //!    Ok(())
 //!}
 //! ```
+//!
+//! # Testing
+//!
+//! Code using the client is tested without a server through MockTransport, which answers
+//! registered queries and records the requests. Client::mock creates a client answered by it,
+//! clients from the builder use it as their transport.
+//!
+//! Integration tests can record real responses instead: the Cassette middleware stores them in
+//! a JSON file on the first run and replays them on the following ones, see CassetteMode.
 
+pub use async_trait::async_trait;
+pub use serde_json::value::RawValue;
+pub use tokio_util::sync::CancellationToken;
+
+mod auth;
+mod batch;
+mod builder;
+mod cache;
+mod cassette;
+mod circuit_breaker;
 mod client;
+mod dedup;
+mod document;
 mod error;
+mod failover;
+mod hash;
+mod hasura;
+mod idempotency;
+mod incremental;
+pub mod introspection;
+mod loader;
+#[cfg(feature = "tracing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tracing")))]
+mod logging;
+mod macros;
+mod metrics;
+mod middleware;
+mod mock;
+mod operation;
+mod over_http;
+mod pagination;
+mod persisted;
+mod prepared;
+#[cfg(feature = "opentelemetry")]
+mod propagation;
+mod rate_limit;
+mod request;
+mod request_id;
+mod response;
+mod retry;
+mod shutdown;
+#[cfg(feature = "sigv4")]
+#[cfg_attr(docsrs, doc(cfg(feature = "sigv4")))]
+mod sigv4;
+mod size_limit;
+#[cfg(feature = "subscription")]
+mod sse;
+mod streaming;
+#[cfg(feature = "subscription")]
+#[cfg_attr(docsrs, doc(cfg(feature = "subscription")))]
+mod subscription;
+mod transport;
+#[cfg(all(unix, feature = "unix-socket"))]
+#[cfg_attr(docsrs, doc(cfg(all(unix, feature = "unix-socket"))))]
+mod unix_socket;
+mod upload;
+#[cfg(feature = "validation")]
+#[cfg_attr(docsrs, doc(cfg(feature = "validation")))]
+mod validation;
 
+pub use auth::{HeaderProvider, TokenProvider, TokenRefresh};
+pub use builder::GQLClientBuilder as ClientBuilder;
+pub use cache::{CacheStore, CachedResponse, MemoryStore, ResponseCache, Revalidated};
+pub use cassette::{Cassette, CassetteMode};
+pub use circuit_breaker::CircuitBreaker;
 pub use client::GQLClient as Client;
+pub use error::ErrorCode;
 pub use error::GraphQLError;
 pub use error::GraphQLErrorMessage;
+pub use failover::FailoverStrategy;
+pub use hasura::HasuraClientBuilder;
+pub use incremental::Patch;
+pub use loader::Loader;
+#[cfg(feature = "tracing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tracing")))]
+pub use logging::RequestLogger;
+pub use metrics::MetricsSink;
+pub use middleware::{Middleware, Next};
+pub use mock::{MockRequest, MockTransport};
+pub use operation::Operation;
+pub use pagination::OffsetPagination;
+pub use persisted::PersistedQueries;
+pub use rate_limit::RateLimit;
+pub use request::GQLRequestBuilder as RequestBuilder;
+#[cfg(feature = "macros")]
+#[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
+pub use reqwest_graphql_macros::{gql, gql_operation, graphql_operations};
+pub use response::GQLResponse as Response;
+pub use retry::RetryPolicy;
+#[cfg(feature = "sigv4")]
+#[cfg_attr(docsrs, doc(cfg(feature = "sigv4")))]
+pub use sigv4::{AwsCredentials, CredentialsProvider, SigV4};
+#[cfg(feature = "subscription")]
+#[cfg_attr(docsrs, doc(cfg(feature = "subscription")))]
+pub use subscription::{
+    ConnectionInitProvider, Subscription, SubscriptionEvent, SubscriptionProtocol,
+};
+pub use transport::Transport;
+#[cfg(all(unix, feature = "unix-socket"))]
+#[cfg_attr(docsrs, doc(cfg(all(unix, feature = "unix-socket"))))]
+pub use unix_socket::UnixSocket;
+pub use upload::Upload;
+#[cfg(feature = "validation")]
+#[cfg_attr(docsrs, doc(cfg(feature = "validation")))]
+pub use validation::{SyntaxError, ValidationError};
+
+#[doc(hidden)]
+pub mod __private {
+    pub use serde;
+    pub use serde_json::{to_value, Map, Value};
+}
@@ -0,0 +1,272 @@
+use crate::client::{from_value, GQLClient};
+use crate::error::GraphQLError;
+use crate::shutdown::Work;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+type Waiter<V> = oneshot::Sender<Result<Option<V>, GraphQLError>>;
+
+/// Batches the keys requested within a short window into a single query, DataLoader style
+///
+/// The query takes the keys as a list variable (`ids` by default) and returns the items at
+/// `path`, which are matched back to the keys through their `id` field. Keys without an item
+/// resolve to `None`. Clones share the pending batch.
+///
+/// ```rust,no_run
+///use reqwest_graphql::Client;
+///use serde::Deserialize;
+///
+///#[derive(Deserialize)]
+///pub struct User {
+///    id: String,
+///    name: String
+///}
+///
+///#[tokio::main]
+///async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///    let client = Client::new("https://example.com/graphql");
+///    let query = "query($ids: [ID!]!) { users(ids: $ids) { id name } }";
+///    let users = client.loader::<User>(query, "users");
+///
+///    // Sent as a single request
+///    let (first, second) = tokio::join!(users.load("1"), users.load("2"));
+///    println!("{:?} {:?}", first?.map(|user| user.name), second?.map(|user| user.name));
+///
+///    Ok(())
+///}
+/// ```
+pub struct Loader<V> {
+    config: Arc<Config>,
+    batch: Arc<Mutex<Batch<V>>>,
+}
+
+#[derive(Clone)]
+struct Config {
+    client: GQLClient,
+    query: String,
+    path: String,
+    variable: String,
+    key_field: String,
+    window: Duration,
+    max_batch: usize,
+}
+
+struct Batch<V> {
+    /// Incremented when the batch is sent, so a timer only sends the batch it was started for
+    generation: u64,
+    waiters: Vec<(String, Waiter<V>)>,
+    keys: usize,
+    /// Held while the batch is pending, so a shutdown sends it and waits for it
+    work: Option<Work>,
+}
+
+impl<V> Loader<V>
+where
+    V: DeserializeOwned + Send + 'static,
+{
+    pub(crate) fn new(mut client: GQLClient, query: &str, path: &str) -> Self {
+        // Batches are admitted when their first key is queued, a shutdown then still sends them
+        client.admitted = true;
+        Self {
+            config: Arc::new(Config {
+                client,
+                query: query.to_string(),
+                path: path.to_string(),
+                variable: "ids".into(),
+                key_field: "id".into(),
+                window: Duration::from_millis(5),
+                max_batch: 100,
+            }),
+            batch: Arc::new(Mutex::new(Batch {
+                generation: 0,
+                waiters: Vec::new(),
+                keys: 0,
+                work: None,
+            })),
+        }
+    }
+
+    /// Name of the list variable receiving the keys
+    pub fn variable(mut self, variable: &str) -> Self {
+        Arc::make_mut(&mut self.config).variable = variable.to_string();
+        self
+    }
+
+    /// Field of the items holding their key
+    pub fn key_field(mut self, key_field: &str) -> Self {
+        Arc::make_mut(&mut self.config).key_field = key_field.to_string();
+        self
+    }
+
+    /// How long keys are collected after the first one of a batch
+    pub fn window(mut self, window: Duration) -> Self {
+        Arc::make_mut(&mut self.config).window = window;
+        self
+    }
+
+    /// Number of distinct keys after which the batch is sent without waiting
+    pub fn max_batch(mut self, max_batch: usize) -> Self {
+        Arc::make_mut(&mut self.config).max_batch = max_batch.max(1);
+        self
+    }
+
+    /// Item with the key, sent with the other keys requested within the window
+    pub async fn load(&self, key: impl Into<String>) -> Result<Option<V>, GraphQLError> {
+        let (sender, receiver) = oneshot::channel();
+        self.enqueue(key.into(), sender);
+
+        receiver
+            .await
+            .unwrap_or_else(|_| Err(GraphQLError::Other("Loader batch was dropped".into())))
+    }
+
+    /// Items of several keys, in the order of the keys
+    pub async fn load_many<I, K>(&self, keys: I) -> Vec<Result<Option<V>, GraphQLError>>
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<String>,
+    {
+        futures_util::future::join_all(keys.into_iter().map(|key| self.load(key))).await
+    }
+
+    fn enqueue(&self, key: String, waiter: Waiter<V>) {
+        let mut batch = self.batch.lock().unwrap();
+        if batch.waiters.is_empty() {
+            match self.config.client.lifecycle.enter() {
+                Ok(work) => batch.work = Some(work),
+                Err(error) => {
+                    let _ = waiter.send(Err(error));
+                    return;
+                }
+            }
+        }
+        if !batch.waiters.iter().any(|(pending, _)| *pending == key) {
+            batch.keys += 1;
+        }
+        batch.waiters.push((key, waiter));
+
+        if batch.keys >= self.config.max_batch {
+            let (waiters, work) = take(&mut batch);
+            tokio::spawn(send(self.config.clone(), waiters, work));
+        } else if batch.waiters.len() == 1 {
+            let generation = batch.generation;
+            let config = self.config.clone();
+            let pending = self.batch.clone();
+            tokio::spawn(async move {
+                // A shutdown sends the batch right away
+                tokio::select! {
+                    _ = tokio::time::sleep(config.window) => {}
+                    _ = config.client.lifecycle.closed() => {}
+                }
+                let (waiters, work) = {
+                    let mut batch = pending.lock().unwrap();
+                    if batch.generation != generation {
+                        return;
+                    }
+                    take(&mut batch)
+                };
+                send(config, waiters, work).await;
+            });
+        }
+    }
+}
+
+impl GQLClient {
+    /// Loader batching the keys requested through it into single queries
+    ///
+    /// `path` is the dot separated location of the list of items in the data, e.g. `users`.
+    pub fn loader<V>(&self, query: &str, path: &str) -> Loader<V>
+    where
+        V: DeserializeOwned + Send + 'static,
+    {
+        Loader::new(self.clone(), query, path)
+    }
+}
+
+impl<V> Clone for Loader<V> {
+    fn clone(&self) -> Self {
+        Self {
+            config: self.config.clone(),
+            batch: self.batch.clone(),
+        }
+    }
+}
+
+fn take<V>(batch: &mut Batch<V>) -> (Vec<(String, Waiter<V>)>, Option<Work>) {
+    batch.generation += 1;
+    batch.keys = 0;
+    (std::mem::take(&mut batch.waiters), batch.work.take())
+}
+
+/// Sends the batch, the work is done once the waiters have their result
+async fn send<V: DeserializeOwned>(
+    config: Arc<Config>,
+    waiters: Vec<(String, Waiter<V>)>,
+    _work: Option<Work>,
+) {
+    let mut keys = Vec::new();
+    for (key, _) in &waiters {
+        if !keys.contains(key) {
+            keys.push(key.clone());
+        }
+    }
+
+    let variables = Value::Object(Map::from_iter([(
+        config.variable.clone(),
+        Value::from(keys),
+    )]));
+    let items = match load(&config, variables).await {
+        Ok(items) => items,
+        Err(error) => {
+            for (_, waiter) in waiters {
+                let _ = waiter.send(Err(error.duplicate()));
+            }
+            return;
+        }
+    };
+
+    for (key, waiter) in waiters {
+        let result = items.get(&key).cloned().map(from_value).transpose();
+        let _ = waiter.send(result);
+    }
+}
+
+/// Items of the batch by key
+async fn load(config: &Config, variables: Value) -> Result<HashMap<String, Value>, GraphQLError> {
+    let data = config
+        .client
+        .query_with_vars::<Value, _>(&config.query, variables)
+        .await?;
+    let items = config
+        .path
+        .split('.')
+        .try_fold(data, |value, key| match value {
+            Value::Object(mut object) => object.remove(key),
+            _ => None,
+        });
+    let items = match items {
+        Some(Value::Array(items)) => items,
+        _ => {
+            return Err(GraphQLError::Other(format!(
+                "No list at `{}` in the data",
+                config.path
+            )))
+        }
+    };
+
+    Ok(items
+        .into_iter()
+        .filter_map(|item| {
+            let key = match item.get(&config.key_field)? {
+                Value::String(key) => key.clone(),
+                Value::Number(key) => key.to_string(),
+                _ => return None,
+            };
+            Some((key, item))
+        })
+        .collect())
+}
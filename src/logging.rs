@@ -0,0 +1,203 @@
+use crate::client::rebuild_response;
+use crate::document::operations;
+use crate::error::{truncate, GraphQLError};
+use crate::middleware::{Middleware, Next};
+use crate::prepared::operation_of;
+use async_trait::async_trait;
+use reqwest::header::{
+    HeaderMap, HeaderName, AUTHORIZATION, CONTENT_TYPE, COOKIE, PROXY_AUTHORIZATION, SET_COOKIE,
+};
+use reqwest::{Request, Response};
+use serde_json::Value;
+use std::time::Instant;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Middleware logging every request with its operation name, status and duration through
+/// `tracing`
+///
+/// Headers and bodies are only logged when enabled. The values of `Authorization`,
+/// `Proxy-Authorization`, `Cookie` and `Set-Cookie` are always redacted, as are the headers and
+/// variables named with `redact_header` and `redact_variable`. The query string is left out of
+/// the logged URL since it holds the variables of queries sent as GET.
+///
+/// ```rust
+///use reqwest_graphql::{Client, RequestLogger};
+///
+///let client = Client::builder()
+///    .endpoint("https://example.com/graphql")
+///    .middleware(
+///        RequestLogger::new()
+///            .bodies(true)
+///            .redact_variable("password"),
+///    )
+///    .build()
+///    .unwrap();
+/// ```
+pub struct RequestLogger {
+    headers: bool,
+    bodies: bool,
+    redacted_headers: Vec<HeaderName>,
+    redacted_variables: Vec<String>,
+}
+
+impl Default for RequestLogger {
+    fn default() -> Self {
+        Self {
+            headers: false,
+            bodies: false,
+            redacted_headers: vec![AUTHORIZATION, PROXY_AUTHORIZATION, COOKIE, SET_COOKIE],
+            redacted_variables: Vec::new(),
+        }
+    }
+}
+
+impl RequestLogger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Logs the request and response headers
+    pub fn headers(mut self, enabled: bool) -> Self {
+        self.headers = enabled;
+        self
+    }
+
+    /// Logs the request and JSON response bodies, truncated to a few kilobytes
+    pub fn bodies(mut self, enabled: bool) -> Self {
+        self.bodies = enabled;
+        self
+    }
+
+    /// Logs the value of the header as `[REDACTED]`
+    ///
+    /// Invalid header names are ignored as no request could have them.
+    pub fn redact_header(mut self, name: &str) -> Self {
+        if let Ok(name) = HeaderName::try_from(name) {
+            self.redacted_headers.push(name);
+        }
+        self
+    }
+
+    /// Logs the values of fields with this name as `[REDACTED]`, wherever they are nested in
+    /// the variables or the response
+    pub fn redact_variable(mut self, name: &str) -> Self {
+        self.redacted_variables.push(name.to_string());
+        self
+    }
+
+    fn format_headers(&self, headers: &HeaderMap) -> String {
+        let headers = headers.iter().map(|(name, value)| {
+            let value = match self.redacted_headers.contains(name) {
+                true => REDACTED,
+                false => value.to_str().unwrap_or("<binary>"),
+            };
+            format!("{}: {}", name, value)
+        });
+        headers.collect::<Vec<_>>().join(", ")
+    }
+
+    fn format_body(&self, body: &[u8]) -> String {
+        let body = match serde_json::from_slice::<Value>(body) {
+            Ok(mut json) => {
+                self.redact(&mut json);
+                json.to_string()
+            }
+            Err(_) => String::from_utf8_lossy(body).into_owned(),
+        };
+        truncate(body)
+    }
+
+    fn redact(&self, value: &mut Value) {
+        match value {
+            Value::Object(fields) => {
+                for (name, value) in fields {
+                    if self.redacted_variables.contains(name) {
+                        *value = Value::String(REDACTED.to_string());
+                    } else {
+                        self.redact(value);
+                    }
+                }
+            }
+            Value::Array(values) => values.iter_mut().for_each(|value| self.redact(value)),
+            _ => {}
+        }
+    }
+}
+
+#[async_trait]
+impl Middleware for RequestLogger {
+    async fn handle(&self, request: Request, next: Next<'_>) -> Result<Response, GraphQLError> {
+        let (query, operation_name) = operation_of(&request);
+        let operation_name = operation_name.or_else(|| {
+            let operations = operations(query.as_deref()?);
+            operations.into_iter().next()?.name
+        });
+        let method = request.method().clone();
+        let mut url = request.url().clone();
+        url.set_query(None);
+        let request_headers = self.headers.then(|| self.format_headers(request.headers()));
+        let request_body = match self.bodies {
+            true => request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .map(|body| self.format_body(body)),
+            false => None,
+        };
+
+        let start = Instant::now();
+        let result = next.run(request).await;
+        let duration_ms = start.elapsed().as_millis() as u64;
+
+        let response = match result {
+            Ok(response) => response,
+            Err(error) => {
+                tracing::warn!(
+                    operation_name,
+                    %method,
+                    %url,
+                    duration_ms,
+                    error = %error.message(),
+                    request_headers,
+                    request_body,
+                    "GraphQL request failed"
+                );
+                return Err(error);
+            }
+        };
+
+        let status = response.status();
+        let response_headers = self
+            .headers
+            .then(|| self.format_headers(response.headers()));
+        // Streamed responses such as `@defer` and subscriptions are passed on unread
+        let json = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .is_some_and(|content_type| content_type.contains("json"));
+        let (response, response_body) = match self.bodies && json {
+            true => {
+                let headers = response.headers().clone();
+                let body = response.bytes().await?;
+                let logged = self.format_body(&body);
+                (rebuild_response(status, headers, body), Some(logged))
+            }
+            false => (response, None),
+        };
+
+        tracing::info!(
+            operation_name,
+            %method,
+            %url,
+            status = status.as_u16(),
+            duration_ms,
+            request_headers,
+            request_body,
+            response_headers,
+            response_body,
+            "GraphQL request completed"
+        );
+        Ok(response)
+    }
+}
@@ -0,0 +1,31 @@
+/// Builds the variables of a query as a JSON map
+///
+/// Values can be anything serializable, so no struct has to be declared for one-off queries.
+///
+/// ```rust
+///use reqwest_graphql::vars;
+///
+///let name = String::from("Leanne");
+///let vars = vars! { "id": 5, "name": name, "tags": ["admin"] };
+///
+///assert_eq!(vars["id"], 5);
+///assert_eq!(vars["name"], "Leanne");
+/// ```
+///
+/// # Panics
+///
+/// If a value can't be represented as JSON, e.g. a map with non-string keys
+#[macro_export]
+macro_rules! vars {
+    ($($key:literal : $value:expr),* $(,)?) => {{
+        #[allow(unused_mut)]
+        let mut variables = $crate::__private::Map::new();
+        $(
+            variables.insert(
+                ::std::string::String::from($key),
+                $crate::__private::to_value(&$value).expect("variable is not serializable"),
+            );
+        )*
+        variables
+    }};
+}
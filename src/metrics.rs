@@ -0,0 +1,132 @@
+use crate::error::GraphQLError;
+use reqwest::Response;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Receives the measurements of every operation sent by the client, e.g. to feed Prometheus
+///
+/// Each operation is measured once, whatever the number of attempts it took. Operations are
+/// tagged with their operation name, None for anonymous ones and batches.
+///
+/// ```rust
+///use reqwest_graphql::{Client, MetricsSink};
+///use std::sync::atomic::{AtomicU64, Ordering};
+///
+///#[derive(Default)]
+///struct Counters {
+///    requests: AtomicU64,
+///    errors: AtomicU64,
+///}
+///
+///impl MetricsSink for Counters {
+///    fn increment_requests(&self, _operation_name: Option<&str>) {
+///        self.requests.fetch_add(1, Ordering::Relaxed);
+///    }
+///
+///    fn increment_errors(&self, _operation_name: Option<&str>, _kind: &str) {
+///        self.errors.fetch_add(1, Ordering::Relaxed);
+///    }
+///}
+///
+///let client = Client::builder()
+///    .endpoint("https://example.com/graphql")
+///    .metrics(Counters::default())
+///    .build()
+///    .unwrap();
+/// ```
+pub trait MetricsSink: Send + Sync + 'static {
+    /// Counts an operation, failed or not
+    fn increment_requests(&self, _operation_name: Option<&str>) {}
+
+    /// Counts a failed operation, `kind` is the `GraphQLError::kind` of its error, responses
+    /// with GraphQL errors count as `graphql`
+    ///
+    /// Errors of the response are counted as it is read, a batch counts once by its first
+    /// failed operation.
+    fn increment_errors(&self, _operation_name: Option<&str>, _kind: &str) {}
+
+    /// Records the time from sending the operation to receiving its response or error
+    fn record_latency(&self, _operation_name: Option<&str>, _latency: Duration) {}
+}
+
+impl<S: MetricsSink> MetricsSink for Arc<S> {
+    fn increment_requests(&self, operation_name: Option<&str>) {
+        S::increment_requests(self, operation_name)
+    }
+
+    fn increment_errors(&self, operation_name: Option<&str>, kind: &str) {
+        S::increment_errors(self, operation_name, kind)
+    }
+
+    fn record_latency(&self, operation_name: Option<&str>, latency: Duration) {
+        S::record_latency(self, operation_name, latency)
+    }
+}
+
+/// Sink of an operation, carried by its response so the errors found while reading it are
+/// counted too
+#[derive(Clone)]
+pub(crate) struct Measured {
+    sink: Arc<dyn MetricsSink>,
+    operation_name: Option<String>,
+}
+
+impl Measured {
+    pub(crate) fn increment_errors(&self, kind: &str) {
+        self.sink
+            .increment_errors(self.operation_name.as_deref(), kind);
+    }
+
+    /// Counts the GraphQL errors of a response, if it has any
+    pub(crate) fn graphql_errors<T>(&self, errors: Option<&[T]>) {
+        if errors.is_some_and(|errors| !errors.is_empty()) {
+            self.increment_errors("graphql");
+        }
+    }
+}
+
+/// Reports the outcome of the request of an operation to the sink
+///
+/// The response is measured once its headers arrive, and left for the code reading it to count
+/// its GraphQL or deserialization errors, see `take`.
+pub(crate) fn record(
+    sink: &Arc<dyn MetricsSink>,
+    operation_name: Option<String>,
+    start: Instant,
+    result: Result<Response, GraphQLError>,
+) -> Result<Response, GraphQLError> {
+    sink.increment_requests(operation_name.as_deref());
+    let result = match result {
+        Ok(mut response) => {
+            let measured = Measured {
+                sink: sink.clone(),
+                operation_name: operation_name.clone(),
+            };
+            response.extensions_mut().insert(measured);
+            Ok(response)
+        }
+        Err(error) => {
+            sink.increment_errors(operation_name.as_deref(), error.kind());
+            Err(error)
+        }
+    };
+    sink.record_latency(operation_name.as_deref(), start.elapsed());
+    result
+}
+
+/// Sink of the operation of the response, taken by the code reading it so its errors are
+/// counted once
+pub(crate) fn take(response: &mut Response) -> Option<Measured> {
+    response.extensions_mut().remove::<Measured>()
+}
+
+/// Counts the error of reading the response, if any
+pub(crate) fn count<T>(
+    result: Result<T, GraphQLError>,
+    measured: Option<&Measured>,
+) -> Result<T, GraphQLError> {
+    if let (Err(error), Some(measured)) = (&result, measured) {
+        measured.increment_errors(error.kind());
+    }
+    result
+}
@@ -0,0 +1,64 @@
+use crate::error::GraphQLError;
+use crate::transport::Transport;
+use async_trait::async_trait;
+use reqwest::{Request, Response};
+use std::sync::Arc;
+
+/// Hook wrapping every HTTP request sent by the client
+///
+/// Middlewares run in registration order, each one decides whether (and how) to call
+/// the rest of the chain through `next.run(request)`. Retries wrap the whole chain,
+/// so every attempt passes through all middlewares.
+///
+/// ```rust
+///use reqwest::{Request, Response};
+///use reqwest_graphql::{async_trait, GraphQLError, Middleware, Next};
+///
+///struct Logger;
+///
+///#[async_trait]
+///impl Middleware for Logger {
+///    async fn handle(&self, request: Request, next: Next<'_>) -> Result<Response, GraphQLError> {
+///        println!("Sending request to {}", request.url());
+///        let response = next.run(request).await?;
+///        println!("Received {}", response.status());
+///        Ok(response)
+///    }
+///}
+/// ```
+#[async_trait]
+pub trait Middleware: Send + Sync + 'static {
+    async fn handle(&self, request: Request, next: Next<'_>) -> Result<Response, GraphQLError>;
+}
+
+/// Remaining part of the middleware chain
+///
+/// It can be copied to run the rest of the chain again, e.g. to retry a request.
+#[derive(Clone, Copy)]
+pub struct Next<'a> {
+    transport: &'a dyn Transport,
+    middlewares: &'a [Arc<dyn Middleware>],
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(
+        transport: &'a dyn Transport,
+        middlewares: &'a [Arc<dyn Middleware>],
+    ) -> Self {
+        Self {
+            transport,
+            middlewares,
+        }
+    }
+
+    pub async fn run(self, request: Request) -> Result<Response, GraphQLError> {
+        match self.middlewares.split_first() {
+            Some((middleware, rest)) => {
+                middleware
+                    .handle(request, Next::new(self.transport, rest))
+                    .await
+            }
+            None => self.transport.execute(request).await,
+        }
+    }
+}
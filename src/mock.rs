@@ -0,0 +1,217 @@
+use crate::client::GQLClient;
+use crate::error::GraphQLError;
+use crate::middleware::{Middleware, Next};
+use crate::transport::Transport;
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+use reqwest::{Client, Method, Request, Response};
+use serde_json::{Map, Value};
+use std::sync::{Arc, Mutex};
+
+const MOCK_ENDPOINT: &str = "http://mock.invalid/graphql";
+
+/// In-memory stand-in for a GraphQL server, for testing code which uses the client
+///
+/// Register a response body per query or operation name, then inspect the received
+/// requests. Requests matching no fixture fail without reaching the network.
+///
+/// ```rust
+///use reqwest_graphql::{Client, MockTransport};
+///use serde_json::{json, Value};
+///
+///#[tokio::main]
+///async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///    let mock = MockTransport::new();
+///    mock.on("{ viewer { id } }", json!({ "data": { "viewer": { "id": "1" } } }));
+///
+///    let client = Client::mock(&mock);
+///    let data = client.query::<Value>("{ viewer { id } }").await?;
+///
+///    assert_eq!(data["viewer"]["id"], "1");
+///    assert_eq!(mock.requests().len(), 1);
+///    Ok(())
+///}
+/// ```
+#[derive(Clone, Default)]
+pub struct MockTransport {
+    inner: Arc<Mutex<Mocked>>,
+}
+
+#[derive(Default)]
+struct Mocked {
+    fixtures: Vec<(Matcher, Value)>,
+    requests: Vec<MockRequest>,
+}
+
+enum Matcher {
+    Query(String),
+    Operation(String),
+}
+
+/// Request received by a `MockTransport`
+#[derive(Clone, Debug)]
+pub struct MockRequest {
+    pub query: String,
+    pub variables: Value,
+    pub operation_name: Option<String>,
+    pub headers: HeaderMap,
+}
+
+impl MockRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answers the query with the response body, e.g. `{ "data": ... }`
+    ///
+    /// Queries are compared ignoring whitespace. Fixtures registered later take precedence.
+    pub fn on(&self, query: &str, body: Value) -> &Self {
+        self.fixture(Matcher::Query(normalize(query)), body)
+    }
+
+    /// Answers every query with the operation name, whether given explicitly or not
+    pub fn on_operation(&self, operation_name: &str, body: Value) -> &Self {
+        self.fixture(Matcher::Operation(operation_name.to_string()), body)
+    }
+
+    /// Requests received so far, in order
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.lock().requests.clone()
+    }
+
+    fn fixture(&self, matcher: Matcher, body: Value) -> &Self {
+        self.lock().fixtures.push((matcher, body));
+        self
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Mocked> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl GQLClient {
+    /// Client answered by the mock instead of a server
+    ///
+    /// Set `MockTransport` as the transport, or last middleware, to mock a client configured
+    /// through the builder.
+    pub fn mock(transport: &MockTransport) -> GQLClient {
+        GQLClient {
+            transport: Arc::new(transport.clone()),
+            ..GQLClient::from_parts(MOCK_ENDPOINT, HeaderMap::new(), Client::new())
+        }
+    }
+}
+
+#[async_trait]
+impl Middleware for MockTransport {
+    async fn handle(&self, request: Request, _next: Next<'_>) -> Result<Response, GraphQLError> {
+        self.execute(request).await
+    }
+}
+
+#[async_trait]
+impl Transport for MockTransport {
+    async fn execute(&self, request: Request) -> Result<Response, GraphQLError> {
+        let received = received(&request)?;
+        let query = normalize(&received.query);
+        let operation_name = received
+            .operation_name
+            .clone()
+            .or_else(|| first_operation_name(&received.query));
+
+        let mut mocked = self.lock();
+        let body = mocked
+            .fixtures
+            .iter()
+            .rev()
+            .find(|(matcher, _)| match matcher {
+                Matcher::Query(fixture) => *fixture == query,
+                Matcher::Operation(name) => operation_name.as_deref() == Some(name.as_str()),
+            })
+            .map(|(_, body)| body.to_string());
+        mocked.requests.push(received);
+
+        let body = body.ok_or_else(|| {
+            GraphQLError::Other(format!("No mocked response for query `{}`", query))
+        })?;
+        let mut response = http::Response::new(body);
+        response
+            .headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        Ok(Response::from(response))
+    }
+}
+
+/// Whether the body is a bare `application/graphql` document rather than a JSON envelope
+fn is_graphql_body(request: &Request) -> bool {
+    request
+        .headers()
+        .get(CONTENT_TYPE)
+        .is_some_and(|content_type| content_type == "application/graphql")
+}
+
+/// Reads the operation from a POST body or the parameters of a GET request
+pub(crate) fn received(request: &Request) -> Result<MockRequest, GraphQLError> {
+    let mut fields = if request.method() == Method::GET {
+        request
+            .url()
+            .query_pairs()
+            .map(|(key, value)| {
+                let value = match key.as_ref() {
+                    "variables" => serde_json::from_str(&value).unwrap_or(Value::Null),
+                    _ => Value::String(value.into_owned()),
+                };
+                (key.into_owned(), value)
+            })
+            .collect()
+    } else if is_graphql_body(request) {
+        let query = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .map(|body| String::from_utf8_lossy(body).into_owned())
+            .unwrap_or_default();
+        Map::from_iter([("query".to_string(), Value::String(query))])
+    } else {
+        request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .and_then(|body| serde_json::from_slice::<Value>(body).ok())
+            .and_then(|body| match body {
+                Value::Object(fields) => Some(fields),
+                _ => None,
+            })
+            .ok_or_else(|| GraphQLError::Other("Mocked request has no JSON body".into()))?
+    };
+
+    Ok(MockRequest {
+        query: match fields.remove("query") {
+            Some(Value::String(query)) => query,
+            _ => String::new(),
+        },
+        variables: fields.remove("variables").unwrap_or_default(),
+        operation_name: match fields.remove("operationName") {
+            Some(Value::String(name)) => Some(name),
+            _ => None,
+        },
+        headers: request.headers().clone(),
+    })
+}
+
+pub(crate) fn normalize(query: &str) -> String {
+    query.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn first_operation_name(query: &str) -> Option<String> {
+    crate::document::operations(query)
+        .into_iter()
+        .next()
+        .and_then(|operation| operation.name)
+}
@@ -0,0 +1,25 @@
+use crate::client::GQLClient;
+use crate::error::GraphQLError;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Operation with typed variables and response, usually generated by `graphql_operations!`
+///
+/// The implementing value holds the variables of the operation.
+pub trait Operation: Serialize {
+    /// Data returned by the operation
+    type Response: DeserializeOwned;
+
+    /// Document containing the operation and the fragments it uses
+    const DOCUMENT: &'static str;
+
+    const OPERATION_NAME: &'static str;
+}
+
+impl GQLClient {
+    /// Executes a typed operation, sending the value as its variables
+    pub async fn execute<O: Operation>(&self, operation: O) -> Result<O::Response, GraphQLError> {
+        self.query_with_operation(O::DOCUMENT, O::OPERATION_NAME, operation)
+            .await
+    }
+}
@@ -0,0 +1,109 @@
+use crate::client::{rebuild_response, GraphQLResponse};
+use crate::error::GraphQLError;
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use reqwest::{Response, StatusCode};
+use serde::de::IgnoredAny;
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Maps the status, headers and body of a response which isn't a GraphQL response to an error
+pub(crate) type ErrorDecoder =
+    Arc<dyn Fn(StatusCode, &HeaderMap, &str) -> Option<GraphQLError> + Send + Sync>;
+
+/// Accept header of the GraphQL-over-HTTP spec, preferring the dedicated media type
+pub(crate) const ACCEPT_GRAPHQL_RESPONSE: &str =
+    "application/graphql-response+json, application/json;q=0.9";
+
+const GRAPHQL_RESPONSE: &str = "application/graphql-response+json";
+const JSON: &str = "application/json";
+const MULTIPART: &str = "multipart/mixed";
+
+/// Interprets the status code as the GraphQL-over-HTTP spec prescribes for the media type
+///
+/// With `application/graphql-response+json`, a non-2xx status with a well-formed response
+/// and no data carries the errors which made the request fail. With `application/json`,
+/// the body of a non-2xx response can't be relied upon and only the status is reported.
+/// https://graphql.github.io/graphql-over-http/draft/#sec-Processing-the-response
+pub(crate) async fn interpret(response: Response) -> Result<Response, GraphQLError> {
+    let status = response.status();
+    let media_type = media_type(response.headers());
+
+    match media_type.as_deref() {
+        Some(GRAPHQL_RESPONSE | JSON | MULTIPART) if status.is_success() => Ok(response),
+        Some(GRAPHQL_RESPONSE) => {
+            let body = response.text().await?;
+            match GraphQLResponse::<IgnoredAny>::from_body(body.clone()) {
+                Ok(GraphQLResponse {
+                    data: None,
+                    errors: Some(errors),
+                    ..
+                }) if !errors.is_empty() => Err(GraphQLError::GraphQL(errors)),
+                _ => Err(GraphQLError::http(status, body)),
+            }
+        }
+        _ if !status.is_success() => Err(GraphQLError::http(status, response.text().await?)),
+        media_type => Err(GraphQLError::Other(format!(
+            "Unexpected response content type: {}",
+            media_type.unwrap_or("none")
+        ))),
+    }
+}
+
+/// Fails non-2xx responses with `GraphQLError::Http` unless they hold a GraphQL response
+///
+/// Servers commonly answer invalid operations with a 4xx status and the errors in the body,
+/// those are passed on to be reported as GraphQL errors. Error pages of proxies and servers,
+/// e.g. an HTML 502, aren't parsed as JSON.
+pub(crate) async fn check_status(response: Response) -> Result<Response, GraphQLError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let headers = response.headers().clone();
+    let body = response.text().await?;
+    if !is_graphql_body(&body) {
+        return Err(GraphQLError::http(status, body));
+    }
+
+    Ok(rebuild_response(status, headers, body))
+}
+
+/// Fails responses which aren't GraphQL responses with the error of the decoder, if it has one
+///
+/// Incremental responses are passed on, their parts are GraphQL responses.
+pub(crate) async fn decode_error(
+    response: Response,
+    decoder: &ErrorDecoder,
+) -> Result<Response, GraphQLError> {
+    if media_type(response.headers()).as_deref() == Some(MULTIPART) {
+        return Ok(response);
+    }
+
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = response.text().await?;
+    if !is_graphql_body(&body) {
+        if let Some(error) = decoder(status, &headers, &body) {
+            return Err(error);
+        }
+    }
+
+    Ok(rebuild_response(status, headers, body))
+}
+
+/// Whether the body is a GraphQL response, or a batch of them
+fn is_graphql_body(body: &str) -> bool {
+    match serde_json::from_str::<Value>(body) {
+        Ok(Value::Object(object)) => object.contains_key("data") || object.contains_key("errors"),
+        Ok(Value::Array(_)) => true,
+        _ => false,
+    }
+}
+
+/// Content type without its parameters
+fn media_type(headers: &HeaderMap) -> Option<String> {
+    let content_type = headers.get(CONTENT_TYPE)?.to_str().ok()?;
+    let media_type = content_type.split(';').next()?.trim();
+    Some(media_type.to_ascii_lowercase())
+}
@@ -0,0 +1,197 @@
+use crate::client::{from_value, GQLClient};
+use crate::error::GraphQLError;
+use futures_util::stream::{self, Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Relay connection, with either `edges { node }` or the `nodes` shorthand selected
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Connection<N> {
+    edges: Option<Vec<Edge<N>>>,
+    nodes: Option<Vec<N>>,
+    page_info: PageInfo,
+}
+
+#[derive(Deserialize)]
+struct Edge<N> {
+    node: N,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+/// Variables used to request the pages of an offset based list
+#[derive(Clone, Debug)]
+pub struct OffsetPagination {
+    offset_variable: String,
+    limit_variable: String,
+    limit: u64,
+    start: u64,
+}
+
+impl OffsetPagination {
+    /// Requests `limit` items per page, starting at offset 0
+    pub fn new(offset_variable: &str, limit_variable: &str, limit: u64) -> Self {
+        Self {
+            offset_variable: offset_variable.to_string(),
+            limit_variable: limit_variable.to_string(),
+            limit: limit.max(1),
+            start: 0,
+        }
+    }
+
+    /// Offset of the first page
+    pub fn start(mut self, offset: u64) -> Self {
+        self.start = offset;
+        self
+    }
+}
+
+impl GQLClient {
+    /// Streams the nodes of a Relay connection, fetching the following pages on demand
+    ///
+    /// `path` is the dot separated location of the connection in the data, e.g. `user.posts`.
+    /// The query must take an `$after` variable and select `pageInfo { hasNextPage endCursor }`
+    /// along with `edges { node { ... } }` or `nodes { ... }`. The stream ends after the last
+    /// page or the first error.
+    pub fn paginate<'a, N, T: Serialize>(
+        &'a self,
+        query: &'a str,
+        variables: T,
+        path: &'a str,
+    ) -> impl Stream<Item = Result<N, GraphQLError>> + 'a
+    where
+        N: DeserializeOwned + 'a,
+    {
+        let variables = object_variables(variables);
+
+        stream::unfold(Some(variables), move |state| async move {
+            let mut variables = match state? {
+                Ok(variables) => variables,
+                Err(error) => return Some((vec![Err(error)], None)),
+            };
+
+            let connection = match self.connection::<N>(query, &variables, path).await {
+                Ok(connection) => connection,
+                Err(error) => return Some((vec![Err(error)], None)),
+            };
+
+            // A cursor which doesn't advance would fetch the same page forever
+            let next = match connection.page_info.end_cursor {
+                Some(cursor)
+                    if connection.page_info.has_next_page && variables["after"] != cursor =>
+                {
+                    variables["after"] = Value::String(cursor);
+                    Some(Ok(variables))
+                }
+                _ => None,
+            };
+            let nodes = match (connection.edges, connection.nodes) {
+                (Some(edges), _) => edges.into_iter().map(|edge| Ok(edge.node)).collect(),
+                (None, nodes) => nodes.unwrap_or_default().into_iter().map(Ok).collect(),
+            };
+
+            Some((nodes, next))
+        })
+        .flat_map(stream::iter)
+    }
+
+    /// Streams the pages of an offset based list, fetching them on demand
+    ///
+    /// The offset and limit variables named by `pagination` are set on each request. `extract`
+    /// returns the items of a page and the total count, if the API reports it. The stream ends
+    /// after a page shorter than the limit, once the total is reached or after the first error.
+    pub fn paginate_offset<'a, K, N, T, F>(
+        &'a self,
+        query: &'a str,
+        variables: T,
+        pagination: OffsetPagination,
+        extract: F,
+    ) -> impl Stream<Item = Result<Vec<N>, GraphQLError>> + 'a
+    where
+        K: DeserializeOwned,
+        N: 'a,
+        T: Serialize,
+        F: Fn(K) -> (Vec<N>, Option<u64>) + 'a,
+    {
+        let variables = object_variables(variables);
+        let start = pagination.start;
+        let shared = Arc::new((pagination, extract));
+
+        stream::unfold(Some((variables, start)), move |state| {
+            let shared = shared.clone();
+            async move {
+                let (pagination, extract) = &*shared;
+                let (mut variables, offset) = match state? {
+                    (Ok(variables), offset) => (variables, offset),
+                    (Err(error), _) => return Some((Err(error), None)),
+                };
+
+                variables[&pagination.offset_variable] = offset.into();
+                variables[&pagination.limit_variable] = pagination.limit.into();
+                let data = match self.query_with_vars::<K, _>(query, &variables).await {
+                    Ok(data) => data,
+                    Err(error) => return Some((Err(error), None)),
+                };
+
+                let (items, total) = extract(data);
+                let end = offset + items.len() as u64;
+                let next = if (items.len() as u64) < pagination.limit
+                    || total.is_some_and(|total| end >= total)
+                {
+                    None
+                } else {
+                    Some((Ok(variables), end))
+                };
+
+                Some((Ok(items), next))
+            }
+        })
+    }
+
+    async fn connection<N>(
+        &self,
+        query: &str,
+        variables: &Value,
+        path: &str,
+    ) -> Result<Connection<N>, GraphQLError>
+    where
+        N: DeserializeOwned,
+    {
+        let data = self.query_with_vars::<Value, _>(query, variables).await?;
+        let connection = path
+            .split('.')
+            .try_fold(data, |value, key| match value {
+                Value::Object(mut object) => object.remove(key),
+                _ => None,
+            })
+            .ok_or_else(|| {
+                GraphQLError::Other(format!("No connection at `{}` in the data", path))
+            })?;
+
+        from_value(connection)
+    }
+}
+
+/// Serializes the variables of a paginated query, which must be an object
+fn object_variables<T: Serialize>(variables: T) -> Result<Value, GraphQLError> {
+    match serde_json::to_value(variables) {
+        Ok(Value::Null) => Ok(Value::Object(Default::default())),
+        Ok(object @ Value::Object(_)) => Ok(object),
+        Ok(other) => Err(GraphQLError::Other(format!(
+            "variables must be a JSON object, got {}",
+            other
+        ))),
+        Err(error) => Err(GraphQLError::Other(format!(
+            "variables couldn't be serialized: {}",
+            error
+        ))),
+    }
+}
@@ -0,0 +1,243 @@
+use crate::client::{rebuild_response, GQLClient, RequestBody, RequestOptions};
+use crate::document::operations;
+use crate::error::{ErrorCode, GraphQLError, GraphQLErrorMessage};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Method, Request, Response};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Hashes of the operations registered with the server, by operation name
+///
+/// A client built with them sends the hash of each operation in
+/// `extensions.persistedQuery` instead of its document, for servers which only execute an
+/// allowlist of persisted operations. Operations missing from the manifest fail without being
+/// sent.
+///
+/// ```rust
+///use reqwest_graphql::{Client, PersistedQueries};
+///
+///let manifest = PersistedQueries::new().operation(
+///    "Viewer",
+///    "0c4bd0ac822f9b98f47e85adaaa049fb68636497cfdee924311521f106051855",
+///);
+///let client = Client::builder()
+///    .endpoint("https://example.com/graphql")
+///    .persisted_queries(manifest)
+///    .build()
+///    .unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct PersistedQueries {
+    hashes: HashMap<String, String>,
+}
+
+impl PersistedQueries {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the SHA-256 hash of an operation, as returned by `Client::query_hash`
+    pub fn operation(mut self, name: impl Into<String>, hash: impl Into<String>) -> Self {
+        self.hashes.insert(name.into(), hash.into());
+        self
+    }
+
+    /// Reads a JSON manifest
+    ///
+    /// The manifest is either an object mapping operation names to hashes, or an Apollo
+    /// persisted query manifest whose `operations` have a `name` and an `id`.
+    pub fn from_manifest(manifest: &str) -> Result<Self, GraphQLError> {
+        let invalid = || GraphQLError::Other("Invalid persisted query manifest".into());
+        let manifest: Value = serde_json::from_str(manifest).map_err(|_| invalid())?;
+
+        let hashes = match manifest.get("operations") {
+            Some(Value::Array(operations)) => operations
+                .iter()
+                .map(|operation| {
+                    let name = operation.get("name")?.as_str()?;
+                    let hash = operation.get("id")?.as_str()?;
+                    Some((name.to_string(), hash.to_string()))
+                })
+                .collect::<Option<_>>(),
+            _ => manifest.as_object().and_then(|hashes| {
+                hashes
+                    .iter()
+                    .map(|(name, hash)| Some((name.clone(), hash.as_str()?.to_string())))
+                    .collect()
+            }),
+        };
+
+        Ok(Self {
+            hashes: hashes.ok_or_else(invalid)?,
+        })
+    }
+
+    /// Reads a JSON manifest from a file, see `from_manifest`
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, GraphQLError> {
+        let path = path.as_ref();
+        let manifest = std::fs::read_to_string(path).map_err(|error| {
+            GraphQLError::Other(format!(
+                "Failed to read persisted query manifest {}: {}",
+                path.display(),
+                error
+            ))
+        })?;
+        Self::from_manifest(&manifest)
+    }
+
+    /// Name and `extensions` of the operation, named explicitly or by its document
+    fn extensions(
+        &self,
+        query: &str,
+        operation_name: Option<&str>,
+    ) -> Result<(String, Value), GraphQLError> {
+        let name = match operation_name {
+            Some(name) => name.to_string(),
+            None => match operations(query).as_slice() {
+                [operation] => operation.name.clone().ok_or_else(|| {
+                    GraphQLError::Other("Persisted queries must be named operations".into())
+                })?,
+                _ => {
+                    return Err(GraphQLError::Other(
+                        "Operation name is required to find the persisted query".into(),
+                    ))
+                }
+            },
+        };
+
+        let hash = self.hashes.get(&name).ok_or_else(|| {
+            GraphQLError::Other(format!(
+                "Operation {} isn't in the persisted query manifest",
+                name
+            ))
+        })?;
+
+        Ok((name, persisted_query(hash)))
+    }
+}
+
+impl GQLClient {
+    /// Body of an operation, minified when enabled, or with only the persisted query hash
+    /// when the client has a manifest
+    pub(crate) fn request_body<'a, T: Serialize>(
+        &self,
+        query: &'a str,
+        operation_name: Option<&'a str>,
+        variables: T,
+    ) -> Result<RequestBody<'a, T>, GraphQLError> {
+        Ok(match &self.persisted_queries {
+            Some(manifest) => {
+                let (name, extensions) = manifest.extensions(query, operation_name)?;
+                RequestBody {
+                    query: None,
+                    variables,
+                    operation_name: Some(Cow::Owned(name)),
+                    extensions: Some(extensions),
+                }
+            }
+            None => RequestBody {
+                query: Some(self.document(query)),
+                variables,
+                operation_name: operation_name.map(Cow::Borrowed),
+                extensions: None,
+            },
+        })
+    }
+
+    /// Replaces the document by its hash with automatic persisted queries
+    pub(crate) fn hash_document<T: Serialize>(&self, body: &mut RequestBody<T>) {
+        if !self.automatic_persisted_queries {
+            return;
+        }
+        if let Some(document) = body.query.take() {
+            body.extensions = Some(persisted_query(&Self::query_hash(&document)));
+        }
+    }
+
+    /// Sends the hashed request again with its document when the server doesn't know the hash
+    /// yet, which registers it for the next requests
+    pub(crate) async fn register_document(
+        &self,
+        response: Response,
+        hashed: Option<Request>,
+        options: &RequestOptions<'_>,
+    ) -> Result<Response, GraphQLError> {
+        // Streamed responses are left alone, they are never about an unknown hash
+        let json = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .is_some_and(|content_type| content_type.contains("json"));
+        let hashed = match hashed {
+            Some(hashed) if json && !options.query.is_empty() => hashed,
+            _ => return Ok(response),
+        };
+
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.bytes().await?;
+        if !unknown_hash(&body) {
+            return Ok(rebuild_response(status, headers, body));
+        }
+
+        match with_document(hashed, &self.document(options.query)) {
+            Some(request) => self.send(request, options, None).await,
+            None => Ok(rebuild_response(status, headers, body)),
+        }
+    }
+}
+
+fn persisted_query(hash: &str) -> Value {
+    json!({ "persistedQuery": { "version": 1, "sha256Hash": hash } })
+}
+
+/// Whether the server answered that it doesn't know the hash, or doesn't support persisted
+/// queries at all
+fn unknown_hash(body: &[u8]) -> bool {
+    #[derive(Deserialize)]
+    struct Errors {
+        errors: Vec<GraphQLErrorMessage>,
+    }
+
+    let errors = match serde_json::from_slice::<Errors>(body) {
+        Ok(body) => body.errors,
+        Err(_) => return false,
+    };
+    errors.iter().any(|error| match error.code() {
+        Some(ErrorCode::PersistedQueryNotFound | ErrorCode::PersistedQueryNotSupported) => true,
+        // Older servers only set the message
+        _ => matches!(
+            error,
+            GraphQLErrorMessage::ConventionalError { message, .. }
+                if message == "PersistedQueryNotFound" || message == "PersistedQueryNotSupported"
+        ),
+    })
+}
+
+/// The hashed request with its document added, None unless it is a single operation sent
+/// without its document
+///
+/// Queries sent as GET stay GET requests with an additional `query` parameter.
+fn with_document(mut request: Request, document: &str) -> Option<Request> {
+    if request.method() == Method::GET {
+        if request.url().query_pairs().any(|(name, _)| name == "query") {
+            return None;
+        }
+        request
+            .url_mut()
+            .query_pairs_mut()
+            .append_pair("query", document);
+    } else {
+        let body = request.body()?.as_bytes()?;
+        let mut body = serde_json::from_slice::<Map<String, Value>>(body).ok()?;
+        if body.contains_key("query") {
+            return None;
+        }
+        body.insert("query".to_string(), document.into());
+        *request.body_mut() = Some(Value::Object(body).to_string().into());
+    }
+    Some(request)
+}
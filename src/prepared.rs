@@ -0,0 +1,76 @@
+use crate::client::{parse_response_with_meta, GQLClient, RequestOptions};
+use crate::error::GraphQLError;
+use crate::response::GQLResponse;
+use reqwest::{Method, Request, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+impl GQLClient {
+    /// Builds the request `query_with_vars` would send, without sending it
+    ///
+    /// It has the URL, client headers, body and timeout of the operation, so it can be
+    /// inspected, logged, signed or sent by another pipeline. Middlewares, request IDs and
+    /// trace context are only added when the client sends a request.
+    pub fn build_request<T: Serialize>(
+        &self,
+        query: &str,
+        variables: T,
+    ) -> Result<RequestBuilder, GraphQLError> {
+        let request = self.request_for(query, None, variables)?;
+        Ok(match self.timeout {
+            Some(timeout) => request.timeout(timeout),
+            None => request,
+        })
+    }
+
+    /// Sends a request built elsewhere, e.g. by `build_request`, through the middlewares,
+    /// retries, cache and response parsing of the client
+    ///
+    /// The document and operation name are read from the body, or the URL of GET requests, to
+    /// apply the options of the operation. Requests without one, such as batches, are retried
+    /// but never cached. The timeout of the request takes precedence over the client one.
+    pub async fn send_request<K>(&self, request: Request) -> Result<GQLResponse<K>, GraphQLError>
+    where
+        K: for<'de> Deserialize<'de>,
+    {
+        let (query, operation_name) = operation_of(&request);
+        let options = RequestOptions {
+            timeout: request.timeout().copied().or(self.timeout),
+            ..match &query {
+                Some(query) => self.options(query, operation_name.as_deref()),
+                None => RequestOptions {
+                    cacheable: false,
+                    ..self.options("", operation_name.as_deref())
+                },
+            }
+        };
+
+        let request = RequestBuilder::from_parts(self.client.clone(), request);
+        let raw_response = self.execute_request(request, &options).await?;
+        parse_response_with_meta(raw_response).await
+    }
+}
+
+/// Document and operation name of a request
+pub(crate) fn operation_of(request: &Request) -> (Option<String>, Option<String>) {
+    if request.method() == Method::GET {
+        let param = |name: &str| {
+            request
+                .url()
+                .query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.into_owned())
+        };
+        return (param("query"), param("operationName"));
+    }
+
+    let body = request
+        .body()
+        .and_then(|body| body.as_bytes())
+        .and_then(|body| serde_json::from_slice::<Value>(body).ok());
+    let string = |name: &str| {
+        let value = body.as_ref()?.get(name)?.as_str()?;
+        Some(value.to_string())
+    };
+    (string("query"), string("operationName"))
+}
@@ -0,0 +1,23 @@
+use opentelemetry::propagation::Injector;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+struct HeaderInjector<'h>(&'h mut HeaderMap);
+
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(key), Ok(value)) = (HeaderName::try_from(key), HeaderValue::try_from(value)) {
+            self.0.insert(key, value);
+        }
+    }
+}
+
+/// Writes the context of the current span (`traceparent`, `tracestate`, ...) into the headers
+/// using the globally registered propagator
+pub(crate) fn inject_context(headers: &mut HeaderMap) {
+    let context = tracing::Span::current().context();
+
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut HeaderInjector(headers))
+    });
+}
@@ -0,0 +1,69 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Token bucket limiting the request rate of a client
+///
+/// Up to `burst` requests are sent immediately, after which requests are spaced
+/// to `per_second` on average. Every HTTP request counts, including retries.
+#[derive(Clone, Debug)]
+pub struct RateLimit {
+    per_second: f64,
+    burst: u32,
+}
+
+impl RateLimit {
+    /// Rate of `per_second` requests, which must be positive and finite or `build` fails
+    pub fn new(per_second: f64) -> Self {
+        Self {
+            per_second,
+            burst: 1,
+        }
+    }
+
+    /// Number of requests which can be sent at once after being idle
+    pub fn burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+
+    pub(crate) fn is_valid(&self) -> bool {
+        self.per_second.is_finite() && self.per_second > 0.0
+    }
+}
+
+/// Shared state of a [`RateLimit`]
+pub(crate) struct RateLimiter {
+    limit: RateLimit,
+    state: Mutex<(f64, Instant)>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(limit: RateLimit) -> Self {
+        Self {
+            state: Mutex::new((f64::from(limit.burst), Instant::now())),
+            limit,
+        }
+    }
+
+    /// Waits until the next request may be sent
+    pub(crate) async fn acquire(&self) {
+        let wait = {
+            let mut state = self.state.lock().unwrap();
+            let (tokens, updated) = &mut *state;
+            let now = Instant::now();
+
+            let refill = now.duration_since(*updated).as_secs_f64() * self.limit.per_second;
+            *tokens = (*tokens + refill).min(f64::from(self.limit.burst));
+            *updated = now;
+
+            // Reserve a token, a negative balance is the wait of the queued requests
+            *tokens -= 1.0;
+            if *tokens >= 0.0 {
+                return;
+            }
+            Duration::from_secs_f64(-*tokens / self.limit.per_second)
+        };
+
+        tokio::time::sleep(wait).await;
+    }
+}
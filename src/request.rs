@@ -0,0 +1,187 @@
+use crate::client::{parse_response_with_meta, GQLClient, RequestOptions};
+use crate::error::GraphQLError;
+use crate::idempotency::key_value;
+use crate::request_id::random_id;
+use crate::response::GQLResponse;
+use crate::retry::RetryPolicy;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::str::FromStr;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// Single request built by `GQLClient::request`
+///
+/// Headers are added on top of the client headers, while timeout and retry
+/// policy replace the client-wide settings for this request only.
+pub struct GQLRequestBuilder<'a> {
+    client: &'a GQLClient,
+    query: &'a str,
+    operation_name: Option<&'a str>,
+    variables: Map<String, Value>,
+    header_map: HeaderMap,
+    timeout: Option<Duration>,
+    retry_policy: Option<RetryPolicy>,
+    cancellation: Option<CancellationToken>,
+    request_id: Option<String>,
+    error: Option<GraphQLError>,
+}
+
+impl<'a> GQLRequestBuilder<'a> {
+    pub(crate) fn new(client: &'a GQLClient, query: &'a str) -> Self {
+        Self {
+            client,
+            query,
+            operation_name: None,
+            variables: Map::new(),
+            header_map: HeaderMap::new(),
+            timeout: client.timeout,
+            retry_policy: client.retry_policy.clone(),
+            cancellation: None,
+            request_id: None,
+            error: None,
+        }
+    }
+
+    /// Selects the operation to execute when the document contains several
+    pub fn operation_name(mut self, operation_name: &'a str) -> Self {
+        self.operation_name = Some(operation_name);
+        self
+    }
+
+    pub fn var<V: Serialize>(mut self, name: &str, value: V) -> Self {
+        match serde_json::to_value(value) {
+            Ok(value) => {
+                self.variables.insert(name.to_string(), value);
+            }
+            Err(_) => self.fail(format!("Failed to serialize variable: {}", name)),
+        }
+        self
+    }
+
+    /// Adds every field of a serializable struct or map as a variable
+    pub fn vars<T: Serialize>(mut self, variables: T) -> Self {
+        match serde_json::to_value(variables) {
+            Ok(Value::Object(variables)) => self.variables.extend(variables),
+            _ => self.fail("Variables must serialize to a JSON object".to_string()),
+        }
+        self
+    }
+
+    /// Sends the header with this request only, responses are then only cached and deduplicated
+    /// with requests sending the same headers
+    pub fn header(mut self, key: &str, value: &str) -> Self {
+        match (HeaderName::from_str(key), HeaderValue::from_str(value)) {
+            (Ok(key), Ok(value)) => {
+                self.header_map.insert(key, value);
+            }
+            _ => self.fail(format!("Invalid header: {}", key)),
+        }
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    pub fn no_retry(mut self) -> Self {
+        self.retry_policy = None;
+        self
+    }
+
+    /// Aborts the request once the token is cancelled, failing with `GraphQLError::Cancelled`
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Sends the key in the idempotency key header, it stays the same when the request is retried
+    ///
+    /// Servers supporting idempotency keys then apply a mutation only once, however often it
+    /// is sent.
+    pub fn idempotency_key(mut self, key: &str) -> Self {
+        match key_value(key) {
+            Ok(value) => {
+                self.header_map
+                    .insert(self.client.idempotency_key_header.clone(), value);
+            }
+            Err(error) => self.fail(error.message()),
+        }
+        self
+    }
+
+    /// Sends a generated idempotency key, see `idempotency_key`
+    pub fn idempotent(self) -> Self {
+        self.idempotency_key(&random_id())
+    }
+
+    /// Sends the ID in the request ID header of the client, instead of a generated one
+    ///
+    /// Errors of the request then carry the ID, see `ClientBuilder::request_ids`.
+    pub fn request_id(mut self, request_id: impl Into<String>) -> Self {
+        self.request_id = Some(request_id.into());
+        self
+    }
+
+    pub async fn send<K>(self) -> Result<K, GraphQLError>
+    where
+        K: for<'de> Deserialize<'de>,
+    {
+        self.send_with_meta().await.map(GQLResponse::into_data)
+    }
+
+    /// Like `send`, but also returns the HTTP status and response headers
+    pub async fn send_with_meta<K>(self) -> Result<GQLResponse<K>, GraphQLError>
+    where
+        K: for<'de> Deserialize<'de>,
+    {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
+        match self.cancellation.clone() {
+            Some(token) => tokio::select! {
+                result = self.execute() => result,
+                _ = token.cancelled() => Err(GraphQLError::Cancelled),
+            },
+            None => self.execute().await,
+        }
+    }
+
+    async fn execute<K>(self) -> Result<GQLResponse<K>, GraphQLError>
+    where
+        K: for<'de> Deserialize<'de>,
+    {
+        let request = self
+            .client
+            .request_for(self.query, self.operation_name, self.variables)?
+            .headers(self.header_map);
+
+        let raw_response = self
+            .client
+            .execute_request(
+                request,
+                &RequestOptions {
+                    timeout: self.timeout,
+                    retry_policy: self.retry_policy.as_ref(),
+                    request_id: self.request_id.as_deref(),
+                    ..self.client.options(self.query, self.operation_name)
+                },
+            )
+            .await?;
+        parse_response_with_meta(raw_response).await
+    }
+
+    fn fail(&mut self, message: String) {
+        if self.error.is_none() {
+            self.error = Some(GraphQLError::Other(message));
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::error::GraphQLError;
+use reqwest::header::{HeaderName, HeaderValue};
+use reqwest::{Request, Response};
+use std::sync::Arc;
+
+/// Header carrying the request ID unless another one is configured
+pub(crate) const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Source of the ID of each request
+pub(crate) type RequestIdGenerator = Arc<dyn Fn() -> String + Send + Sync>;
+
+/// ID of the request which received the response, kept in its extensions
+#[derive(Clone)]
+struct RequestId(String);
+
+/// Random UUID, the default request ID
+pub(crate) fn random_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// Sends the ID in the header
+pub(crate) fn attach(
+    request: &mut Request,
+    header: &HeaderName,
+    request_id: &str,
+) -> Result<(), GraphQLError> {
+    let value = HeaderValue::from_str(request_id)
+        .map_err(|_| GraphQLError::Other(format!("Invalid request ID: {}", request_id)))?;
+    request.headers_mut().insert(header.clone(), value);
+    Ok(())
+}
+
+/// Adds the request ID to the outcome of the request, so errors read from the response
+/// carry it too
+pub(crate) fn correlate(
+    result: Result<Response, GraphQLError>,
+    request_id: &str,
+) -> Result<Response, GraphQLError> {
+    match result {
+        Ok(mut response) => {
+            response
+                .extensions_mut()
+                .insert(RequestId(request_id.to_string()));
+            Ok(response)
+        }
+        Err(error) => Err(error.with_request_id(request_id)),
+    }
+}
+
+/// Request ID of the response, if it was sent with one
+pub(crate) fn of(response: &Response) -> Option<String> {
+    response
+        .extensions()
+        .get::<RequestId>()
+        .map(|request_id| request_id.0.clone())
+}
+
+/// Adds the request ID, if any, to the error of reading the response
+pub(crate) fn tag<T>(
+    result: Result<T, GraphQLError>,
+    request_id: Option<String>,
+) -> Result<T, GraphQLError> {
+    match request_id {
+        Some(request_id) => result.map_err(|error| error.with_request_id(&request_id)),
+        None => result,
+    }
+}
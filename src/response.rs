@@ -0,0 +1,18 @@
+use reqwest::{header::HeaderMap, StatusCode};
+use serde_json::Value;
+
+/// Deserialized data together with the HTTP response metadata
+#[derive(Debug)]
+pub struct GQLResponse<K> {
+    pub data: K,
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    /// Top-level `extensions` of the response, e.g. query cost or tracing data
+    pub extensions: Option<Value>,
+}
+
+impl<K> GQLResponse<K> {
+    pub fn into_data(self) -> K {
+        self.data
+    }
+}
@@ -0,0 +1,190 @@
+use crate::client::{rebuild_response, GraphQLResponse};
+use crate::error::{ErrorCode, GraphQLError, GraphQLErrorMessage};
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Response, StatusCode};
+use serde::de::IgnoredAny;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// Exponential backoff applied to transient failures
+///
+/// Connect errors, timeouts and 429/502/503/504 responses are retried until
+/// `max_attempts` requests have been sent, as well as GraphQL errors selected with `retry_if`
+/// or `retry_on_codes`. The delay before attempt `n + 1`
+/// is `base_delay * 2^(n - 1)`, capped by `max_delay` and reduced by up to `jitter`.
+/// 429 and 503 responses with a `Retry-After` header are retried after the requested delay
+/// instead, also capped by `max_delay`.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: f64,
+    retry_if: Option<ErrorPredicate>,
+}
+
+/// Decides whether a GraphQL error is transient
+#[derive(Clone)]
+struct ErrorPredicate(Arc<dyn Fn(&GraphQLErrorMessage) -> bool + Send + Sync>);
+
+impl fmt::Debug for ErrorPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ErrorPredicate")
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            ..Self::default()
+        }
+    }
+
+    pub fn base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Fraction of the delay (0.0 - 1.0) that is randomized
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Also retries responses containing a GraphQL error for which the predicate returns true
+    ///
+    /// The body of every response is then buffered to look at its errors.
+    pub fn retry_if<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&GraphQLErrorMessage) -> bool + Send + Sync + 'static,
+    {
+        self.retry_if = Some(ErrorPredicate(Arc::new(predicate)));
+        self
+    }
+
+    /// Also retries responses containing a GraphQL error with one of the codes, e.g.
+    /// `retry_on_codes([ErrorCode::InternalServerError, "RATE_LIMITED".into()])`
+    pub fn retry_on_codes<I>(self, codes: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<ErrorCode>,
+    {
+        let codes: Vec<ErrorCode> = codes.into_iter().map(Into::into).collect();
+        self.retry_if(move |error| error.code().is_some_and(|code| codes.contains(&code)))
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    pub(crate) fn should_retry(&self, result: &Result<Response, GraphQLError>) -> bool {
+        match result {
+            Ok(response) => matches!(
+                response.status(),
+                StatusCode::TOO_MANY_REQUESTS
+                    | StatusCode::BAD_GATEWAY
+                    | StatusCode::SERVICE_UNAVAILABLE
+                    | StatusCode::GATEWAY_TIMEOUT
+            ),
+            Err(GraphQLError::Network(error)) => error.is_connect(),
+            Err(error) => error.is_timeout(),
+        }
+    }
+
+    /// Whether to retry, also looking at the GraphQL errors of the response when configured
+    ///
+    /// The body is buffered for that, the returned response reads from the buffer.
+    pub(crate) async fn check(
+        &self,
+        result: Result<Response, GraphQLError>,
+    ) -> (Result<Response, GraphQLError>, bool) {
+        if self.should_retry(&result) {
+            return (result, true);
+        }
+        let retry_if = match &self.retry_if {
+            Some(retry_if) => retry_if,
+            None => return (result, false),
+        };
+        let response = match result {
+            Ok(response) => response,
+            Err(error) => return (Err(error), false),
+        };
+
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = match response.bytes().await {
+            Ok(body) => body,
+            Err(error) => return (Err(error.into()), false),
+        };
+        let retry = serde_json::from_slice::<GraphQLResponse<IgnoredAny>>(&body)
+            .ok()
+            .and_then(|response| response.errors)
+            .is_some_and(|errors| errors.iter().any(|error| (retry_if.0)(error)));
+
+        (Ok(rebuild_response(status, headers, body)), retry)
+    }
+
+    /// Delay to wait after the given (1-based) failed attempt, the `Retry-After` of the
+    /// response when it has one
+    pub(crate) fn delay(&self, attempt: u32, result: &Result<Response, GraphQLError>) -> Duration {
+        match result.as_ref().ok().and_then(retry_after) {
+            Some(retry_after) => retry_after.min(self.max_delay),
+            None => self.backoff(attempt),
+        }
+    }
+
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(31);
+        let delay = self
+            .base_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay);
+
+        delay.mul_f64(1.0 - self.jitter * random_fraction())
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+            jitter: 0.5,
+            retry_if: None,
+        }
+    }
+}
+
+/// Delay requested by a 429 or 503 response, in seconds or as an HTTP date
+fn retry_after(response: &Response) -> Option<Duration> {
+    if !matches!(
+        response.status(),
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+    ) {
+        return None;
+    }
+
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+    match value.parse::<f64>() {
+        Ok(seconds) => Duration::try_from_secs_f64(seconds).ok(),
+        Err(_) => {
+            let date = httpdate::parse_http_date(value).ok()?;
+            Some(date.duration_since(SystemTime::now()).unwrap_or_default())
+        }
+    }
+}
+
+fn random_fraction() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
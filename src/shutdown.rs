@@ -0,0 +1,95 @@
+use crate::client::{rebuild_response, GQLClient};
+use crate::error::GraphQLError;
+use futures_util::StreamExt;
+use reqwest::{Body, Response};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Shutdown state shared by the clones of a client
+pub(crate) struct Lifecycle {
+    closed: watch::Sender<bool>,
+    /// Number of requests and queued batches in progress
+    active: watch::Sender<usize>,
+}
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Self {
+            closed: watch::channel(false).0,
+            active: watch::channel(0).0,
+        }
+    }
+}
+
+impl Lifecycle {
+    /// Admits new work, or fails with `GraphQLError::Shutdown` once the client is shut down
+    pub(crate) fn enter(self: &Arc<Self>) -> Result<Work, GraphQLError> {
+        if self.is_closed() {
+            return Err(GraphQLError::Shutdown);
+        }
+        self.active.send_modify(|active| *active += 1);
+        Ok(Work(self.clone()))
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        *self.closed.borrow()
+    }
+
+    /// Completes once the shutdown begins, so queued work can be flushed
+    pub(crate) async fn closed(&self) {
+        let _ = self.closed.subscribe().wait_for(|closed| *closed).await;
+    }
+
+    async fn idle(&self) {
+        let _ = self
+            .active
+            .subscribe()
+            .wait_for(|active| *active == 0)
+            .await;
+    }
+}
+
+/// Work in progress, which the shutdown waits for until it is dropped
+pub(crate) struct Work(Arc<Lifecycle>);
+
+impl Work {
+    /// Keeps the work in progress until the body of the response is read or dropped
+    pub(crate) fn hold(self, response: Response) -> Response {
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.bytes_stream().map(move |chunk| {
+            let _work = &self;
+            chunk
+        });
+        rebuild_response(status, headers, Body::wrap_stream(body))
+    }
+}
+
+impl Drop for Work {
+    fn drop(&mut self) {
+        self.0.active.send_modify(|active| *active -= 1);
+    }
+}
+
+impl GQLClient {
+    /// Shuts the client and its clones down, letting the work in progress complete
+    ///
+    /// New requests and subscriptions fail with `GraphQLError::Shutdown` from then on. The
+    /// subscription WebSocket is closed after completing each subscription, the event streams
+    /// of SSE subscriptions end, and the batches queued in loaders are sent right away. Resolves
+    /// once the requests in flight completed and their responses were read, or fails with
+    /// `GraphQLError::Timeout` if they didn't within the deadline.
+    pub async fn shutdown(&self, deadline: Duration) -> Result<(), GraphQLError> {
+        self.lifecycle.closed.send_replace(true);
+
+        let drain = async {
+            #[cfg(feature = "subscription")]
+            self.multiplexer.shutdown().await;
+            self.lifecycle.idle().await;
+        };
+        tokio::time::timeout(deadline, drain)
+            .await
+            .map_err(|_| GraphQLError::Timeout)
+    }
+}
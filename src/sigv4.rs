@@ -0,0 +1,268 @@
+use crate::error::GraphQLError;
+use crate::hash::hex;
+use crate::middleware::{Middleware, Next};
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use reqwest::header::{HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use reqwest::{Request, Response};
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+use std::future::Future;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+/// AWS access key, with the session token of temporary credentials
+#[derive(Clone)]
+pub struct AwsCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+}
+
+impl AwsCredentials {
+    pub fn new(access_key_id: &str, secret_access_key: &str) -> Self {
+        Self {
+            access_key_id: access_key_id.to_string(),
+            secret_access_key: secret_access_key.to_string(),
+            session_token: None,
+        }
+    }
+
+    /// Session token of temporary credentials, e.g. from STS or an instance role
+    pub fn session_token(mut self, token: &str) -> Self {
+        self.session_token = Some(token.to_string());
+        self
+    }
+}
+
+impl std::fmt::Debug for AwsCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AwsCredentials")
+            .field("access_key_id", &self.access_key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Source of the credentials used to sign each request
+///
+/// Fixed `AwsCredentials` and async closures returning them implement it.
+#[async_trait]
+pub trait CredentialsProvider: Send + Sync + 'static {
+    async fn credentials(&self) -> Result<AwsCredentials, GraphQLError>;
+}
+
+#[async_trait]
+impl CredentialsProvider for AwsCredentials {
+    async fn credentials(&self) -> Result<AwsCredentials, GraphQLError> {
+        Ok(self.clone())
+    }
+}
+
+#[async_trait]
+impl<F, Fut> CredentialsProvider for F
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<AwsCredentials, GraphQLError>> + Send,
+{
+    async fn credentials(&self) -> Result<AwsCredentials, GraphQLError> {
+        self().await
+    }
+}
+
+/// Middleware signing requests with AWS Signature Version 4, e.g. for IAM authenticated
+/// AppSync APIs
+///
+/// Register it after the middlewares which modify requests, headers added later aren't signed.
+///
+/// ```rust
+///use reqwest_graphql::{AwsCredentials, Client, SigV4};
+///
+///let credentials = AwsCredentials::new("<access_key_id>", "<secret_access_key>");
+///let client = Client::builder()
+///    .endpoint("https://example.appsync-api.eu-west-1.amazonaws.com/graphql")
+///    .middleware(SigV4::new("eu-west-1", credentials))
+///    .build()
+///    .unwrap();
+/// ```
+pub struct SigV4 {
+    region: String,
+    service: String,
+    credentials: Box<dyn CredentialsProvider>,
+}
+
+impl SigV4 {
+    /// Signs for the `appsync` service of the region
+    pub fn new<P: CredentialsProvider>(region: &str, credentials: P) -> Self {
+        Self {
+            region: region.to_string(),
+            service: "appsync".to_string(),
+            credentials: Box::new(credentials),
+        }
+    }
+
+    pub fn service(mut self, service: &str) -> Self {
+        self.service = service.to_string();
+        self
+    }
+
+    fn sign(&self, request: &mut Request, credentials: &AwsCredentials, time: SystemTime) {
+        let timestamp = amz_date(time);
+        let date = &timestamp[..8];
+
+        let headers = request.headers_mut();
+        headers.insert(X_AMZ_DATE, header_value(&timestamp));
+        if let Some(token) = &credentials.session_token {
+            headers.insert(X_AMZ_SECURITY_TOKEN, header_value(token));
+        }
+
+        let url = request.url();
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+        let mut signed = vec![("host".to_string(), host)];
+        for name in [CONTENT_TYPE, X_AMZ_DATE, X_AMZ_SECURITY_TOKEN] {
+            if let Some(value) = request.headers().get(&name) {
+                let value = String::from_utf8_lossy(value.as_bytes()).trim().to_string();
+                signed.push((name.as_str().to_string(), value));
+            }
+        }
+        signed.sort();
+
+        let mut query = url
+            .query_pairs()
+            .map(|(key, value)| (encode(&key), encode(&value)))
+            .collect::<Vec<_>>();
+        query.sort();
+        let query = query
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let signed_headers = signed
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_headers = signed
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value))
+            .collect::<String>();
+        let body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .unwrap_or_default();
+
+        let canonical_request = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            request.method(),
+            url.path(),
+            query,
+            canonical_headers,
+            signed_headers,
+            hex(&Sha256::digest(body))
+        );
+        let scope = format!("{}/{}/{}/aws4_request", date, self.region, self.service);
+        let string_to_sign = format!(
+            "{}\n{}\n{}\n{}",
+            ALGORITHM,
+            timestamp,
+            scope,
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let key = format!("AWS4{}", credentials.secret_access_key);
+        let key = hmac(key.as_bytes(), date.as_bytes());
+        let key = hmac(&key, self.region.as_bytes());
+        let key = hmac(&key, self.service.as_bytes());
+        let key = hmac(&key, b"aws4_request");
+        let signature = hex(&hmac(&key, string_to_sign.as_bytes()));
+
+        let mut authorization = header_value(&format!(
+            "{} Credential={}/{}, SignedHeaders={}, Signature={}",
+            ALGORITHM, credentials.access_key_id, scope, signed_headers, signature
+        ));
+        authorization.set_sensitive(true);
+        request.headers_mut().insert(AUTHORIZATION, authorization);
+    }
+}
+
+#[async_trait]
+impl Middleware for SigV4 {
+    async fn handle(&self, mut request: Request, next: Next<'_>) -> Result<Response, GraphQLError> {
+        if request.body().is_some_and(|body| body.as_bytes().is_none()) {
+            return Err(GraphQLError::Other(
+                "SigV4 signing requires a buffered request body".into(),
+            ));
+        }
+
+        let credentials = self.credentials.credentials().await?;
+        self.sign(&mut request, &credentials, SystemTime::now());
+        next.run(request).await
+    }
+}
+
+const X_AMZ_DATE: HeaderName = HeaderName::from_static("x-amz-date");
+const X_AMZ_SECURITY_TOKEN: HeaderName = HeaderName::from_static("x-amz-security-token");
+
+fn header_value(value: &str) -> HeaderValue {
+    HeaderValue::from_str(value).unwrap_or_else(|_| HeaderValue::from_static(""))
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encodes everything but the unreserved characters of RFC 3986
+fn encode(value: &str) -> String {
+    value.bytes().fold(String::new(), |mut encoded, byte| {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => {
+                let _ = write!(encoded, "%{:02X}", byte);
+            }
+        }
+        encoded
+    })
+}
+
+/// `YYYYMMDDTHHMMSSZ` timestamp in UTC
+fn amz_date(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    let (days, time_of_day) = (seconds / 86400, seconds % 86400);
+
+    // Civil date from days since the epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        year,
+        month,
+        day,
+        time_of_day / 3600,
+        time_of_day % 3600 / 60,
+        time_of_day % 60
+    )
+}
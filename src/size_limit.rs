@@ -0,0 +1,56 @@
+use crate::client::rebuild_response;
+use crate::error::GraphQLError;
+use futures_util::StreamExt;
+use reqwest::{Body, Response};
+use std::error::Error;
+use std::fmt;
+
+/// Error ending the body of a response once it exceeds the size limit
+#[derive(Debug)]
+struct TooLarge {
+    limit: u64,
+}
+
+impl fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Response exceeds the limit of {} bytes", self.limit)
+    }
+}
+
+impl Error for TooLarge {}
+
+/// Fails the response with `GraphQLError::ResponseTooLarge` when its `Content-Length` exceeds
+/// the limit, or once more bytes than that have been read from its body
+pub(crate) fn limit_size(response: Response, limit: u64) -> Result<Response, GraphQLError> {
+    if response
+        .content_length()
+        .is_some_and(|length| length > limit)
+    {
+        return Err(GraphQLError::ResponseTooLarge { limit });
+    }
+
+    let status = response.status();
+    let headers = response.headers().clone();
+    let mut read = 0;
+    let body = response.bytes_stream().map(move |chunk| {
+        let chunk = chunk?;
+        read += chunk.len() as u64;
+        if read > limit {
+            return Err(Box::new(TooLarge { limit }) as Box<dyn Error + Send + Sync>);
+        }
+        Ok(chunk)
+    });
+    Ok(rebuild_response(status, headers, Body::wrap_stream(body)))
+}
+
+/// Limit of the response whose body was cut off, if that's what made the request fail
+pub(crate) fn exceeded(error: &reqwest::Error) -> Option<u64> {
+    let mut source = error.source();
+    while let Some(error) = source {
+        if let Some(too_large) = error.downcast_ref::<TooLarge>() {
+            return Some(too_large.limit);
+        }
+        source = error.source();
+    }
+    None
+}
@@ -0,0 +1,291 @@
+use crate::client::{from_body, GraphQLResponse};
+use crate::error::GraphQLError;
+use crate::middleware::{Middleware, Next};
+use crate::request_id::random_id;
+use crate::shutdown::Lifecycle;
+use crate::transport::Transport;
+use futures_util::{stream, Stream};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT};
+use reqwest::{Client, Method, RequestBuilder, Response};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+const EVENT_STREAM: &str = "text/event-stream";
+const TOKEN_HEADER: HeaderName = HeaderName::from_static("x-graphql-event-stream-token");
+
+/// GraphQL over Server-Sent Events, as specified by the graphql-sse library
+pub(crate) struct EventSource {
+    pub(crate) client: Client,
+    pub(crate) middlewares: Vec<Arc<dyn Middleware>>,
+    pub(crate) transport: Arc<dyn Transport>,
+    pub(crate) endpoint: String,
+    pub(crate) headers: HeaderMap,
+    pub(crate) lifecycle: Arc<Lifecycle>,
+}
+
+impl EventSource {
+    /// Streams the results of the operation over its own event stream
+    pub(crate) fn distinct<K>(
+        self,
+        payload: Result<Value, GraphQLError>,
+    ) -> impl Stream<Item = Result<K, GraphQLError>>
+    where
+        K: for<'de> Deserialize<'de>,
+    {
+        let lifecycle = self.lifecycle.clone();
+        let events = async move {
+            let payload = payload?;
+            let request = self
+                .request(Method::POST)
+                .header(ACCEPT, EVENT_STREAM)
+                .json(&payload);
+            Ok((self.send(request).await?, None))
+        };
+
+        results(events, lifecycle)
+    }
+
+    /// Streams the results of the operation over a reserved event stream, which the server
+    /// could share between operations
+    ///
+    /// The operation is tagged with an ID of its own, and stopped on the server with a DELETE
+    /// request if the stream is dropped before it completes.
+    pub(crate) fn single_connection<K>(
+        self,
+        payload: Result<Value, GraphQLError>,
+    ) -> impl Stream<Item = Result<K, GraphQLError>>
+    where
+        K: for<'de> Deserialize<'de>,
+    {
+        let lifecycle = self.lifecycle.clone();
+        let events = async move {
+            let mut payload = payload?;
+            let token = self.send(self.request(Method::PUT)).await?.text().await?;
+            let token = HeaderValue::from_str(token.trim())
+                .map_err(|_| GraphQLError::Other("Invalid event stream token".into()))?;
+
+            let events = self
+                .send(
+                    self.request(Method::GET)
+                        .header(ACCEPT, EVENT_STREAM)
+                        .header(TOKEN_HEADER, token.clone()),
+                )
+                .await?;
+
+            let id = random_id();
+            match &mut payload["extensions"] {
+                Value::Object(extensions) => {
+                    extensions.insert("operationId".into(), id.clone().into());
+                }
+                extensions => *extensions = json!({ "operationId": id }),
+            }
+            let request = self
+                .request(Method::POST)
+                .header(TOKEN_HEADER, token.clone())
+                .json(&payload);
+            self.send(request).await?;
+
+            let stop = self
+                .request(Method::DELETE)
+                .header(TOKEN_HEADER, token)
+                .query(&[("operationId", &id)]);
+            let stop = async move {
+                let _ = self.send(stop).await;
+            };
+            let operation = Operation {
+                id,
+                stop: Some(Box::pin(stop)),
+            };
+            Ok((events, Some(operation)))
+        };
+
+        results(events, lifecycle)
+    }
+
+    fn request(&self, method: Method) -> RequestBuilder {
+        self.client
+            .request(method, &self.endpoint)
+            .headers(self.headers.clone())
+    }
+
+    async fn send(&self, request: RequestBuilder) -> Result<Response, GraphQLError> {
+        let response = Next::new(&*self.transport, &self.middlewares)
+            .run(request.build()?)
+            .await?;
+
+        let status = response.status();
+        if status.is_success() {
+            Ok(response)
+        } else {
+            let body = response.text().await.unwrap_or_default();
+            Err(GraphQLError::http(status, body))
+        }
+    }
+}
+
+/// Operation sent over a reserved event stream
+struct Operation {
+    id: String,
+    /// Stops the operation on the server, unless it completed
+    stop: Option<Pin<Box<dyn Future<Output = ()> + Send>>>,
+}
+
+impl Drop for Operation {
+    fn drop(&mut self) {
+        if let (Some(stop), Ok(runtime)) = (self.stop.take(), tokio::runtime::Handle::try_current())
+        {
+            runtime.spawn(stop);
+        }
+    }
+}
+
+/// `next` event data in single connection mode, which tags results with their operation
+#[derive(Deserialize)]
+struct Tagged {
+    id: String,
+    payload: Option<Value>,
+}
+
+/// Turns the `next` events of the response into results until `complete`, only reading those
+/// tagged with the ID of the operation in single connection mode
+///
+/// The stream fails with `GraphQLError::Shutdown` when polled after the client was shut down,
+/// and ends once the shutdown begins.
+fn results<K, F>(
+    events: F,
+    lifecycle: Arc<Lifecycle>,
+) -> impl Stream<Item = Result<K, GraphQLError>>
+where
+    K: for<'de> Deserialize<'de>,
+    F: Future<Output = Result<(Response, Option<Operation>), GraphQLError>>,
+{
+    stream::unfold(Reading::Connecting(Box::pin(events)), move |state| {
+        let lifecycle = lifecycle.clone();
+        async move {
+            let (mut response, mut decoder, mut operation) = match state {
+                Reading::Connecting(_) if lifecycle.is_closed() => {
+                    return Some((Err(GraphQLError::Shutdown), Reading::Done))
+                }
+                Reading::Connecting(events) => match events.await {
+                    Ok((response, operation)) => {
+                        (Box::new(response), Decoder::default(), operation)
+                    }
+                    Err(error) => return Some((Err(error), Reading::Done)),
+                },
+                Reading::Events(response, decoder, operation) => (response, decoder, operation),
+                Reading::Done => return None,
+            };
+
+            loop {
+                while let Some(event) = decoder.next() {
+                    match event.name.as_str() {
+                        "next" => {
+                            let payload = if let Some(operation) = &operation {
+                                match from_body::<Tagged>(event.data) {
+                                    Ok(tagged) if tagged.id == operation.id => tagged.payload,
+                                    Ok(_) => continue,
+                                    Err(error) => return Some((Err(error), Reading::Done)),
+                                }
+                            } else {
+                                match from_body::<Value>(event.data) {
+                                    Ok(payload) => Some(payload),
+                                    Err(error) => return Some((Err(error), Reading::Done)),
+                                }
+                            };
+                            let result =
+                                GraphQLResponse::<K>::from_value(payload.unwrap_or_default())
+                                    .and_then(GraphQLResponse::into_result);
+                            let reading = Reading::Events(response, decoder, operation);
+                            return Some((result, reading));
+                        }
+                        "complete" => match &mut operation {
+                            Some(operation) => {
+                                let done = from_body::<Tagged>(event.data)
+                                    .map_or(true, |tagged| tagged.id == operation.id);
+                                if done {
+                                    // Completed on the server, there is nothing left to stop
+                                    operation.stop = None;
+                                    return None;
+                                }
+                            }
+                            None => return None,
+                        },
+                        _ => {}
+                    }
+                }
+
+                let chunk = tokio::select! {
+                    chunk = response.chunk() => chunk,
+                    _ = lifecycle.closed() => return None,
+                };
+                match chunk {
+                    Ok(Some(chunk)) => decoder.push(&chunk),
+                    Ok(None) => return None,
+                    Err(error) => return Some((Err(error.into()), Reading::Done)),
+                }
+            }
+        }
+    })
+}
+
+enum Reading<F> {
+    Connecting(Pin<Box<F>>),
+    Events(Box<Response>, Decoder, Option<Operation>),
+    Done,
+}
+
+struct Event {
+    name: String,
+    data: String,
+}
+
+/// Splits an event stream into events, see
+/// https://html.spec.whatwg.org/multipage/server-sent-events.html#event-stream-interpretation
+#[derive(Default)]
+struct Decoder {
+    buffer: Vec<u8>,
+    name: String,
+    data: Vec<String>,
+}
+
+impl Decoder {
+    fn push(&mut self, chunk: &[u8]) {
+        self.buffer.extend_from_slice(chunk);
+    }
+
+    fn next(&mut self) -> Option<Event> {
+        while let Some(end) = self.buffer.iter().position(|&byte| byte == b'\n') {
+            let line = self.buffer.drain(..=end).collect::<Vec<_>>();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\r', '\n']);
+
+            // A blank line dispatches the event, `complete` may come without data
+            if line.is_empty() {
+                if self.data.is_empty() && self.name.is_empty() {
+                    continue;
+                }
+                return Some(Event {
+                    name: match std::mem::take(&mut self.name) {
+                        name if name.is_empty() => "message".to_string(),
+                        name => name,
+                    },
+                    data: std::mem::take(&mut self.data).join("\n"),
+                });
+            }
+
+            let (field, value) = line.split_once(':').unwrap_or((line, ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match field {
+                "event" => self.name = value.to_string(),
+                "data" => self.data.push(value.to_string()),
+                // Comments keep the connection alive, ids and retries aren't used
+                _ => {}
+            }
+        }
+
+        None
+    }
+}
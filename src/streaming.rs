@@ -0,0 +1,103 @@
+use crate::client::{GQLClient, GraphQLResponse};
+use crate::error::GraphQLError;
+use crate::metrics;
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufReader, Read};
+use tokio::sync::mpsc;
+
+/// Body chunks read ahead of the deserializer
+const CHUNKS_IN_FLIGHT: usize = 16;
+
+impl GQLClient {
+    /// Deserializes the response while it is being received instead of buffering the body
+    ///
+    /// Memory use is bounded by the deserialized data rather than the size of the body, which
+    /// helps with very large results. Deserialization runs on tokio's blocking thread pool.
+    /// Errors don't include the raw body, since it isn't kept.
+    pub async fn query_streamed<K, T: Serialize>(
+        &self,
+        query: &str,
+        variables: T,
+    ) -> Result<K, GraphQLError>
+    where
+        K: for<'de> Deserialize<'de> + Send + 'static,
+    {
+        let request = self.request_for(query, None, variables)?;
+        let mut raw_response = self
+            .execute_request(request, &self.options(query, None))
+            .await?;
+        let measured = metrics::take(&mut raw_response);
+
+        let (sender, receiver) = mpsc::channel(CHUNKS_IN_FLIGHT);
+        let parser = tokio::task::spawn_blocking(move || {
+            let reader = BufReader::new(ChunkReader::new(receiver));
+            let mut deserializer = serde_json::Deserializer::from_reader(reader);
+            serde_path_to_error::deserialize::<_, GraphQLResponse<K>>(&mut deserializer)
+        });
+
+        let read = async move {
+            while let Some(chunk) = raw_response.chunk().await? {
+                // The deserializer stopped early and will report why
+                if sender.send(chunk).await.is_err() {
+                    break;
+                }
+            }
+            Ok::<_, GraphQLError>(())
+        }
+        .await;
+
+        let result = async {
+            let parsed = parser.await.map_err(|error| {
+                GraphQLError::Other(format!("Deserialization failed: {}", error))
+            })?;
+            // A body cut short by the network is reported as such rather than as invalid JSON
+            read?;
+
+            parsed
+                .map_err(|error| GraphQLError::deserialize(error, String::new()))?
+                .into_result()
+        };
+        metrics::count(result.await, measured.as_ref())
+    }
+}
+
+/// Blocking reader over the body chunks sent by the async side
+struct ChunkReader<B> {
+    receiver: mpsc::Receiver<B>,
+    chunk: Option<B>,
+    position: usize,
+}
+
+impl<B> ChunkReader<B> {
+    fn new(receiver: mpsc::Receiver<B>) -> Self {
+        Self {
+            receiver,
+            chunk: None,
+            position: 0,
+        }
+    }
+}
+
+impl<B: AsRef<[u8]>> Read for ChunkReader<B> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if let Some(chunk) = &self.chunk {
+                let remaining = &chunk.as_ref()[self.position..];
+                if !remaining.is_empty() {
+                    let len = remaining.len().min(buf.len());
+                    buf[..len].copy_from_slice(&remaining[..len]);
+                    self.position += len;
+                    return Ok(len);
+                }
+            }
+
+            match self.receiver.blocking_recv() {
+                Some(chunk) => {
+                    self.chunk = Some(chunk);
+                    self.position = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+    }
+}
@@ -0,0 +1,711 @@
+use crate::client::{from_body, from_value, GQLClient, GraphQLResponse};
+use crate::error::GraphQLError;
+use crate::retry::RetryPolicy;
+use crate::sse::EventSource;
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use futures_util::{stream, SinkExt, Stream, StreamExt};
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, SEC_WEBSOCKET_PROTOCOL};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, oneshot};
+use tokio_tungstenite::{
+    connect_async, tungstenite::client::IntoClientRequest, tungstenite::Message, MaybeTlsStream,
+    WebSocketStream,
+};
+
+type WebSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+type EventStream<K> =
+    Pin<Box<dyn Stream<Item = Result<SubscriptionEvent<K>, GraphQLError>> + Send>>;
+
+/// Stream of subscription results returned by `GQLClient::subscribe`
+///
+/// Reconnections are transparent, use `events` to be notified of them.
+pub struct Subscription<K> {
+    inner: EventStream<K>,
+}
+
+impl<K> Subscription<K> {
+    /// Stream of the results along with the reconnections of the WebSocket
+    pub fn events(self) -> impl Stream<Item = Result<SubscriptionEvent<K>, GraphQLError>> + Send {
+        self.inner
+    }
+}
+
+impl<K> Stream for Subscription<K> {
+    type Item = Result<K, GraphQLError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let inner = &mut self.get_mut().inner;
+        loop {
+            return match inner.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Ok(SubscriptionEvent::Reconnected))) => continue,
+                Poll::Ready(Some(Ok(SubscriptionEvent::Data(data)))) => Poll::Ready(Some(Ok(data))),
+                Poll::Ready(Some(Err(error))) => Poll::Ready(Some(Err(error))),
+                Poll::Ready(None) => Poll::Ready(None),
+                Poll::Pending => Poll::Pending,
+            };
+        }
+    }
+}
+
+/// Item of `Subscription::events`
+#[derive(Debug)]
+pub enum SubscriptionEvent<K> {
+    /// Result of the subscription
+    Data(K),
+    /// The WebSocket dropped and the subscription was sent again over a new one, events
+    /// published in between are missed
+    Reconnected,
+}
+
+/// Protocol spoken with the server, over a WebSocket unless stated otherwise
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SubscriptionProtocol {
+    /// Modern `graphql-transport-ws` protocol from the graphql-ws library
+    #[default]
+    GraphQLTransportWs,
+    /// Legacy `graphql-ws` protocol from subscriptions-transport-ws
+    GraphQLWs,
+    /// AWS AppSync real-time protocol
+    ///
+    /// The client headers (e.g. `x-api-key` or `authorization`) are sent in the connection
+    /// URL and with each subscription, as AppSync expects. IAM authorization isn't supported.
+    AppSync,
+    /// GraphQL over Server-Sent Events from the graphql-sse library, in distinct connections
+    /// mode
+    ///
+    /// Each subscription is a POST request to the GraphQL endpoint answered with an event
+    /// stream, which works behind proxies that don't allow WebSockets.
+    Sse,
+    /// GraphQL over Server-Sent Events in single connection mode
+    ///
+    /// An event stream is reserved with a PUT request and opened with a GET request before
+    /// the subscription is sent, for servers which only allow a single connection per client.
+    /// Dropping the stream before the subscription completes stops it with a DELETE request.
+    SseSingleConnection,
+}
+
+impl SubscriptionProtocol {
+    fn name(self) -> &'static str {
+        match self {
+            Self::GraphQLWs | Self::AppSync => "graphql-ws",
+            _ => "graphql-transport-ws",
+        }
+    }
+
+    fn start_message(self) -> &'static str {
+        match self {
+            Self::GraphQLWs | Self::AppSync => "start",
+            _ => "subscribe",
+        }
+    }
+
+    fn stop_message(self) -> &'static str {
+        match self {
+            Self::GraphQLWs | Self::AppSync => "stop",
+            _ => "complete",
+        }
+    }
+
+    fn data_message(self) -> &'static str {
+        match self {
+            Self::GraphQLWs | Self::AppSync => "data",
+            _ => "next",
+        }
+    }
+}
+
+/// Source of the `connection_init` payload, which graphql-ws servers commonly authenticate
+///
+/// The provider is asked for the payload each time a WebSocket is opened, reconnections
+/// included, so it can hand out a refreshed token. A fixed `Value` and async closures returning
+/// `Result<Value, GraphQLError>` implement it.
+///
+/// ```rust
+///use reqwest_graphql::{Client, GraphQLError};
+///use serde_json::json;
+///
+///let client = Client::builder()
+///    .endpoint("https://example.com/graphql")
+///    .connection_init_provider(|| async {
+///        Ok::<_, GraphQLError>(json!({ "authToken": "<some_token>" }))
+///    })
+///    .build()
+///    .unwrap();
+/// ```
+#[async_trait]
+pub trait ConnectionInitProvider: Send + Sync + 'static {
+    async fn payload(&self) -> Result<Value, GraphQLError>;
+}
+
+#[async_trait]
+impl<F, Fut> ConnectionInitProvider for F
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Value, GraphQLError>> + Send,
+{
+    async fn payload(&self) -> Result<Value, GraphQLError> {
+        self().await
+    }
+}
+
+#[async_trait]
+impl ConnectionInitProvider for Value {
+    async fn payload(&self) -> Result<Value, GraphQLError> {
+        Ok(self.clone())
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct ServerMessage {
+    #[serde(rename = "type")]
+    kind: String,
+    id: Option<String>,
+    payload: Option<Value>,
+}
+
+impl GQLClient {
+    /// Subscribes to the operation, over the WebSocket shared by the subscriptions of the
+    /// client and its clones
+    ///
+    /// The WebSocket is opened by the first subscription and closed once the last one ends.
+    /// Dropping the returned stream ends the subscription on the server.
+    pub fn subscribe<K, T: Serialize>(&self, query: &str, variables: T) -> Subscription<K>
+    where
+        K: for<'de> Deserialize<'de> + Send + 'static,
+    {
+        let protocol = self.subscription_protocol;
+        let mut url = websocket_endpoint(&self.endpoint);
+        let headers = self.header_map.clone();
+        let init_payload = self.connection_init.clone();
+        let payload = self
+            .request_body(query, None, variables)
+            .and_then(|body| self.envelope(&body));
+        #[cfg(feature = "validation")]
+        let payload = self.check_syntax(query).and(payload);
+
+        if let SubscriptionProtocol::Sse | SubscriptionProtocol::SseSingleConnection = protocol {
+            let source = EventSource {
+                client: self.client.clone(),
+                middlewares: self.middlewares.clone(),
+                transport: self.transport.clone(),
+                endpoint: self.endpoint.to_string(),
+                headers,
+                lifecycle: self.lifecycle.clone(),
+            };
+            let inner: Pin<Box<dyn Stream<Item = Result<K, GraphQLError>> + Send>> = match protocol
+            {
+                SubscriptionProtocol::Sse => Box::pin(source.distinct(payload)),
+                _ => Box::pin(source.single_connection(payload)),
+            };
+            return Subscription {
+                inner: Box::pin(inner.map(|result| result.map(SubscriptionEvent::Data))),
+            };
+        }
+
+        let mut payload = payload;
+        if protocol == SubscriptionProtocol::AppSync {
+            let authorization = appsync_authorization(&self.endpoint, &headers);
+            url = appsync_endpoint(&self.endpoint, &authorization);
+            payload = payload.map(|payload| {
+                json!({
+                    "data": payload.to_string(),
+                    "extensions": { "authorization": authorization }
+                })
+            });
+        }
+
+        let payload = match payload {
+            Ok(payload) => payload,
+            Err(error) => {
+                return Subscription {
+                    inner: Box::pin(stream::iter(Some(Err(error)))),
+                }
+            }
+        };
+        let connection = Connection {
+            protocol,
+            url,
+            headers,
+            init_payload,
+            keep_alive: self.subscription_keep_alive,
+            reconnect: self.subscription_reconnect.clone(),
+        };
+        let multiplexer = self.multiplexer.clone();
+        let lifecycle = self.lifecycle.clone();
+
+        // Registered once polled, so nothing is spawned for a subscription never used
+        let inner = stream::once(async move {
+            match lifecycle.is_closed() {
+                true => Err(GraphQLError::Shutdown),
+                false => Ok(multiplexer.subscribe(connection, payload)),
+            }
+        })
+        .flat_map(|registration| match registration {
+            Ok(registration) => stream::unfold(registration, |mut registration| async move {
+                let event = registration.events.recv().await?;
+                Some((event, registration))
+            })
+            .left_stream(),
+            Err(error) => stream::iter(Some(Err(error))).right_stream(),
+        })
+        .map(|event| match event? {
+            SubscriptionEvent::Data(payload) => GraphQLResponse::<K>::from_value(payload)
+                .and_then(GraphQLResponse::into_result)
+                .map(SubscriptionEvent::Data),
+            SubscriptionEvent::Reconnected => Ok(SubscriptionEvent::Reconnected),
+        });
+
+        Subscription {
+            inner: Box::pin(inner),
+        }
+    }
+}
+
+/// Everything needed to open the WebSocket, again after a reconnection
+#[derive(Clone)]
+struct Connection {
+    protocol: SubscriptionProtocol,
+    url: String,
+    headers: HeaderMap,
+    init_payload: Option<Arc<dyn ConnectionInitProvider>>,
+    keep_alive: Option<Duration>,
+    reconnect: Option<RetryPolicy>,
+}
+
+impl Connection {
+    /// Opens the WebSocket, returning it along with the window within which the server sends
+    /// something on a live connection
+    async fn open(&self) -> Result<(WebSocket, Option<Duration>), GraphQLError> {
+        let mut request = self.url.as_str().into_client_request()?;
+        request.headers_mut().extend(self.headers.clone());
+        request.headers_mut().insert(
+            SEC_WEBSOCKET_PROTOCOL,
+            HeaderValue::from_static(self.protocol.name()),
+        );
+
+        let init = match &self.init_payload {
+            Some(init_payload) => {
+                json!({ "type": "connection_init", "payload": init_payload.payload().await? })
+            }
+            None => json!({ "type": "connection_init" }),
+        };
+        let (mut socket, _) = connect_async(request).await?;
+        send(&mut socket, init).await?;
+
+        // Wait for the server to acknowledge the connection
+        loop {
+            let message = match receive(&mut socket, self.keep_alive).await? {
+                Some(message) => message,
+                None => {
+                    return Err(GraphQLError::Other(
+                        "Connection closed before acknowledgement".into(),
+                    ))
+                }
+            };
+
+            match message.kind.as_str() {
+                "connection_ack" => {
+                    // AppSync announces how often it sends `ka` messages
+                    let timeout = message.payload.as_ref().and_then(|payload| {
+                        payload["connectionTimeoutMs"]
+                            .as_u64()
+                            .map(Duration::from_millis)
+                    });
+                    return Ok((socket, self.keep_alive.or(timeout)));
+                }
+                "ping" => send(&mut socket, json!({ "type": "pong" })).await?,
+                "ka" => {}
+                "connection_error" => return Err(server_error(message.payload)),
+                kind => return Err(GraphQLError::Other(format!("Unexpected message: {}", kind))),
+            }
+        }
+    }
+
+    /// Opens the WebSocket, retrying with the backoff of the reconnect policy after a drop
+    ///
+    /// Each reconnection waits for the backoff first, so a server dropping every connection
+    /// isn't flooded. It fails once `max_attempts` reconnections in a row have failed.
+    async fn reopen(
+        &self,
+        reconnecting: bool,
+    ) -> Result<(WebSocket, Option<Duration>), GraphQLError> {
+        let mut failures = 0;
+        loop {
+            if let (true, Some(policy)) = (reconnecting, &self.reconnect) {
+                tokio::time::sleep(policy.backoff(failures + 1)).await;
+            }
+            match (self.open().await, &self.reconnect) {
+                (Ok(socket), _) => return Ok(socket),
+                (Err(_), Some(policy)) if reconnecting && failures + 1 < policy.max_attempts() => {
+                    failures += 1
+                }
+                (Err(error), _) => return Err(error),
+            }
+        }
+    }
+}
+
+type Events = mpsc::UnboundedSender<Result<SubscriptionEvent<Value>, GraphQLError>>;
+
+type Commands = Arc<Mutex<Option<mpsc::UnboundedSender<Command>>>>;
+
+enum Command {
+    Subscribe {
+        id: String,
+        payload: Value,
+        events: Events,
+    },
+    Stop {
+        id: String,
+    },
+    /// Completes the subscriptions and closes the WebSocket, then answers
+    Shutdown(oneshot::Sender<()>),
+}
+
+/// WebSocket shared by the subscriptions of a client, driven by a task while any is active
+#[derive(Default)]
+pub(crate) struct Multiplexer {
+    /// Commands of the running task
+    commands: Commands,
+    last_id: AtomicU64,
+}
+
+impl Multiplexer {
+    /// Adds the subscription to the WebSocket, opening one if none is
+    fn subscribe(&self, connection: Connection, payload: Value) -> Registration {
+        let id = (self.last_id.fetch_add(1, Ordering::Relaxed) + 1).to_string();
+        let (events, receiver) = mpsc::unbounded_channel();
+
+        // The task only stops while holding the lock, so the command can't get lost
+        let mut commands = self.commands.lock().unwrap();
+        let sender = match &*commands {
+            Some(sender) if !sender.is_closed() => sender.clone(),
+            _ => {
+                let (sender, receiver) = mpsc::unbounded_channel();
+                let driver = Driver {
+                    connection,
+                    subscriptions: HashMap::new(),
+                    socket: None,
+                    reconnecting: false,
+                };
+                tokio::spawn(driver.run(self.commands.clone(), receiver));
+                *commands = Some(sender.clone());
+                sender
+            }
+        };
+        let _ = sender.send(Command::Subscribe {
+            id: id.clone(),
+            payload,
+            events,
+        });
+
+        Registration {
+            id,
+            commands: sender,
+            events: receiver,
+        }
+    }
+
+    /// Completes the subscriptions and closes the WebSocket
+    pub(crate) async fn shutdown(&self) {
+        let commands = self.commands.lock().unwrap().take();
+        if let Some(commands) = commands {
+            let (done, closed) = oneshot::channel();
+            if commands.send(Command::Shutdown(done)).is_ok() {
+                let _ = closed.await;
+            }
+        }
+    }
+}
+
+/// Subscription of a stream, stopped on the server when dropped
+struct Registration {
+    id: String,
+    commands: mpsc::UnboundedSender<Command>,
+    events: mpsc::UnboundedReceiver<Result<SubscriptionEvent<Value>, GraphQLError>>,
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        let id = std::mem::take(&mut self.id);
+        let _ = self.commands.send(Command::Stop { id });
+    }
+}
+
+/// Task owning the WebSocket, routing its messages to the subscriptions by id
+struct Driver {
+    connection: Connection,
+    /// Payload and events of the active subscriptions by id
+    subscriptions: HashMap<String, (Value, Events)>,
+    socket: Option<(WebSocket, Option<Duration>)>,
+    reconnecting: bool,
+}
+
+impl Driver {
+    async fn run(mut self, slot: Commands, mut commands: mpsc::UnboundedReceiver<Command>) {
+        let mut shut_down = None;
+        loop {
+            let command = if self.subscriptions.is_empty() {
+                let mut slot = slot.lock().unwrap();
+                let command = commands.try_recv().ok();
+                if command.is_none() {
+                    *slot = None;
+                }
+                command
+            } else if let Some((socket, keep_alive)) = &mut self.socket {
+                let keep_alive = *keep_alive;
+                tokio::select! {
+                    command = commands.recv() => command,
+                    message = receive(socket, keep_alive) => {
+                        match message {
+                            Ok(Some(message)) => self.route(message).await,
+                            Ok(None) => self.dropped(None),
+                            Err(error) => self.dropped(Some(error)),
+                        }
+                        continue;
+                    },
+                }
+            } else {
+                self.connect().await;
+                continue;
+            };
+
+            match command {
+                Some(Command::Shutdown(done)) => {
+                    self.complete_all().await;
+                    shut_down = Some(done);
+                    break;
+                }
+                Some(command) => self.apply(command).await,
+                None => break,
+            }
+        }
+
+        if let Some((mut socket, _)) = self.socket.take() {
+            // The legacy protocol announces the end of the connection before closing it
+            if self.connection.protocol == SubscriptionProtocol::GraphQLWs {
+                let _ = send(&mut socket, json!({ "type": "connection_terminate" })).await;
+            }
+            let _ = socket.close(None).await;
+        }
+        if let Some(done) = shut_down {
+            let _ = done.send(());
+        }
+    }
+
+    /// Opens the WebSocket and sends the active subscriptions
+    async fn connect(&mut self) {
+        let socket = match self.connection.reopen(self.reconnecting).await {
+            Ok(socket) => socket,
+            Err(error) => {
+                for (_, (_, events)) in self.subscriptions.drain() {
+                    let _ = events.send(Err(error.duplicate()));
+                }
+                self.reconnecting = false;
+                return;
+            }
+        };
+
+        self.socket = Some(socket);
+        let reconnected = std::mem::take(&mut self.reconnecting);
+        let start = self.connection.protocol.start_message();
+        let mut messages = Vec::new();
+        for (id, (payload, events)) in &self.subscriptions {
+            if reconnected {
+                let _ = events.send(Ok(SubscriptionEvent::Reconnected));
+            }
+            messages.push(json!({ "id": id, "type": start, "payload": payload }));
+        }
+        for message in messages {
+            self.send(message).await;
+        }
+    }
+
+    async fn apply(&mut self, command: Command) {
+        match command {
+            Command::Subscribe {
+                id,
+                payload,
+                events,
+            } => {
+                let start = self.connection.protocol.start_message();
+                let message = json!({ "id": id, "type": start, "payload": payload });
+                self.subscriptions.insert(id, (payload, events));
+                self.send(message).await;
+            }
+            Command::Stop { id } => {
+                if self.subscriptions.remove(&id).is_some() {
+                    let message =
+                        json!({ "id": id, "type": self.connection.protocol.stop_message() });
+                    self.send(message).await;
+                }
+            }
+            Command::Shutdown(_) => unreachable!("handled by the run loop"),
+        }
+    }
+
+    /// Completes the active subscriptions on the server, ending their streams
+    async fn complete_all(&mut self) {
+        let stop = self.connection.protocol.stop_message();
+        let ids: Vec<_> = self.subscriptions.drain().map(|(id, _)| id).collect();
+        for id in ids {
+            self.send(json!({ "id": id, "type": stop })).await;
+        }
+    }
+
+    async fn route(&mut self, message: ServerMessage) {
+        let protocol = self.connection.protocol;
+        let id = message.id.as_deref().unwrap_or_default();
+
+        match message.kind.as_str() {
+            kind if kind == protocol.data_message() => {
+                if let Some((_, events)) = self.subscriptions.get(id) {
+                    let payload = message.payload.unwrap_or_default();
+                    let _ = events.send(Ok(SubscriptionEvent::Data(payload)));
+                }
+            }
+            "error" => {
+                if let Some((_, events)) = self.subscriptions.remove(id) {
+                    let _ = events.send(Err(server_error(message.payload)));
+                }
+            }
+            // Dropping the sender ends the stream of the subscription
+            "complete" => {
+                self.subscriptions.remove(id);
+            }
+            "ping" => self.send(json!({ "type": "pong" })).await,
+            _ => {}
+        }
+    }
+
+    /// Sends the message if the WebSocket is open, it is sent again after a reconnection
+    /// otherwise
+    async fn send(&mut self, message: Value) {
+        if let Some((socket, _)) = &mut self.socket {
+            if let Err(error) = send(socket, message).await {
+                self.dropped(Some(error));
+            }
+        }
+    }
+
+    /// Reconnects after the WebSocket closed or errored, or ends the subscriptions without a
+    /// reconnect policy
+    fn dropped(&mut self, error: Option<GraphQLError>) {
+        self.socket = None;
+        if self.connection.reconnect.is_some() {
+            self.reconnecting = true;
+            return;
+        }
+
+        for (_, (_, events)) in self.subscriptions.drain() {
+            if let Some(error) = &error {
+                let _ = events.send(Err(error.duplicate()));
+            }
+        }
+    }
+}
+
+/// Converts an `error` payload, which is a list of errors or a single error object
+fn server_error(payload: Option<Value>) -> GraphQLError {
+    let errors = match payload.unwrap_or_default() {
+        Value::Array(errors) => errors,
+        error => vec![error],
+    };
+
+    match from_value(Value::Array(errors)) {
+        Ok(errors) => GraphQLError::from_json(errors),
+        Err(error) => error,
+    }
+}
+
+async fn send(socket: &mut WebSocket, message: Value) -> Result<(), GraphQLError> {
+    socket.send(Message::Text(message.to_string())).await?;
+    Ok(())
+}
+
+/// Returns the next protocol message, or None once the socket is closed
+///
+/// Fails with `GraphQLError::Timeout` when nothing, not even a ping frame, arrives within the
+/// keep-alive window.
+async fn receive(
+    socket: &mut WebSocket,
+    keep_alive: Option<Duration>,
+) -> Result<Option<ServerMessage>, GraphQLError> {
+    loop {
+        let message = match keep_alive {
+            Some(window) => tokio::time::timeout(window, socket.next())
+                .await
+                .map_err(|_| GraphQLError::Timeout)?,
+            None => socket.next().await,
+        };
+
+        match message {
+            Some(message) => match message? {
+                Message::Text(text) => return from_body(text).map(Some),
+                Message::Close(_) => return Ok(None),
+                _ => {}
+            },
+            None => return Ok(None),
+        }
+    }
+}
+
+/// Host of the GraphQL endpoint along with the client headers, which AppSync authorizes
+fn appsync_authorization(endpoint: &str, headers: &HeaderMap) -> Value {
+    let host = reqwest::Url::parse(endpoint)
+        .ok()
+        .and_then(|url| url.host_str().map(String::from))
+        .unwrap_or_default();
+
+    let mut authorization = serde_json::Map::new();
+    authorization.insert("host".into(), host.into());
+    for (name, value) in headers {
+        if let Ok(value) = value.to_str() {
+            let name = if name == AUTHORIZATION {
+                "Authorization"
+            } else {
+                name.as_str()
+            };
+            authorization.insert(name.into(), value.into());
+        }
+    }
+    Value::Object(authorization)
+}
+
+/// Real-time endpoint, with the authorization passed as base64 encoded `header` parameter
+fn appsync_endpoint(endpoint: &str, authorization: &Value) -> String {
+    // Custom domains serve the real-time API on the /realtime path
+    let endpoint = if endpoint.contains("appsync-api") {
+        endpoint.replacen("appsync-api", "appsync-realtime-api", 1)
+    } else {
+        format!("{}/realtime", endpoint.trim_end_matches('/'))
+    };
+
+    format!(
+        "{}?header={}&payload=e30=",
+        websocket_endpoint(&endpoint),
+        BASE64.encode(authorization.to_string())
+    )
+}
+
+fn websocket_endpoint(endpoint: &str) -> String {
+    if let Some(rest) = endpoint.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = endpoint.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else {
+        endpoint.to_string()
+    }
+}
@@ -0,0 +1,35 @@
+use crate::error::GraphQLError;
+use async_trait::async_trait;
+use reqwest::{Client, Request, Response};
+
+/// Sends the requests which made it through the middleware chain
+///
+/// The reqwest client is used by default. Other implementations, set on the client builder,
+/// can send requests over a Unix socket, another HTTP stack or no network at all, converting
+/// from and to `http` types with `http::Request::try_from` and `Response::from`.
+///
+/// ```rust
+///use reqwest::{Request, Response};
+///use reqwest_graphql::{async_trait, GraphQLError, Transport};
+///
+///struct Offline;
+///
+///#[async_trait]
+///impl Transport for Offline {
+///    async fn execute(&self, _request: Request) -> Result<Response, GraphQLError> {
+///        let body = r#"{ "data": { "status": "offline" } }"#;
+///        Ok(Response::from(http::Response::new(body)))
+///    }
+///}
+/// ```
+#[async_trait]
+pub trait Transport: Send + Sync + 'static {
+    async fn execute(&self, request: Request) -> Result<Response, GraphQLError>;
+}
+
+#[async_trait]
+impl Transport for Client {
+    async fn execute(&self, request: Request) -> Result<Response, GraphQLError> {
+        Ok(Client::execute(self, request).await?)
+    }
+}
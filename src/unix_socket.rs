@@ -0,0 +1,101 @@
+use crate::error::GraphQLError;
+use crate::transport::Transport;
+use async_trait::async_trait;
+use hyper::client::conn;
+use reqwest::header::{HeaderValue, HOST};
+use reqwest::{Request, Response};
+use std::path::{Path, PathBuf};
+use tokio::net::UnixStream;
+
+/// Transport sending requests to a server listening on a Unix domain socket
+///
+/// The path and query of the endpoint are used for the HTTP request, its host only for the
+/// `Host` header. Each request opens a new connection to the socket.
+///
+/// ```rust
+///use reqwest_graphql::Client;
+///
+///let client = Client::builder()
+///    .endpoint("http://localhost/graphql")
+///    .unix_socket("/var/run/api.sock")
+///    .build()
+///    .unwrap();
+/// ```
+pub struct UnixSocket {
+    path: PathBuf,
+}
+
+impl UnixSocket {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    async fn send(&self, request: Request) -> Result<Response, GraphQLError> {
+        let body = match request.body() {
+            Some(body) => body
+                .as_bytes()
+                .ok_or_else(|| {
+                    GraphQLError::Other(
+                        "Unix socket transport requires a buffered request body".into(),
+                    )
+                })?
+                .to_vec(),
+            None => Vec::new(),
+        };
+
+        let url = request.url();
+        let target = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        let mut builder = http::Request::builder()
+            .method(request.method().clone())
+            .uri(target);
+        if let Some(headers) = builder.headers_mut() {
+            headers.extend(request.headers().clone());
+            if !headers.contains_key(HOST) {
+                let host = url.host_str().unwrap_or("localhost");
+                headers.insert(
+                    HOST,
+                    HeaderValue::from_str(host).unwrap_or(HeaderValue::from_static("localhost")),
+                );
+            }
+        }
+        let request = builder
+            .body(hyper::Body::from(body))
+            .map_err(|error| GraphQLError::Other(format!("Invalid request: {}", error)))?;
+
+        let stream = UnixStream::connect(&self.path)
+            .await
+            .map_err(|error| self.error(error))?;
+        let (mut sender, connection) = conn::handshake(stream)
+            .await
+            .map_err(|error| self.error(error))?;
+        // Drives the connection until the response body has been read
+        tokio::spawn(connection);
+
+        let response = sender
+            .send_request(request)
+            .await
+            .map_err(|error| self.error(error))?;
+        Ok(Response::from(response))
+    }
+
+    fn error<E: std::fmt::Display>(&self, error: E) -> GraphQLError {
+        GraphQLError::Other(format!("Unix socket {}: {}", self.path.display(), error))
+    }
+}
+
+#[async_trait]
+impl Transport for UnixSocket {
+    async fn execute(&self, request: Request) -> Result<Response, GraphQLError> {
+        match request.timeout().copied() {
+            Some(timeout) => tokio::time::timeout(timeout, self.send(request))
+                .await
+                .map_err(|_| GraphQLError::Timeout)?,
+            None => self.send(request).await,
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use crate::client::{parse_response_with_meta, GQLClient};
+use crate::error::GraphQLError;
+use crate::response::GQLResponse;
+use bytes::Bytes;
+use futures_util::{stream, Stream, StreamExt, TryStreamExt};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::multipart::{Form, Part};
+use reqwest::Body;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::error::Error;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::io::AsyncRead;
+use tokio_util::io::ReaderStream;
+
+/// Size of the chunks in which uploads with a progress callback are sent
+const CHUNK_SIZE: usize = 64 * 1024;
+
+type Progress = Arc<dyn Fn(u64, u64) + Send + Sync>;
+
+type ByteStream =
+    Pin<Box<dyn Stream<Item = Result<Bytes, Box<dyn Error + Send + Sync>>> + Send + Sync>>;
+
+/// File sent as an `Upload` scalar following the GraphQL multipart request spec
+///
+/// https://github.com/jaydenseric/graphql-multipart-request-spec
+pub struct Upload {
+    file_name: String,
+    mime: Option<String>,
+    data: Data,
+    progress: Option<Progress>,
+}
+
+enum Data {
+    Bytes(Vec<u8>),
+    /// Read as the request is sent, never held in memory as a whole
+    Stream {
+        stream: ByteStream,
+        length: u64,
+    },
+}
+
+impl Upload {
+    pub fn new(file_name: impl Into<String>, data: impl Into<Vec<u8>>) -> Self {
+        Self {
+            file_name: file_name.into(),
+            mime: None,
+            data: Data::Bytes(data.into()),
+            progress: None,
+        }
+    }
+
+    /// File read from a stream of chunks while the request is sent, e.g. for files too large to
+    /// be loaded in memory
+    ///
+    /// `length` must be the exact number of bytes of the stream, the request fails otherwise.
+    /// Requests streaming a file aren't retried.
+    pub fn from_stream<S, B, E>(file_name: impl Into<String>, stream: S, length: u64) -> Self
+    where
+        S: Stream<Item = Result<B, E>> + Send + Sync + 'static,
+        B: Into<Bytes> + 'static,
+        E: Into<Box<dyn Error + Send + Sync>> + 'static,
+    {
+        let stream = stream.map_ok(Into::into).map_err(Into::into);
+        Self {
+            file_name: file_name.into(),
+            mime: None,
+            data: Data::Stream {
+                stream: Box::pin(stream),
+                length,
+            },
+            progress: None,
+        }
+    }
+
+    /// File read from an `AsyncRead`, such as a `tokio::fs::File`, while the request is sent
+    ///
+    /// `length` must be the exact number of bytes read, the request fails otherwise.
+    pub fn from_reader<R>(file_name: impl Into<String>, reader: R, length: u64) -> Self
+    where
+        R: AsyncRead + Send + Sync + 'static,
+    {
+        Self::from_stream(file_name, ReaderStream::new(reader), length)
+    }
+
+    pub fn mime(mut self, mime: impl Into<String>) -> Self {
+        self.mime = Some(mime.into());
+        self
+    }
+
+    /// Calls back with the bytes of the file sent so far and its total size as it is sent,
+    /// e.g. to render a progress bar
+    ///
+    /// The file is then streamed in chunks, and the request isn't retried.
+    pub fn on_progress<F>(mut self, progress: F) -> Self
+    where
+        F: Fn(u64, u64) + Send + Sync + 'static,
+    {
+        self.progress = Some(Arc::new(progress));
+        self
+    }
+
+    fn into_part(self) -> Result<Part, GraphQLError> {
+        let part = match (self.data, self.progress) {
+            (Data::Bytes(data), None) => Part::bytes(data),
+            (Data::Bytes(data), Some(progress)) => {
+                let length = data.len() as u64;
+                let chunks = stream::iter(chunks(Bytes::from(data))).map(Ok::<_, std::io::Error>);
+                streamed(report(chunks, length, progress), length)
+            }
+            (Data::Stream { stream, length }, Some(progress)) => {
+                streamed(report(stream, length, progress), length)
+            }
+            (Data::Stream { stream, length }, None) => streamed(stream, length),
+        };
+        let part = part.file_name(self.file_name);
+
+        match self.mime {
+            Some(mime) => Ok(part.mime_str(&mime)?),
+            None => Ok(part),
+        }
+    }
+}
+
+impl GQLClient {
+    /// Sends the operation as a multipart request
+    ///
+    /// Each file is paired with its object path in the operation, for example
+    /// `variables.file` or `variables.files.0`. The value at that path is replaced by null.
+    pub async fn query_with_files<K, T: Serialize>(
+        &self,
+        query: &str,
+        variables: T,
+        files: Vec<(&str, Upload)>,
+    ) -> Result<K, GraphQLError>
+    where
+        K: for<'de> Deserialize<'de>,
+    {
+        #[cfg(feature = "validation")]
+        self.check_syntax(query)?;
+
+        let mut operations = self.envelope(&self.request_body(query, None, variables)?)?;
+        let mut map = Map::new();
+        let mut parts = Vec::new();
+
+        for (index, (path, upload)) in files.into_iter().enumerate() {
+            set_null(&mut operations, path)?;
+            map.insert(index.to_string(), Value::from(vec![path]));
+            parts.push((index.to_string(), upload.into_part()?));
+        }
+
+        // The spec requires operations and map to precede the file parts
+        let mut form = Form::new()
+            .text("operations", operations.to_string())
+            .text("map", Value::Object(map).to_string());
+        for (name, part) in parts {
+            form = form.part(name, part);
+        }
+
+        // The multipart boundary must be the only content type
+        let mut headers = self.header_map.clone();
+        headers.remove(CONTENT_TYPE);
+
+        let request = self
+            .client
+            .post(&self.endpoint)
+            .headers(headers)
+            .multipart(form);
+
+        let raw_response = self
+            .execute_request(request, &self.options(query, None))
+            .await?;
+        parse_response_with_meta(raw_response)
+            .await
+            .map(GQLResponse::into_data)
+    }
+}
+
+fn streamed<S, E>(stream: S, length: u64) -> Part
+where
+    S: Stream<Item = Result<Bytes, E>> + Send + Sync + 'static,
+    E: Into<Box<dyn Error + Send + Sync>> + 'static,
+{
+    Part::stream_with_length(Body::wrap_stream(stream), length)
+}
+
+/// Splits the data in chunks, so the progress is reported as they are sent
+fn chunks(data: Bytes) -> Vec<Bytes> {
+    (0..data.len())
+        .step_by(CHUNK_SIZE)
+        .map(|start| data.slice(start..data.len().min(start + CHUNK_SIZE)))
+        .collect()
+}
+
+/// Reports the bytes handed to the connection so far
+fn report<S, E>(
+    stream: S,
+    total: u64,
+    progress: Progress,
+) -> impl Stream<Item = Result<Bytes, E>> + Send + Sync + 'static
+where
+    S: Stream<Item = Result<Bytes, E>> + Send + Sync + 'static,
+{
+    let mut sent = 0;
+    stream.map_ok(move |chunk| {
+        sent += chunk.len() as u64;
+        progress(sent, total);
+        chunk
+    })
+}
+
+fn set_null(operations: &mut Value, path: &str) -> Result<(), GraphQLError> {
+    let mut target = &mut *operations;
+
+    for segment in path.split('.') {
+        target = match target {
+            Value::Object(object) => object.entry(segment).or_insert(Value::Null),
+            Value::Array(array) => segment
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| array.get_mut(index))
+                .ok_or_else(|| invalid_path(path))?,
+            _ => return Err(invalid_path(path)),
+        };
+    }
+
+    *target = Value::Null;
+    Ok(())
+}
+
+fn invalid_path(path: &str) -> GraphQLError {
+    GraphQLError::Other(format!("Invalid upload path: {}", path))
+}
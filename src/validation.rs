@@ -0,0 +1,418 @@
+use crate::client::GQLClient;
+use crate::error::GraphQLError;
+use crate::introspection::{Schema, Type, TypeKind};
+use graphql_parser::query::{
+    self, parse_query, Definition, Field, FragmentDefinition, OperationDefinition, Selection,
+    SelectionSet, TypeCondition, Value,
+};
+use graphql_parser::Pos;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display, Formatter};
+
+/// Document which isn't valid GraphQL
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyntaxError {
+    message: String,
+}
+
+impl SyntaxError {
+    /// Parser message, including the position of the error
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for SyntaxError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for SyntaxError {}
+
+/// Problem found while validating a document against a schema
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationError {
+    message: String,
+    position: Option<(usize, usize)>,
+}
+
+impl ValidationError {
+    fn new(message: String, position: Pos) -> Self {
+        Self {
+            message,
+            position: Some((position.line, position.column)),
+        }
+    }
+
+    /// Description of the problem
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Line and column of the offending part of the document, if known
+    pub fn position(&self) -> Option<(usize, usize)> {
+        self.position
+    }
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.position {
+            Some((line, column)) => write!(f, "{} at {}:{}", self.message, line, column),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl From<SyntaxError> for ValidationError {
+    fn from(error: SyntaxError) -> Self {
+        Self {
+            message: error.message,
+            position: None,
+        }
+    }
+}
+
+impl GQLClient {
+    /// Checks that the document is syntactically valid, without contacting the server
+    pub fn validate(&self, query: &str) -> Result<(), SyntaxError> {
+        parse_query::<&str>(query)
+            .map(|_| ())
+            .map_err(|error| SyntaxError {
+                message: error.to_string().trim_end().to_string(),
+            })
+    }
+
+    /// Checks that the fields, arguments and variable types used by the document exist in
+    /// the schema, without contacting the server
+    ///
+    /// Every problem found is returned, a document which fails to parse yields a single error.
+    pub fn validate_against_schema(
+        &self,
+        query: &str,
+        schema: &Schema,
+    ) -> Result<(), Vec<ValidationError>> {
+        let document = parse_query::<&str>(query).map_err(|error| {
+            vec![ValidationError::from(SyntaxError {
+                message: error.to_string().trim_end().to_string(),
+            })]
+        })?;
+
+        let mut validator = Validator {
+            schema,
+            fragments: document
+                .definitions
+                .iter()
+                .filter_map(|definition| match definition {
+                    Definition::Fragment(fragment) => Some((fragment.name, fragment)),
+                    Definition::Operation(_) => None,
+                })
+                .collect(),
+            errors: Vec::new(),
+        };
+        for definition in &document.definitions {
+            if let Definition::Operation(operation) = definition {
+                validator.operation(operation);
+            }
+        }
+
+        if validator.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(validator.errors)
+        }
+    }
+
+    /// Validates the document before sending it, if enabled on the builder
+    pub(crate) fn check_syntax(&self, query: &str) -> Result<(), GraphQLError> {
+        if self.validate_queries {
+            self.validate(query)?;
+        }
+        Ok(())
+    }
+}
+
+/// Walks the operations of a document, following fragment spreads
+struct Validator<'s, 'd> {
+    schema: &'s Schema,
+    fragments: HashMap<&'d str, &'d FragmentDefinition<'d, &'d str>>,
+    errors: Vec<ValidationError>,
+}
+
+/// Variables declared by the operation being validated
+struct Scope<'d> {
+    variables: HashSet<&'d str>,
+    spread: HashSet<&'d str>,
+}
+
+impl<'s, 'd> Validator<'s, 'd> {
+    fn error(&mut self, message: String, position: Pos) {
+        let error = ValidationError::new(message, position);
+        // Fragments spread by several operations would report the same problem again
+        if !self.errors.contains(&error) {
+            self.errors.push(error);
+        }
+    }
+
+    fn operation(&mut self, operation: &'d OperationDefinition<'d, &'d str>) {
+        let (kind, root, position, variables, selection_set) = match operation {
+            OperationDefinition::SelectionSet(selection_set) => (
+                "query",
+                &self.schema.query_type,
+                selection_set.span.0,
+                &[][..],
+                selection_set,
+            ),
+            OperationDefinition::Query(query) => (
+                "query",
+                &self.schema.query_type,
+                query.position,
+                &query.variable_definitions[..],
+                &query.selection_set,
+            ),
+            OperationDefinition::Mutation(mutation) => (
+                "mutation",
+                &self.schema.mutation_type,
+                mutation.position,
+                &mutation.variable_definitions[..],
+                &mutation.selection_set,
+            ),
+            OperationDefinition::Subscription(subscription) => (
+                "subscription",
+                &self.schema.subscription_type,
+                subscription.position,
+                &subscription.variable_definitions[..],
+                &subscription.selection_set,
+            ),
+        };
+
+        for variable in variables {
+            let type_name = named_type(&variable.var_type);
+            match self.schema.get_type(type_name) {
+                Some(input)
+                    if matches!(
+                        input.kind,
+                        TypeKind::Scalar | TypeKind::Enum | TypeKind::InputObject
+                    ) => {}
+                Some(_) => self.error(
+                    format!(
+                        "Variable `${}` has type `{}` which is not an input type",
+                        variable.name, type_name
+                    ),
+                    variable.position,
+                ),
+                None => self.error(
+                    format!(
+                        "Variable `${}` has unknown type `{}`",
+                        variable.name, type_name
+                    ),
+                    variable.position,
+                ),
+            }
+        }
+
+        let root = match root
+            .as_ref()
+            .and_then(|root| self.schema.get_type(&root.name))
+        {
+            Some(root) => root,
+            None => {
+                self.error(
+                    format!("Schema does not support {} operations", kind),
+                    position,
+                );
+                return;
+            }
+        };
+
+        let mut scope = Scope {
+            variables: variables.iter().map(|variable| variable.name).collect(),
+            spread: HashSet::new(),
+        };
+        self.selection_set(root, selection_set, &mut scope);
+    }
+
+    fn selection_set(
+        &mut self,
+        parent: &'s Type,
+        selection_set: &'d SelectionSet<'d, &'d str>,
+        scope: &mut Scope<'d>,
+    ) {
+        for selection in &selection_set.items {
+            match selection {
+                Selection::Field(field) => self.field(parent, field, scope),
+                Selection::FragmentSpread(spread) => {
+                    let fragment = match self.fragments.get(spread.fragment_name) {
+                        Some(fragment) => *fragment,
+                        None => {
+                            self.error(
+                                format!("Unknown fragment `{}`", spread.fragment_name),
+                                spread.position,
+                            );
+                            continue;
+                        }
+                    };
+                    // Each fragment is checked once per operation, which also stops cycles
+                    if scope.spread.insert(fragment.name) {
+                        let TypeCondition::On(type_name) = fragment.type_condition;
+                        if let Some(target) = self.type_condition(type_name, fragment.position) {
+                            self.selection_set(target, &fragment.selection_set, scope);
+                        }
+                    }
+                }
+                Selection::InlineFragment(fragment) => {
+                    let target = match &fragment.type_condition {
+                        Some(TypeCondition::On(type_name)) => {
+                            self.type_condition(type_name, fragment.position)
+                        }
+                        None => Some(parent),
+                    };
+                    if let Some(target) = target {
+                        self.selection_set(target, &fragment.selection_set, scope);
+                    }
+                }
+            }
+        }
+    }
+
+    fn type_condition(&mut self, type_name: &str, position: Pos) -> Option<&'s Type> {
+        match self.schema.get_type(type_name) {
+            Some(target) if is_composite(target) => Some(target),
+            Some(_) => {
+                self.error(
+                    format!(
+                        "Fragment cannot condition on non composite type `{}`",
+                        type_name
+                    ),
+                    position,
+                );
+                None
+            }
+            None => {
+                self.error(format!("Unknown type `{}`", type_name), position);
+                None
+            }
+        }
+    }
+
+    fn field(&mut self, parent: &'s Type, field: &'d Field<'d, &'d str>, scope: &mut Scope<'d>) {
+        for (_, value) in &field.arguments {
+            self.variables(value, field.position, scope);
+        }
+
+        // Meta fields aren't part of the introspected types
+        if field.name.starts_with("__") {
+            return;
+        }
+
+        let parent_name = parent.name.as_deref().unwrap_or_default();
+        let definition = match parent.field(field.name) {
+            Some(definition) => definition,
+            None => {
+                self.error(
+                    format!("Unknown field `{}` on type `{}`", field.name, parent_name),
+                    field.position,
+                );
+                return;
+            }
+        };
+
+        for (name, _) in &field.arguments {
+            if !definition
+                .args
+                .iter()
+                .any(|argument| argument.name == *name)
+            {
+                self.error(
+                    format!(
+                        "Unknown argument `{}` on field `{}.{}`",
+                        name, parent_name, field.name
+                    ),
+                    field.position,
+                );
+            }
+        }
+        for argument in &definition.args {
+            let required =
+                argument.value_type.kind == TypeKind::NonNull && argument.default_value.is_none();
+            if required
+                && !field
+                    .arguments
+                    .iter()
+                    .any(|(name, _)| *name == argument.name)
+            {
+                self.error(
+                    format!(
+                        "Missing required argument `{}` on field `{}.{}`",
+                        argument.name, parent_name, field.name
+                    ),
+                    field.position,
+                );
+            }
+        }
+
+        let type_name = definition.field_type.named_type().unwrap_or_default();
+        let field_type = match self.schema.get_type(type_name) {
+            Some(field_type) => field_type,
+            None => return,
+        };
+        let selected = !field.selection_set.items.is_empty();
+        if is_composite(field_type) {
+            if selected {
+                self.selection_set(field_type, &field.selection_set, scope);
+            } else {
+                self.error(
+                    format!(
+                        "Field `{}` of type `{}` must have a selection of subfields",
+                        field.name, type_name
+                    ),
+                    field.position,
+                );
+            }
+        } else if selected {
+            self.error(
+                format!(
+                    "Field `{}` of type `{}` cannot have a selection of subfields",
+                    field.name, type_name
+                ),
+                field.position,
+            );
+        }
+    }
+
+    fn variables(&mut self, value: &'d Value<'d, &'d str>, position: Pos, scope: &Scope<'d>) {
+        match value {
+            Value::Variable(name) if !scope.variables.contains(name) => {
+                self.error(format!("Variable `${}` is not defined", name), position)
+            }
+            Value::List(values) => {
+                for value in values {
+                    self.variables(value, position, scope);
+                }
+            }
+            Value::Object(fields) => {
+                for value in fields.values() {
+                    self.variables(value, position, scope);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn named_type<'d>(var_type: &query::Type<'d, &'d str>) -> &'d str {
+    match var_type {
+        query::Type::NamedType(name) => name,
+        query::Type::ListType(inner) | query::Type::NonNullType(inner) => named_type(inner),
+    }
+}
+
+fn is_composite(schema_type: &Type) -> bool {
+    matches!(
+        schema_type.kind,
+        TypeKind::Object | TypeKind::Interface | TypeKind::Union
+    )
+}
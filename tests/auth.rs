@@ -0,0 +1,260 @@
+mod server;
+
+use reqwest::header::HeaderMap;
+use reqwest_graphql::{Client, GraphQLError, ResponseCache, TokenRefresh};
+use serde::Deserialize;
+use serde_json::json;
+use server::Response;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub viewer: String,
+}
+
+#[tokio::test]
+pub async fn sends_bearer_token() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .auth_bearer("secret")
+        .build()
+        .unwrap();
+
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    assert_eq!(
+        server.requests()[0].header("authorization"),
+        Some("Bearer secret")
+    );
+}
+
+#[tokio::test]
+pub async fn sends_basic_credentials() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let with_password = Client::builder()
+        .endpoint(&server.url)
+        .basic_auth("aladdin", Some("open sesame"))
+        .build()
+        .unwrap();
+    let without_password = Client::builder()
+        .endpoint(&server.url)
+        .basic_auth("aladdin", None::<&str>)
+        .build()
+        .unwrap();
+
+    with_password.query::<Viewer>("{ viewer }").await.unwrap();
+    without_password
+        .query::<Viewer>("{ viewer }")
+        .await
+        .unwrap();
+
+    let requests = server.requests();
+    assert_eq!(
+        requests[0].header("authorization"),
+        Some("Basic YWxhZGRpbjpvcGVuIHNlc2FtZQ==")
+    );
+    assert_eq!(
+        requests[1].header("authorization"),
+        Some("Basic YWxhZGRpbjo=")
+    );
+}
+
+#[tokio::test]
+pub async fn asks_token_provider_for_every_request() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .auth_bearer("static")
+        .token_provider(move || {
+            let call = counter.fetch_add(1, Ordering::SeqCst);
+            async move { Ok(format!("token-{}", call)) }
+        })
+        .build()
+        .unwrap();
+
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests[0].header("authorization"), Some("Bearer token-0"));
+    assert_eq!(requests[1].header("authorization"), Some("Bearer token-1"));
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+pub async fn asks_header_provider_for_every_request() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .header("x-tenant", "default")
+        .header("x-app", "tests")
+        .header_provider(move || {
+            let call = counter.fetch_add(1, Ordering::SeqCst);
+            async move {
+                let mut headers = HeaderMap::new();
+                headers.insert("x-tenant", format!("tenant-{}", call).parse().unwrap());
+                headers.insert("x-api-key", "rotated".parse().unwrap());
+                Ok(headers)
+            }
+        })
+        .build()
+        .unwrap();
+
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests[0].header("x-tenant"), Some("tenant-0"));
+    assert_eq!(requests[1].header("x-tenant"), Some("tenant-1"));
+    assert_eq!(requests[1].header("x-api-key"), Some("rotated"));
+    assert_eq!(requests[1].header("x-app"), Some("tests"));
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+pub async fn fails_when_header_provider_fails() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .header_provider(|| async { Err(GraphQLError::Other("vault unavailable".into())) })
+        .build()
+        .unwrap();
+
+    let error = client.query::<Viewer>("{ viewer }").await.unwrap_err();
+
+    assert!(error.to_string().contains("vault unavailable"));
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+pub async fn fails_when_token_provider_fails() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .token_provider(|| async { Err(GraphQLError::Other("expired".into())) })
+        .build()
+        .unwrap();
+
+    let error = client.query::<Viewer>("{ viewer }").await.unwrap_err();
+
+    assert!(matches!(error, GraphQLError::Other(message) if message == "expired"));
+    assert!(server.requests().is_empty());
+}
+
+#[test]
+pub fn rejects_invalid_bearer_token() {
+    let result = Client::builder()
+        .endpoint("http://localhost")
+        .auth_bearer("line\nbreak")
+        .build();
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+pub async fn refreshes_token_on_unauthorized() {
+    let server = server::serve(|request| match request.header("authorization") {
+        Some("Bearer fresh") => Response::json(json!({ "data": { "viewer": "me" } })),
+        _ => Response::text(401, "Unauthorized"),
+    })
+    .await;
+    let refreshes = Arc::new(AtomicUsize::new(0));
+    let counter = refreshes.clone();
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .middleware(
+            TokenRefresh::new(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                async { Ok("fresh".to_string()) }
+            })
+            .token("expired"),
+        )
+        .build()
+        .unwrap();
+
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(requests[0].header("authorization"), Some("Bearer expired"));
+    assert_eq!(requests[1].header("authorization"), Some("Bearer fresh"));
+    assert_eq!(requests[2].header("authorization"), Some("Bearer fresh"));
+    assert_eq!(refreshes.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+pub async fn refreshes_token_on_unauthenticated_error() {
+    let server = server::serve(|request| match request.header("authorization") {
+        Some("Bearer fresh") => Response::json(json!({ "data": { "viewer": "me" } })),
+        _ => Response::json(json!({ "errors": [{
+            "message": "Token expired",
+            "extensions": { "code": "UNAUTHENTICATED" }
+        }] })),
+    })
+    .await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .auth_bearer("expired")
+        .middleware(TokenRefresh::new(|| async { Ok("fresh".to_string()) }))
+        .build()
+        .unwrap();
+
+    let data = client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    assert_eq!(data.viewer, "me");
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+pub async fn retries_only_once_after_refresh() {
+    let server = server::serve(|_| Response::text(401, "Unauthorized")).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .middleware(TokenRefresh::new(|| async { Ok("fresh".to_string()) }))
+        .build()
+        .unwrap();
+
+    let result = client.query::<Viewer>("{ viewer }").await;
+
+    assert!(result.is_err());
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+pub async fn never_shares_responses_between_provided_headers() {
+    let server = server::serve(|request| {
+        let viewer = request.header("x-tenant").unwrap_or_default();
+        Response::json(json!({ "data": { "viewer": viewer } }))
+    })
+    .await;
+    let calls = AtomicUsize::new(0);
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .cache(ResponseCache::new(Duration::from_secs(60)))
+        .etags(true)
+        .deduplicate(true)
+        .header_provider(move || {
+            let tenant = ["a", "b"][calls.fetch_add(1, Ordering::SeqCst) % 2];
+            async move {
+                let mut headers = HeaderMap::new();
+                headers.insert("x-tenant", tenant.parse().unwrap());
+                Ok(headers)
+            }
+        })
+        .build()
+        .unwrap();
+
+    let a = client.query::<Viewer>("{ viewer }").await.unwrap();
+    let b = client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    assert_eq!((a.viewer.as_str(), b.viewer.as_str()), ("a", "b"));
+    assert_eq!(server.requests().len(), 2);
+}
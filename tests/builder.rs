@@ -0,0 +1,98 @@
+mod server;
+
+use reqwest_graphql::Client;
+use serde::Deserialize;
+use serde_json::json;
+use server::Response;
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub viewer: String,
+}
+
+#[tokio::test]
+pub async fn sends_configured_headers_and_user_agent() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .header("x-tenant", "acme")
+        .user_agent("builder-test")
+        .build()
+        .unwrap();
+
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    let request = &server.requests()[0];
+    assert_eq!(request.header("x-tenant"), Some("acme"));
+    assert_eq!(request.header("user-agent"), Some("builder-test"));
+}
+
+#[tokio::test]
+pub async fn sends_default_user_agent() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let expected = format!("reqwest-graphql/{}", env!("CARGO_PKG_VERSION"));
+
+    Client::new(&server.url)
+        .query::<Viewer>("{ viewer }")
+        .await
+        .unwrap();
+    Client::builder()
+        .endpoint(&server.url)
+        .build()
+        .unwrap()
+        .query::<Viewer>("{ viewer }")
+        .await
+        .unwrap();
+
+    for request in server.requests() {
+        assert_eq!(request.header("user-agent"), Some(expected.as_str()));
+    }
+}
+
+#[test]
+pub fn rejects_missing_endpoint() {
+    assert!(Client::builder().build().is_err());
+}
+
+#[test]
+pub fn rejects_invalid_header() {
+    let error = Client::builder()
+        .endpoint("http://localhost")
+        .header("bad header", "value")
+        .build()
+        .err()
+        .unwrap();
+
+    assert!(error.message().contains("bad header"));
+}
+
+#[tokio::test]
+pub async fn sends_requests_through_proxy() {
+    let proxy = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoint("http://graphql.internal/api")
+        .proxy(&proxy.url)
+        .build()
+        .unwrap();
+
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    let request = &proxy.requests()[0];
+    assert_eq!(request.path, "http://graphql.internal/api");
+    assert_eq!(request.header("host"), Some("graphql.internal"));
+}
+
+#[tokio::test]
+pub async fn bypasses_proxy_for_listed_hosts() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .proxy("http://127.0.0.1:1")
+        .no_proxy_hosts("127.0.0.1")
+        .build()
+        .unwrap();
+
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    assert_eq!(server.requests()[0].path, "/graphql");
+}
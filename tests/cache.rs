@@ -0,0 +1,290 @@
+mod server;
+
+use reqwest_graphql::{
+    async_trait, CacheStore, CachedResponse, Client, MemoryStore, ResponseCache,
+};
+use serde::Deserialize;
+use serde_json::json;
+use server::Response;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub viewer: String,
+}
+
+#[tokio::test]
+pub async fn serves_repeated_queries_from_cache() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .cache(ResponseCache::new(Duration::from_secs(60)))
+        .build()
+        .unwrap();
+
+    for _ in 0..3 {
+        let data = client.query::<Viewer>("{ viewer }").await.unwrap();
+        assert_eq!(data.viewer, "me");
+    }
+    client
+        .query_with_vars::<Viewer, _>("query($id: ID) { viewer }", json!({ "id": 1 }))
+        .await
+        .unwrap();
+
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+pub async fn keeps_entries_per_headers() {
+    let server = server::serve(|request| {
+        let viewer = request.header("authorization").unwrap_or_default();
+        Response::json(json!({ "data": { "viewer": viewer } }))
+    })
+    .await;
+    let cache = ResponseCache::new(Duration::from_secs(60));
+    let client = |token: &str| {
+        Client::builder()
+            .endpoint(&server.url)
+            .header("authorization", token)
+            .cache(cache.clone())
+            .build()
+            .unwrap()
+    };
+    let (alice, bob) = (client("alice"), client("bob"));
+
+    let data = alice.query::<Viewer>("{ viewer }").await.unwrap();
+    assert_eq!(data.viewer, "alice");
+    let data = bob.query::<Viewer>("{ viewer }").await.unwrap();
+    assert_eq!(data.viewer, "bob");
+    let data = alice.query::<Viewer>("{ viewer }").await.unwrap();
+    assert_eq!(data.viewer, "alice");
+
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+pub async fn keeps_entries_per_query_headers() {
+    let server = server::serve(|request| {
+        let viewer = request.header("x-tenant").unwrap_or_default();
+        Response::json(json!({ "data": { "viewer": viewer } }))
+    })
+    .await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .cache(ResponseCache::new(Duration::from_secs(60)))
+        .etags(true)
+        .deduplicate(true)
+        .build()
+        .unwrap();
+
+    for tenant in ["a", "b", "a"] {
+        let headers = HashMap::from([("x-tenant", tenant)]);
+        let data = client
+            .query_with_vars_and_headers::<Viewer, _>("{ viewer }", (), headers)
+            .await
+            .unwrap();
+        assert_eq!(data.viewer, tenant);
+    }
+
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+pub async fn keeps_entries_per_request_headers() {
+    let server = server::serve(|request| {
+        let viewer = request.header("x-tenant").unwrap_or_default();
+        Response::json(json!({ "data": { "viewer": viewer } })).delay(Duration::from_millis(50))
+    })
+    .await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .cache(ResponseCache::new(Duration::from_secs(60)))
+        .deduplicate(true)
+        .build()
+        .unwrap();
+    let query = |tenant: &'static str| {
+        client
+            .request("{ viewer }")
+            .header("x-tenant", tenant)
+            .send::<Viewer>()
+    };
+
+    let (a, b) = tokio::join!(query("a"), query("b"));
+    assert_eq!(a.unwrap().viewer, "a");
+    assert_eq!(b.unwrap().viewer, "b");
+    assert_eq!(query("b").await.unwrap().viewer, "b");
+
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+pub async fn never_caches_mutations_or_errors() {
+    let server = server::serve(|request| {
+        if request.text().contains("mutation") {
+            Response::json(json!({ "data": { "viewer": "me" } }))
+        } else {
+            Response::json(json!({ "data": null, "errors": [{ "message": "denied" }] }))
+        }
+    })
+    .await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .cache(ResponseCache::new(Duration::from_secs(60)))
+        .build()
+        .unwrap();
+
+    for _ in 0..2 {
+        client.query::<Viewer>("mutation { viewer }").await.unwrap();
+        assert!(client.query::<Viewer>("{ viewer }").await.is_err());
+    }
+
+    assert_eq!(server.requests().len(), 4);
+}
+
+#[tokio::test]
+pub async fn expires_and_evicts_entries() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .cache(ResponseCache::new(Duration::from_millis(50)).max_entries(1))
+        .build()
+        .unwrap();
+
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+    assert_eq!(server.requests().len(), 1);
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+    assert_eq!(server.requests().len(), 2);
+
+    client
+        .query::<Viewer>("query Other { viewer }")
+        .await
+        .unwrap();
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+    assert_eq!(server.requests().len(), 4);
+}
+
+#[derive(Default)]
+struct Log {
+    keys: Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl CacheStore for Log {
+    async fn get(&self, _key: &str) -> Option<CachedResponse> {
+        None
+    }
+
+    async fn set(&self, key: &str, response: CachedResponse, _ttl: Duration) {
+        assert_eq!(response.status, 200);
+        assert!(response.body.contains("me"));
+        self.keys.lock().unwrap().push(key.to_string());
+    }
+
+    async fn invalidate(&self, _key: &str) {}
+}
+
+#[tokio::test]
+pub async fn uses_custom_store() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let store = Arc::new(Log::default());
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .cache(ResponseCache::new(Duration::from_secs(60)).store(store.clone()))
+        .build()
+        .unwrap();
+
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    let keys = store.keys.lock().unwrap().clone();
+    assert_eq!(server.requests().len(), 2);
+    assert_eq!(keys.len(), 2);
+    assert_eq!(keys[0], keys[1]);
+    assert_eq!(keys[0].len(), 64);
+}
+
+#[tokio::test]
+pub async fn invalidates_queries() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let store = Arc::new(MemoryStore::new(10));
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .cache(ResponseCache::new(Duration::from_secs(60)).store(store.clone()))
+        .build()
+        .unwrap();
+
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+    client.invalidate_query("{ viewer }", ()).await.unwrap();
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+    store.clear();
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test]
+pub async fn revalidates_stale_responses_in_background() {
+    let calls = AtomicUsize::new(0);
+    let server = server::serve(move |_| {
+        let viewer = format!("v{}", calls.fetch_add(1, Ordering::SeqCst));
+        Response::json(json!({ "data": { "viewer": viewer } }))
+    })
+    .await;
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .cache(
+            ResponseCache::new(Duration::from_millis(50))
+                .stale_while_revalidate(Duration::from_secs(60))
+                .on_revalidate(move |revalidated| {
+                    sender.send(revalidated.data::<Viewer>().unwrap()).unwrap();
+                }),
+        )
+        .build()
+        .unwrap();
+
+    let data = client.query::<Viewer>("{ viewer }").await.unwrap();
+    assert_eq!(data.viewer, "v0");
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let data = client.query::<Viewer>("{ viewer }").await.unwrap();
+    assert_eq!(data.viewer, "v0");
+
+    let fresh = receiver.recv().await.unwrap();
+    assert_eq!(fresh.viewer, "v1");
+
+    let data = client.query::<Viewer>("{ viewer }").await.unwrap();
+    assert_eq!(data.viewer, "v1");
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+pub async fn revalidates_with_etags() {
+    let server = server::serve(|request| match request.header("if-none-match") {
+        Some("\"v1\"") => Response::text(304, ""),
+        _ => Response::json(json!({ "data": { "viewer": "me" } })).header("etag", "\"v1\""),
+    })
+    .await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .etags(true)
+        .build()
+        .unwrap();
+
+    for _ in 0..2 {
+        let data = client.query::<Viewer>("{ viewer }").await.unwrap();
+        assert_eq!(data.viewer, "me");
+    }
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].header("if-none-match"), None);
+    assert_eq!(requests[1].header("if-none-match"), Some("\"v1\""));
+}
@@ -0,0 +1,148 @@
+mod server;
+
+use reqwest_graphql::{Cassette, CassetteMode, Client, GraphQLError, Upload};
+use serde_json::{json, Value};
+use server::Response;
+use std::path::PathBuf;
+
+fn cassette_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir()
+        .join(format!("reqwest-graphql-{}", std::process::id()))
+        .join(format!("{}.json", name));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn client(endpoint: &str, cassette: Cassette) -> Client {
+    Client::builder()
+        .endpoint(endpoint)
+        .header("authorization", "Bearer secret")
+        .middleware(cassette)
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+pub async fn records_and_replays_responses() {
+    let server =
+        server::serve(|_| Response::json(json!({ "data": { "viewer": { "id": "1" } } }))).await;
+    let path = cassette_path("records_and_replays");
+
+    let recording = client(&server.url, Cassette::new(&path));
+    let recorded = recording.query::<Value>("{ viewer { id } }").await.unwrap();
+    assert_eq!(server.requests().len(), 1);
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert!(contents.contains("viewer"));
+    assert!(!contents.contains("secret"));
+
+    let replaying = client(&server.url, Cassette::new(&path).mode(CassetteMode::Replay));
+    let replayed = replaying
+        .query::<Value>("{\n  viewer {\n    id\n  }\n}")
+        .await
+        .unwrap();
+
+    assert_eq!(replayed, recorded);
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+pub async fn keys_interactions_by_variables() {
+    let server = server::serve(|request| {
+        let id = request.json()["variables"]["id"].clone();
+        Response::json(json!({ "data": { "post": { "id": id } } }))
+    })
+    .await;
+    let path = cassette_path("keys_by_variables");
+    let client = client(&server.url, Cassette::new(&path));
+    let query = "query ($id: ID!) { post(id: $id) { id } }";
+
+    for id in ["1", "2", "1"] {
+        let data = client
+            .query_with_vars::<Value, _>(query, json!({ "id": id }))
+            .await
+            .unwrap();
+        assert_eq!(data["post"]["id"], id);
+    }
+
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+pub async fn fails_unrecorded_requests_when_replaying() {
+    let server = server::serve(|_| Response::json(json!({ "data": {} }))).await;
+    let path = cassette_path("fails_unrecorded");
+    let client = client(&server.url, Cassette::new(&path).mode(CassetteMode::Replay));
+
+    let error = client
+        .query::<Value>("{ viewer { id } }")
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, GraphQLError::Other(message) if message.contains("No interaction")));
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+pub async fn rerecords_in_record_mode() {
+    let server =
+        server::serve(|_| Response::json(json!({ "data": { "viewer": { "id": "1" } } }))).await;
+    let path = cassette_path("rerecords");
+    let client = client(&server.url, Cassette::new(&path).mode(CassetteMode::Record));
+
+    client.query::<Value>("{ viewer { id } }").await.unwrap();
+    client.query::<Value>("{ viewer { id } }").await.unwrap();
+
+    assert_eq!(server.requests().len(), 2);
+    let contents: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(contents["interactions"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+pub async fn records_and_replays_batches() {
+    let server = server::serve(|_| {
+        Response::json(json!([
+            { "data": { "viewer": { "id": "1" } } },
+            { "data": { "viewer": { "id": "1" } } }
+        ]))
+    })
+    .await;
+    let path = cassette_path("batches");
+    let operations = [("{ viewer { id } }", ()), ("{ viewer { id } }", ())];
+
+    let recording = client(&server.url, Cassette::new(&path));
+    let recorded = recording
+        .query_batch::<Value, _>(&operations)
+        .await
+        .unwrap();
+    assert_eq!(server.requests().len(), 1);
+
+    let replaying = client(&server.url, Cassette::new(&path).mode(CassetteMode::Replay));
+    let replayed = replaying
+        .query_batch::<Value, _>(&operations)
+        .await
+        .unwrap();
+
+    assert_eq!(replayed.len(), 2);
+    assert_eq!(replayed[0].as_ref().unwrap(), recorded[0].as_ref().unwrap());
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+pub async fn sends_uploads() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "upload": true } }))).await;
+    let path = cassette_path("uploads");
+    let client = client(&server.url, Cassette::new(&path));
+
+    let data = client
+        .query_with_files::<Value, _>(
+            "mutation ($file: Upload!) { upload(file: $file) }",
+            json!({ "file": null }),
+            vec![("variables.file", Upload::new("a.txt", "data"))],
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(data["upload"], true);
+    assert_eq!(server.requests().len(), 1);
+}
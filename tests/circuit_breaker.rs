@@ -0,0 +1,83 @@
+mod server;
+
+use reqwest_graphql::{CircuitBreaker, Client};
+use serde::Deserialize;
+use serde_json::json;
+use server::Response;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub viewer: String,
+}
+
+#[tokio::test]
+pub async fn opens_after_consecutive_failures() {
+    let server = server::serve(|_| Response::text(500, "down")).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .circuit_breaker(CircuitBreaker::new(2))
+        .build()
+        .unwrap();
+
+    for _ in 0..2 {
+        let error = client.query::<Viewer>("{ viewer }").await.unwrap_err();
+        assert!(!error.is_circuit_open());
+    }
+    let error = client.query::<Viewer>("{ viewer }").await.unwrap_err();
+
+    assert!(error.is_circuit_open());
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+pub async fn closes_after_successful_probe() {
+    let healthy = Arc::new(AtomicBool::new(false));
+    let server = server::serve({
+        let healthy = healthy.clone();
+        move |_| match healthy.load(Ordering::SeqCst) {
+            true => Response::json(json!({ "data": { "viewer": "me" } })),
+            false => Response::text(503, "unavailable"),
+        }
+    })
+    .await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .circuit_breaker(CircuitBreaker::new(1).cool_down(Duration::from_millis(50)))
+        .build()
+        .unwrap();
+
+    client.query::<Viewer>("{ viewer }").await.unwrap_err();
+    let error = client.query::<Viewer>("{ viewer }").await.unwrap_err();
+    assert!(error.is_circuit_open());
+
+    healthy.store(true, Ordering::SeqCst);
+    tokio::time::sleep(Duration::from_millis(60)).await;
+
+    for _ in 0..2 {
+        let data = client.query::<Viewer>("{ viewer }").await.unwrap();
+        assert_eq!(data.viewer, "me");
+    }
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test]
+pub async fn reopens_after_failed_probe() {
+    let server = server::serve(|_| Response::text(502, "bad gateway")).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .circuit_breaker(CircuitBreaker::new(1).cool_down(Duration::from_millis(50)))
+        .build()
+        .unwrap();
+
+    client.query::<Viewer>("{ viewer }").await.unwrap_err();
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    let probe = client.query::<Viewer>("{ viewer }").await.unwrap_err();
+    let error = client.query::<Viewer>("{ viewer }").await.unwrap_err();
+
+    assert!(!probe.is_circuit_open());
+    assert!(error.is_circuit_open());
+    assert_eq!(server.requests().len(), 2);
+}
@@ -0,0 +1,761 @@
+mod server;
+
+use reqwest_graphql::{vars, CancellationToken, Client, GraphQLError, GraphQLErrorMessage};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use server::Response;
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub viewer: String,
+}
+
+#[tokio::test]
+pub async fn reuses_connection_across_queries() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::new(&server.url);
+
+    for _ in 0..3 {
+        let data = client.query::<Viewer>("{ viewer }").await.unwrap();
+        assert_eq!(data.viewer, "me");
+    }
+
+    assert_eq!(server.requests().len(), 3);
+    assert_eq!(server.connections(), 1);
+}
+
+#[tokio::test]
+pub async fn opens_new_connections_without_idle_pool() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .pool_max_idle_per_host(0)
+        .build()
+        .unwrap();
+
+    for _ in 0..3 {
+        client.query::<Viewer>("{ viewer }").await.unwrap();
+    }
+
+    assert_eq!(server.connections(), 3);
+}
+
+#[tokio::test]
+pub async fn closes_connections_idle_for_too_long() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .pool_idle_timeout(Duration::from_millis(50))
+        .tcp_keepalive(Duration::from_secs(30))
+        .build()
+        .unwrap();
+
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    assert_eq!(server.connections(), 2);
+}
+
+#[tokio::test]
+pub async fn shares_clones_across_tasks() {
+    fn assert_shareable<T: Clone + Send + Sync + 'static>(_: &T) {}
+
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::new(&server.url);
+    assert_shareable(&client);
+
+    let tasks = (0..3)
+        .map(|_| {
+            let client = client.clone();
+            tokio::spawn(async move { client.query::<Viewer>("{ viewer }").await })
+        })
+        .collect::<Vec<_>>();
+    for task in tasks {
+        assert_eq!(task.await.unwrap().unwrap().viewer, "me");
+    }
+
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test]
+pub async fn uses_provided_reqwest_client() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let http_client = reqwest::Client::builder()
+        .user_agent("custom-agent")
+        .build()
+        .unwrap();
+    let client = Client::with_client(&server.url, http_client);
+
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    assert_eq!(
+        server.requests()[0].header("user-agent"),
+        Some("custom-agent")
+    );
+}
+
+#[tokio::test]
+pub async fn sends_provided_headers() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let headers = HashMap::from([("x-tenant", "acme")]);
+    let client = Client::try_new_with_headers(&server.url, headers).unwrap();
+
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    assert_eq!(server.requests()[0].header("x-tenant"), Some("acme"));
+}
+
+#[test]
+pub fn reports_invalid_headers() {
+    let headers = HashMap::from([
+        ("x-tenant", "acme"),
+        ("bad name", "value"),
+        ("x-bad-value", "line\nbreak"),
+    ]);
+
+    match Client::try_new_with_headers("http://localhost/graphql", headers) {
+        Err(GraphQLError::Other(message)) => {
+            assert_eq!(message, "Invalid header: bad name, x-bad-value")
+        }
+        _ => panic!("Expected invalid header error"),
+    }
+}
+
+#[tokio::test]
+pub async fn sends_batched_operations() {
+    let server = server::serve(|request| {
+        let operations = request.json();
+        assert_eq!(operations.as_array().unwrap().len(), 2);
+
+        Response::json(json!([
+            { "data": { "viewer": operations[0]["variables"]["name"] } },
+            { "errors": [{ "message": "Not found" }] }
+        ]))
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    let results = client
+        .query_batch::<Viewer, _>(&[
+            (
+                "query ($name: String) { viewer(name: $name) }",
+                json!({ "name": "first" }),
+            ),
+            (
+                "query ($name: String) { viewer(name: $name) }",
+                json!({ "name": "second" }),
+            ),
+        ])
+        .await
+        .unwrap();
+
+    assert_eq!(server.requests().len(), 1);
+    assert_eq!(results[0].as_ref().unwrap().viewer, "first");
+    assert!(results[1].is_err());
+}
+
+#[tokio::test]
+pub async fn runs_queries_with_bounded_concurrency() {
+    let server = server::serve(|request| {
+        let name = request.json()["variables"]["name"].clone();
+        Response::json(json!({ "data": { "viewer": name } })).delay(Duration::from_millis(20))
+    })
+    .await;
+    let client = Client::new(&server.url);
+    let names = ["a", "b", "c", "d", "e"];
+
+    let results = client
+        .query_many::<Viewer, _, _>(
+            names.iter().map(|name| {
+                (
+                    "query ($name: String) { viewer(name: $name) }",
+                    json!({ "name": name }),
+                )
+            }),
+            2,
+        )
+        .await;
+
+    let viewers: Vec<String> = results.into_iter().map(|r| r.unwrap().viewer).collect();
+    assert_eq!(viewers, names);
+    assert_eq!(server.requests().len(), 5);
+    assert!(server.connections() <= 2);
+}
+
+#[tokio::test]
+pub async fn fails_with_timeout_error() {
+    let server = server::serve(|_| {
+        Response::json(json!({ "data": { "viewer": "me" } })).delay(Duration::from_secs(5))
+    })
+    .await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .timeout(Duration::from_millis(50))
+        .build()
+        .unwrap();
+
+    let error = client.query::<Viewer>("{ viewer }").await.err().unwrap();
+
+    assert!(error.is_timeout());
+    assert!(!error.is_connect_timeout());
+}
+
+#[tokio::test]
+pub async fn fails_with_connect_timeout_error() {
+    // A listener which never accepts, once its backlog is full further connections hang
+    let socket = tokio::net::TcpSocket::new_v4().unwrap();
+    socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+    let listener = socket.listen(1).unwrap();
+    let address = listener.local_addr().unwrap();
+    let mut backlog = Vec::new();
+    while let Ok(Ok(stream)) = tokio::time::timeout(
+        Duration::from_millis(100),
+        tokio::net::TcpStream::connect(address),
+    )
+    .await
+    {
+        backlog.push(stream);
+    }
+
+    let client = Client::builder()
+        .endpoint(&format!("http://{}", address))
+        .connect_timeout(Duration::from_millis(50))
+        .timeout(Duration::from_secs(5))
+        .build()
+        .unwrap();
+
+    let error = client.query::<Viewer>("{ viewer }").await.err().unwrap();
+
+    assert!(error.is_connect_timeout());
+    assert!(error.is_timeout());
+    drop(listener);
+}
+
+#[tokio::test]
+pub async fn speaks_http2_with_prior_knowledge() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let preface = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut preface = [0; 24];
+        tokio::io::AsyncReadExt::read_exact(&mut stream, &mut preface)
+            .await
+            .unwrap();
+        preface
+    });
+    let client = Client::builder()
+        .endpoint(&format!("http://{}", address))
+        .http2_prior_knowledge()
+        .http2_adaptive_window(true)
+        .timeout(Duration::from_millis(500))
+        .build()
+        .unwrap();
+
+    let _ = client.query::<Viewer>("{ viewer }").await;
+
+    assert_eq!(&preface.await.unwrap(), b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n");
+}
+
+#[tokio::test]
+pub async fn overrides_options_per_request() {
+    let server = server::serve(|request| {
+        let viewer = request.json()["variables"]["name"].clone();
+        Response::json(json!({ "data": { "viewer": viewer } }))
+    })
+    .await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .header("x-tenant", "default")
+        .header("x-app", "tests")
+        .build()
+        .unwrap();
+
+    let data = client
+        .request("query ($name: String) { viewer(name: $name) }")
+        .var("name", "override")
+        .header("x-tenant", "a")
+        .timeout(Duration::from_secs(5))
+        .send::<Viewer>()
+        .await
+        .unwrap();
+
+    let request = &server.requests()[0];
+    assert_eq!(data.viewer, "override");
+    assert_eq!(request.header("x-tenant"), Some("a"));
+    assert_eq!(request.header("x-app"), Some("tests"));
+}
+
+#[tokio::test]
+pub async fn sends_headers_of_single_query() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .header("x-tenant", "default")
+        .header("x-app", "tests")
+        .build()
+        .unwrap();
+
+    let headers = HashMap::from([("x-tenant", "a"), ("accept-language", "fr")]);
+    let data = client
+        .query_with_vars_and_headers::<Viewer, _>("{ viewer }", (), headers)
+        .await
+        .unwrap();
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    let requests = server.requests();
+    assert_eq!(data.viewer, "me");
+    assert_eq!(requests[0].header("x-tenant"), Some("a"));
+    assert_eq!(requests[0].header("x-app"), Some("tests"));
+    assert_eq!(requests[0].header("accept-language"), Some("fr"));
+    assert_eq!(requests[1].header("x-tenant"), Some("default"));
+    assert_eq!(requests[1].header("accept-language"), None);
+}
+
+#[tokio::test]
+pub async fn rejects_invalid_headers_of_single_query() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::new(&server.url);
+
+    let headers = HashMap::from([("x-tenant", "line\nbreak")]);
+    let error = client
+        .query_with_vars_and_headers::<Viewer, _>("{ viewer }", (), headers)
+        .await
+        .unwrap_err();
+
+    assert!(error.to_string().contains("Invalid header: x-tenant"));
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+pub async fn applies_per_request_timeout() {
+    let server = server::serve(|_| {
+        Response::json(json!({ "data": { "viewer": "me" } })).delay(Duration::from_secs(5))
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    let error = client
+        .request("{ viewer }")
+        .timeout(Duration::from_millis(50))
+        .send::<Viewer>()
+        .await
+        .err()
+        .unwrap();
+
+    assert!(error.is_timeout());
+}
+
+#[tokio::test]
+pub async fn returns_status_and_headers() {
+    let server = server::serve(|_| {
+        Response::json(json!({ "data": { "viewer": "me" } })).header("x-ratelimit-remaining", "42")
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    let response = client
+        .query_with_meta::<Viewer, _>("{ viewer }", ())
+        .await
+        .unwrap();
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.headers["x-ratelimit-remaining"], "42");
+    assert_eq!(response.data.viewer, "me");
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Profile {
+    pub viewer: String,
+    pub avatar: Option<String>,
+}
+
+#[tokio::test]
+pub async fn returns_response_extensions() {
+    let server = server::serve(|_| {
+        Response::json(json!({
+            "data": { "viewer": "me" },
+            "extensions": { "cost": { "requestedQueryCost": 12 } }
+        }))
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    let response = client
+        .query_with_meta::<Viewer, _>("{ viewer }", ())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        response.extensions.unwrap()["cost"]["requestedQueryCost"],
+        12
+    );
+}
+
+#[tokio::test]
+pub async fn returns_partial_data_with_errors() {
+    let server = server::serve(|_| {
+        Response::json(json!({
+            "data": { "viewer": "me", "avatar": null },
+            "errors": [{ "message": "Avatar service unavailable", "path": ["avatar"] }]
+        }))
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    let (data, errors) = client
+        .query_partial::<Profile, _>("{ viewer avatar }", ())
+        .await
+        .unwrap();
+
+    let data = data.unwrap();
+    assert_eq!(data.viewer, "me");
+    assert!(data.avatar.is_none());
+    assert_eq!(errors.len(), 1);
+}
+
+#[tokio::test]
+pub async fn returns_raw_response_document() {
+    let body = json!({
+        "data": { "viewer": "me", "avatar": null },
+        "errors": [{ "message": "Avatar service unavailable", "path": ["avatar"] }],
+        "extensions": { "cost": 2 }
+    });
+    let response = body.clone();
+    let server = server::serve(move |_| Response::json(response.clone())).await;
+    let client = Client::new(&server.url);
+
+    let raw = client
+        .query_raw(
+            "query Profile($id: ID!) { viewer avatar }",
+            json!({ "id": "1" }),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(raw, body);
+    assert_eq!(server.requests()[0].json()["variables"]["id"], "1");
+}
+
+#[tokio::test]
+pub async fn returns_data_as_raw_json() {
+    let server = server::serve(|_| {
+        Response::text(200, r#"{"data":{"viewer":"me","tags":[1, 2]}}"#)
+            .header("content-type", "application/json")
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    let data = client.query_raw_data("{ viewer tags }", ()).await.unwrap();
+
+    assert_eq!(data.get(), r#"{"viewer":"me","tags":[1, 2]}"#);
+}
+
+fn query_params(path: &str) -> Vec<(String, String)> {
+    reqwest::Url::parse(&format!("http://localhost{}", path))
+        .unwrap()
+        .query_pairs()
+        .into_owned()
+        .collect()
+}
+
+#[tokio::test]
+pub async fn sends_queries_as_get() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .use_get_for_queries(true)
+        .build()
+        .unwrap();
+
+    let query = r#"
+      # Fragments come first
+      fragment ViewerFields on Query { viewer }
+      query Viewer($name: String = "{") { ...ViewerFields }
+    "#;
+    client
+        .query_with_vars::<Viewer, _>(query, json!({ "name": "me" }))
+        .await
+        .unwrap();
+    client.query::<Viewer>("mutation { viewer }").await.unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests[0].method, "GET");
+    assert_eq!(
+        query_params(&requests[0].path),
+        vec![
+            ("query".to_string(), query.to_string()),
+            ("variables".to_string(), r#"{"name":"me"}"#.to_string()),
+        ]
+    );
+    assert_eq!(requests[1].method, "POST");
+}
+
+#[tokio::test]
+pub async fn modifies_request_bodies_with_hook() {
+    let server = server::serve(|request| {
+        let viewers = match request.json() {
+            Value::Array(operations) => json!(operations
+                .iter()
+                .map(|_| json!({ "data": { "viewer": "me" } }))
+                .collect::<Vec<_>>()),
+            _ => json!({ "data": { "viewer": "me" } }),
+        };
+        Response::json(viewers)
+    })
+    .await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .on_request_body(|body| {
+            body["extensions"] = json!({ "clientInfo": { "name": "tests" } });
+            body.as_object_mut().unwrap().remove("variables");
+        })
+        .build()
+        .unwrap();
+
+    client
+        .query_with_vars::<Viewer, _>("{ viewer }", json!({ "unused": true }))
+        .await
+        .unwrap();
+    client
+        .query_batch::<Viewer, _>(&[("{ viewer }", ()), ("{ viewer }", ())])
+        .await
+        .unwrap();
+
+    let requests = server.requests();
+    assert_eq!(
+        requests[0].json(),
+        json!({ "query": "{ viewer }", "extensions": { "clientInfo": { "name": "tests" } } })
+    );
+    for operation in requests[1].json().as_array().unwrap() {
+        assert_eq!(operation["extensions"]["clientInfo"]["name"], "tests");
+    }
+}
+
+#[tokio::test]
+pub async fn sends_hooked_fields_as_get_parameters() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .use_get_for_queries(true)
+        .on_request_body(|body| {
+            body["extensions"] = json!({ "clientInfo": { "name": "tests" } });
+            body["locale"] = json!("fr");
+        })
+        .build()
+        .unwrap();
+
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    assert_eq!(
+        query_params(&server.requests()[0].path),
+        vec![
+            ("query".to_string(), "{ viewer }".to_string()),
+            (
+                "extensions".to_string(),
+                r#"{"clientInfo":{"name":"tests"}}"#.to_string()
+            ),
+            ("locale".to_string(), "fr".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+pub async fn transforms_responses_with_hook() {
+    let server = server::serve(
+        |request| match request.json()["variables"]["fail"].as_bool() {
+            Some(true) => {
+                Response::json(json!({ "result": { "errors": [{ "message": "Denied" }] } }))
+                    .status(400)
+            }
+            _ => Response::json(json!({ "result": { "data": { "viewer": "me" } } })),
+        },
+    )
+    .await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .on_response_body(|body| *body = body["result"].take())
+        .build()
+        .unwrap();
+
+    let data = client
+        .query_with_vars::<Viewer, _>("{ viewer }", json!({ "fail": false }))
+        .await
+        .unwrap();
+    let error = client
+        .query_with_vars::<Viewer, _>("{ viewer }", json!({ "fail": true }))
+        .await
+        .unwrap_err();
+    let raw = client.query_raw("{ viewer }", ()).await.unwrap();
+
+    assert_eq!(data.viewer, "me");
+    assert!(matches!(
+        &error.json().unwrap()[0],
+        GraphQLErrorMessage::ConventionalError { message, .. } if message == "Denied"
+    ));
+    assert_eq!(raw, json!({ "data": { "viewer": "me" } }));
+}
+
+#[tokio::test]
+pub async fn sends_documents_as_application_graphql() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .use_graphql_content_type(true)
+        .build()
+        .unwrap();
+
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+    client
+        .query_with_vars::<Viewer, _>("query($id: ID) { viewer }", json!({ "id": "1" }))
+        .await
+        .unwrap();
+
+    let requests = server.requests();
+    assert_eq!(
+        requests[0].header("content-type"),
+        Some("application/graphql")
+    );
+    assert_eq!(requests[0].text(), "{ viewer }");
+    assert_eq!(requests[1].header("content-type"), Some("application/json"));
+    assert_eq!(requests[1].json()["variables"]["id"], "1");
+}
+
+#[tokio::test]
+pub async fn sends_operation_name() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::new(&server.url);
+    let document = "query First { viewer } query Second { viewer }";
+
+    client
+        .query_with_operation::<Viewer, _>(document, "Second", ())
+        .await
+        .unwrap();
+    client
+        .request(document)
+        .operation_name("First")
+        .send::<Viewer>()
+        .await
+        .unwrap();
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests[0].json()["operationName"], "Second");
+    assert_eq!(requests[1].json()["operationName"], "First");
+    assert!(requests[2].json().get("operationName").is_none());
+}
+
+#[tokio::test]
+pub async fn cancels_in_flight_requests() {
+    let server = server::serve(|_| {
+        Response::json(json!({ "data": { "viewer": "me" } })).delay(Duration::from_secs(5))
+    })
+    .await;
+    let client = Client::new(&server.url);
+    let token = CancellationToken::new();
+
+    let canceller = token.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        canceller.cancel();
+    });
+
+    let error = client
+        .request("{ viewer }")
+        .cancellation_token(token)
+        .send::<Viewer>()
+        .await
+        .unwrap_err();
+
+    assert!(error.is_cancelled());
+}
+
+#[tokio::test]
+pub async fn sends_variables_built_with_macro() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::new(&server.url);
+    let name = "Leanne";
+
+    client
+        .query_with_vars::<Viewer, _>("{ viewer }", vars! { "id": 5, "name": name })
+        .await
+        .unwrap();
+
+    assert_eq!(
+        server.requests()[0].json()["variables"],
+        json!({ "id": 5, "name": "Leanne" })
+    );
+}
+
+#[tokio::test]
+pub async fn sends_json_variables() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::new(&server.url);
+
+    client
+        .query_with_json_vars::<Viewer>("{ viewer }", json!({ "id": 5 }))
+        .await
+        .unwrap();
+    let error = client
+        .query_with_json_vars::<Viewer>("{ viewer }", json!([5]))
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, GraphQLError::Other(_)));
+    assert_eq!(server.requests().len(), 1);
+    assert_eq!(server.requests()[0].json()["variables"], json!({ "id": 5 }));
+}
+
+#[tokio::test]
+pub async fn minifies_queries() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .minify_queries(true)
+        .build()
+        .unwrap();
+
+    let query = r#"
+        # Current user
+        query Viewer($id: ID!, $first: Int = 10) {
+          viewer(id: $id, first: $first) @include(if: true) {
+            ...Profile # fields
+            note(format: "a  # b")
+            bio(text: """
+              two  spaces
+            """)
+          }
+        }
+    "#;
+    client
+        .query_with_vars::<Viewer, _>(query, json!({ "id": "1" }))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        server.requests()[0].json()["query"],
+        "query Viewer($id:ID!$first:Int=10){viewer(id:$id first:$first)@include(if:true){...Profile \
+         note(format:\"a  # b\")bio(text:\"\"\"\n              two  spaces\n            \"\"\")}}"
+    );
+}
+
+#[tokio::test]
+pub async fn sends_queries_unchanged_by_default() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::new(&server.url);
+
+    client.query::<Viewer>("{\n  viewer # me\n}").await.unwrap();
+
+    assert_eq!(server.requests()[0].json()["query"], "{\n  viewer # me\n}");
+}
+
+#[tokio::test]
+pub async fn hashes_queries_like_persisted_queries() {
+    assert_eq!(
+        Client::query_hash("{ viewer }"),
+        "480d1ad89e6f682af029c2c96237cb905b785052dc00370e40015b12fa39c36c"
+    );
+}
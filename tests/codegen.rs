@@ -0,0 +1,87 @@
+#![cfg(feature = "macros")]
+
+mod server;
+
+use reqwest_graphql::{graphql_operations, Client, Operation};
+use serde_json::json;
+use server::Response;
+
+graphql_operations!(
+    "tests/fixtures/schema.graphql",
+    "tests/fixtures/operations.graphql"
+);
+
+#[tokio::test]
+pub async fn executes_generated_queries() {
+    let server = server::serve(|_| {
+        Response::json(json!({ "data": { "user": {
+            "id": "1",
+            "name": "Leanne",
+            "role": "ADMIN",
+            "createdAt": "2024-01-01",
+            "friends": [{ "name": "Ervin" }]
+        } } }))
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    let data = client.execute(UserById { id: "1".into() }).await.unwrap();
+
+    let user = data.user.unwrap();
+    assert_eq!(user.name.as_deref(), Some("Leanne"));
+    assert_eq!(user.role, Role::Admin);
+    assert_eq!(user.created_at, Some(json!("2024-01-01")));
+    assert_eq!(user.friends[0].name.as_deref(), Some("Ervin"));
+
+    let body = server.requests()[0].json();
+    assert_eq!(body["operationName"], "UserById");
+    assert_eq!(body["variables"], json!({ "id": "1" }));
+    let document = body["query"].as_str().unwrap();
+    assert!(document.contains("fragment UserFields on User"));
+}
+
+#[tokio::test]
+pub async fn makes_fields_of_other_types_optional() {
+    let server = server::serve(|_| {
+        Response::json(json!({ "data": { "node": { "__typename": "User", "id": "1" } } }))
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    let node = client
+        .execute(NodeById { id: "1".into() })
+        .await
+        .unwrap()
+        .node
+        .unwrap();
+
+    assert_eq!(node.typename, "User");
+    assert_eq!(node.title, None);
+}
+
+#[tokio::test]
+pub async fn serializes_input_objects() {
+    let server = server::serve(|_| {
+        Response::json(json!({ "data": { "createUser": { "id": "2", "role": "MEMBER" } } }))
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    let data = client
+        .execute(CreateUser {
+            input: UserInput {
+                name: "Ervin".into(),
+                role: Some(Role::Member),
+                tags: None,
+            },
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(data.create_user.role, Role::Member);
+    assert_eq!(
+        server.requests()[0].json()["variables"],
+        json!({ "input": { "name": "Ervin", "role": "MEMBER", "tags": null } })
+    );
+    assert!(!CreateUser::DOCUMENT.contains("fragment"));
+}
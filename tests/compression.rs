@@ -0,0 +1,40 @@
+#![cfg(feature = "gzip")]
+
+mod server;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use reqwest_graphql::Client;
+use serde::Deserialize;
+use serde_json::json;
+use server::Response;
+use std::io::Write;
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub viewer: String,
+}
+
+#[tokio::test]
+pub async fn decompresses_gzip_responses() {
+    let server = server::serve(|_| {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        let body = json!({ "data": { "viewer": "me" } }).to_string();
+        encoder.write_all(body.as_bytes()).unwrap();
+
+        let mut response = Response::json(json!(null)).header("content-encoding", "gzip");
+        response.body = encoder.finish().unwrap();
+        response
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    let data = client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    assert_eq!(data.viewer, "me");
+    let accept_encoding = server.requests()[0]
+        .header("accept-encoding")
+        .unwrap()
+        .to_string();
+    assert!(accept_encoding.contains("gzip"));
+}
@@ -0,0 +1,43 @@
+#![cfg(feature = "cookies")]
+
+mod server;
+
+use reqwest_graphql::Client;
+use serde::Deserialize;
+use serde_json::json;
+use server::Response;
+
+#[derive(Deserialize, Debug)]
+pub struct Login {
+    pub login: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub viewer: String,
+}
+
+#[tokio::test]
+pub async fn sends_session_cookies() {
+    let server = server::serve(|request| {
+        if request.text().contains("login") {
+            Response::json(json!({ "data": { "login": true } }))
+                .header("set-cookie", "session=abc123; Path=/")
+        } else {
+            Response::json(json!({ "data": { "viewer": "me" } }))
+        }
+    })
+    .await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .cookie_store(true)
+        .build()
+        .unwrap();
+
+    client.query::<Login>("mutation { login }").await.unwrap();
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests[0].header("cookie"), None);
+    assert_eq!(requests[1].header("cookie"), Some("session=abc123"));
+}
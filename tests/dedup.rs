@@ -0,0 +1,70 @@
+mod server;
+
+use futures_util::future::join_all;
+use reqwest_graphql::Client;
+use serde::Deserialize;
+use serde_json::json;
+use server::Response;
+use std::time::Duration;
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub viewer: String,
+}
+
+fn client(url: &str) -> Client {
+    Client::builder()
+        .endpoint(url)
+        .deduplicate(true)
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+pub async fn coalesces_identical_queries() {
+    let server = server::serve(|_| {
+        Response::json(json!({ "data": { "viewer": "me" } })).delay(Duration::from_millis(100))
+    })
+    .await;
+    let client = client(&server.url);
+
+    let results = join_all((0..5).map(|_| client.query::<Viewer>("{ viewer }"))).await;
+
+    for result in results {
+        assert_eq!(result.unwrap().viewer, "me");
+    }
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+pub async fn sends_different_variables_separately() {
+    let server = server::serve(|_| {
+        Response::json(json!({ "data": { "viewer": "me" } })).delay(Duration::from_millis(50))
+    })
+    .await;
+    let client = client(&server.url);
+
+    let query = "query($id: ID) { viewer }";
+    let results = join_all([
+        client.query_with_vars::<Viewer, _>(query, json!({ "id": 1 })),
+        client.query_with_vars::<Viewer, _>(query, json!({ "id": 2 })),
+    ])
+    .await;
+
+    assert!(results.iter().all(Result::is_ok));
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+pub async fn never_coalesces_mutations() {
+    let server = server::serve(|_| {
+        Response::json(json!({ "data": { "viewer": "me" } })).delay(Duration::from_millis(50))
+    })
+    .await;
+    let client = client(&server.url);
+
+    let results = join_all((0..2).map(|_| client.query::<Viewer>("mutation { viewer }"))).await;
+
+    assert!(results.iter().all(Result::is_ok));
+    assert_eq!(server.requests().len(), 2);
+}
@@ -1,7 +1,17 @@
+#![allow(
+    clippy::redundant_static_lifetimes,
+    clippy::bool_assert_comparison,
+    clippy::unnecessary_cast,
+    clippy::len_zero
+)]
+
+mod server;
 mod structs;
 
 use crate::structs::{inputs::SinglePostVariables, SinglePost};
-use reqwest_graphql::Client;
+use reqwest_graphql::{async_trait, Client, ErrorCode, GraphQLError, Transport};
+use serde_json::json;
+use server::Response;
 
 // Initialize endpoint
 const ENDPOINT: &'static str = "https://graphqlzero.almansi.me/api";
@@ -28,3 +38,265 @@ pub async fn properly_parses_json_errors() {
     assert_eq!(errors.is_some(), true);
     assert!(errors.unwrap().json().as_ref().unwrap().len() > 0 as usize);
 }
+
+#[tokio::test]
+pub async fn returns_graphql_variant() {
+    let server =
+        server::serve(|_| Response::json(json!({ "errors": [{ "message": "Unknown field" }] })))
+            .await;
+    let client = Client::new(&server.url);
+
+    let error = client
+        .query::<SinglePost>("{ post { id1 } }")
+        .await
+        .err()
+        .unwrap();
+
+    match error {
+        GraphQLError::GraphQL(errors) => assert_eq!(errors.len(), 1),
+        error => panic!("Unexpected error: {:?}", error),
+    }
+}
+
+#[tokio::test]
+pub async fn keeps_raw_body_of_invalid_json() {
+    let server = server::serve(|_| Response::text(200, "<html>oops</html>")).await;
+    let client = Client::new(&server.url);
+
+    let error = client
+        .query::<SinglePost>("{ post { id } }")
+        .await
+        .err()
+        .unwrap();
+
+    match error {
+        GraphQLError::Deserialize { raw_body, .. } => assert_eq!(raw_body, "<html>oops</html>"),
+        error => panic!("Unexpected error: {:?}", error),
+    }
+}
+
+#[tokio::test]
+pub async fn reports_path_of_mismatched_field() {
+    let server =
+        server::serve(|_| Response::json(json!({ "data": { "post": { "id": 5 } } }))).await;
+    let client = Client::new(&server.url);
+
+    let error = client
+        .query::<SinglePost>("{ post { id } }")
+        .await
+        .err()
+        .unwrap();
+
+    match error {
+        GraphQLError::Deserialize { path, raw_body, .. } => {
+            assert_eq!(path, "data.post.id");
+            assert!(raw_body.contains("\"id\":5"));
+        }
+        error => panic!("Unexpected error: {:?}", error),
+    }
+}
+
+#[tokio::test]
+pub async fn truncates_large_raw_body() {
+    let server = server::serve(|_| Response::text(200, &"x".repeat(100_000))).await;
+    let client = Client::new(&server.url);
+
+    let error = client
+        .query::<SinglePost>("{ post { id } }")
+        .await
+        .err()
+        .unwrap();
+
+    match error {
+        GraphQLError::Deserialize { raw_body, .. } => assert!(raw_body.len() < 5000),
+        error => panic!("Unexpected error: {:?}", error),
+    }
+}
+
+#[tokio::test]
+pub async fn returns_http_variant_for_error_pages() {
+    let server = server::serve(|_| {
+        Response::text(500, "<html>Internal Server Error</html>").content_type("text/html")
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    let error = client
+        .query::<SinglePost>("{ post { id } }")
+        .await
+        .err()
+        .unwrap();
+
+    match error {
+        GraphQLError::Http { status, body } => {
+            assert_eq!(status, 500);
+            assert_eq!(body, "<html>Internal Server Error</html>");
+        }
+        error => panic!("Unexpected error: {:?}", error),
+    }
+}
+
+#[tokio::test]
+pub async fn returns_graphql_variant_for_failed_status() {
+    let server = server::serve(|_| {
+        Response::json(json!({ "errors": [{ "message": "Unknown field" }] })).status(400)
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    let error = client
+        .query::<SinglePost>("{ post { id1 } }")
+        .await
+        .err()
+        .unwrap();
+
+    match error {
+        GraphQLError::GraphQL(errors) => assert_eq!(errors.len(), 1),
+        error => panic!("Unexpected error: {:?}", error),
+    }
+}
+
+#[tokio::test]
+pub async fn parses_error_codes() {
+    let server = server::serve(|_| {
+        Response::json(json!({
+            "errors": [
+                { "message": "Log in first", "extensions": { "code": "UNAUTHENTICATED" } },
+                { "message": "Slow down", "extensions": { "code": "RATE_LIMITED" } },
+                { "message": "No code" }
+            ]
+        }))
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    let error = client
+        .query::<SinglePost>("{ post { id } }")
+        .await
+        .err()
+        .unwrap();
+
+    assert_eq!(
+        error.codes(),
+        vec![
+            ErrorCode::Unauthenticated,
+            ErrorCode::Other("RATE_LIMITED".into())
+        ]
+    );
+    assert!(error.has_code("UNAUTHENTICATED"));
+    assert!(error.has_code(ErrorCode::Other("RATE_LIMITED".into())));
+    assert!(!error.has_code(ErrorCode::Forbidden));
+    assert!(error.json().unwrap()[0].has_code(ErrorCode::Unauthenticated));
+    assert_eq!(error.json().unwrap()[2].code(), None);
+}
+
+#[tokio::test]
+pub async fn abandons_responses_over_max_size() {
+    let body = json!({ "data": { "post": { "id": "x".repeat(10_000) } } }).to_string();
+    let server = server::serve(move |_| Response::text(200, &body)).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .max_response_size(1000)
+        .build()
+        .unwrap();
+
+    let error = client
+        .query::<SinglePost>("{ post { id } }")
+        .await
+        .err()
+        .unwrap();
+
+    assert!(error.is_response_too_large());
+    assert!(matches!(
+        error,
+        GraphQLError::ResponseTooLarge { limit: 1000 }
+    ));
+}
+
+#[tokio::test]
+pub async fn reads_responses_within_max_size() {
+    let server =
+        server::serve(|_| Response::json(json!({ "data": { "post": { "id": "1" } } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .max_response_size(1000)
+        .build()
+        .unwrap();
+
+    let data = client.query::<SinglePost>("{ post { id } }").await.unwrap();
+
+    assert_eq!(data.post.id, "1");
+}
+
+/// Answers with a body of unknown length, streamed in small chunks
+pub struct Chunked;
+
+#[async_trait]
+impl Transport for Chunked {
+    async fn execute(&self, _: reqwest::Request) -> Result<reqwest::Response, GraphQLError> {
+        let chunks = std::iter::once(r#"{ "data": { "post": { "id": ""#.to_string())
+            .chain(std::iter::repeat_n("x".repeat(100), 100))
+            .chain(std::iter::once(r#"" } } }"#.to_string()))
+            .map(Ok::<_, std::io::Error>);
+        let body = reqwest::Body::wrap_stream(futures_util::stream::iter(chunks));
+        Ok(reqwest::Response::from(http::Response::new(body)))
+    }
+}
+
+#[tokio::test]
+pub async fn abandons_streamed_responses_over_max_size() {
+    let client = Client::builder()
+        .endpoint("http://localhost/graphql")
+        .transport(Chunked)
+        .max_response_size(1000)
+        .build()
+        .unwrap();
+
+    let error = client
+        .query::<SinglePost>("{ post { id } }")
+        .await
+        .err()
+        .unwrap();
+
+    assert!(error.is_response_too_large());
+}
+
+#[tokio::test]
+pub async fn decodes_errors_of_non_graphql_responses() {
+    let server = server::serve(
+        |request| match request.json()["variables"]["page"].as_str() {
+            Some("maintenance") => {
+                Response::text(503, "<html>Down for maintenance</html>").content_type("text/html")
+            }
+            Some("gateway") => Response::json(json!({ "fault": { "reason": "quota exceeded" } })),
+            Some("teapot") => Response::text(418, "I'm a teapot"),
+            _ => Response::json(json!({ "data": { "post": { "id": "1" } } })),
+        },
+    )
+    .await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .error_decoder(|status, headers, body| {
+            if status == 503 && headers["content-type"] == "text/html" {
+                return Some(GraphQLError::Other("Server under maintenance".into()));
+            }
+            let body = serde_json::from_str::<serde_json::Value>(body).ok()?;
+            let reason = body["fault"]["reason"].as_str()?;
+            Some(GraphQLError::Other(format!("Gateway fault: {}", reason)))
+        })
+        .build()
+        .unwrap();
+    let query = |page: &'static str| {
+        client.query_with_vars::<SinglePost, _>("{ post { id } }", json!({ "page": page }))
+    };
+
+    let maintenance = query("maintenance").await.unwrap_err();
+    let gateway = query("gateway").await.unwrap_err();
+    let teapot = query("teapot").await.unwrap_err();
+    let post = query("post").await.unwrap();
+
+    assert!(maintenance.message().contains("Server under maintenance"));
+    assert!(gateway.message().contains("Gateway fault: quota exceeded"));
+    assert!(matches!(teapot, GraphQLError::Http { status, .. } if status == 418));
+    assert_eq!(post.post.id, "1");
+}
@@ -0,0 +1,84 @@
+mod server;
+
+use reqwest_graphql::{Client, FailoverStrategy};
+use serde::Deserialize;
+use serde_json::json;
+use server::Response;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub viewer: String,
+}
+
+#[tokio::test]
+pub async fn fails_over_to_next_endpoint() {
+    let primary = server::serve(|_| Response::text(503, "unavailable")).await;
+    let secondary = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoints(&[&primary.url, &secondary.url])
+        .build()
+        .unwrap();
+
+    for _ in 0..2 {
+        let data = client.query::<Viewer>("{ viewer }").await.unwrap();
+        assert_eq!(data.viewer, "me");
+    }
+
+    // The secondary endpoint is kept once the primary failed
+    assert_eq!(primary.requests().len(), 1);
+    assert_eq!(secondary.requests().len(), 2);
+}
+
+#[tokio::test]
+pub async fn fails_over_on_connect_errors() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoints(&["http://127.0.0.1:1/graphql", &server.url])
+        .build()
+        .unwrap();
+
+    let data = client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    assert_eq!(data.viewer, "me");
+}
+
+#[tokio::test]
+pub async fn returns_to_primary_endpoint_by_priority() {
+    let healthy = Arc::new(AtomicBool::new(false));
+    let primary = server::serve({
+        let healthy = healthy.clone();
+        move |_| match healthy.load(Ordering::SeqCst) {
+            true => Response::json(json!({ "data": { "viewer": "primary" } })),
+            false => Response::text(500, "error"),
+        }
+    })
+    .await;
+    let secondary =
+        server::serve(|_| Response::json(json!({ "data": { "viewer": "secondary" } }))).await;
+    let client = Client::builder()
+        .endpoints(&[&primary.url, &secondary.url])
+        .failover_strategy(FailoverStrategy::Priority)
+        .build()
+        .unwrap();
+
+    let data = client.query::<Viewer>("{ viewer }").await.unwrap();
+    assert_eq!(data.viewer, "secondary");
+
+    healthy.store(true, Ordering::SeqCst);
+    let data = client.query::<Viewer>("{ viewer }").await.unwrap();
+    assert_eq!(data.viewer, "primary");
+}
+
+#[tokio::test]
+pub async fn rejects_invalid_endpoints() {
+    let result = Client::builder()
+        .endpoints(&["https://example.com/graphql", "not a url"])
+        .build();
+
+    match result {
+        Err(error) => assert_eq!(error.message(), "Invalid endpoint: not a url"),
+        Ok(_) => panic!("Expected invalid endpoint error"),
+    }
+}
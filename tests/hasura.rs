@@ -0,0 +1,90 @@
+mod server;
+mod ws_server;
+
+use reqwest_graphql::Client;
+use serde::Deserialize;
+use serde_json::json;
+use server::Response;
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub viewer: String,
+}
+
+#[tokio::test]
+pub async fn sends_hasura_headers() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::hasura(&server.url)
+        .admin_secret("secret")
+        .role("user")
+        .user_id("42")
+        .session_variable("X-Hasura-Org-Id", "7")
+        .configure(|builder| builder.header("x-request-source", "tests"))
+        .build()
+        .unwrap();
+
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    let request = &server.requests()[0];
+    assert_eq!(request.header("x-hasura-admin-secret"), Some("secret"));
+    assert_eq!(request.header("x-hasura-role"), Some("user"));
+    assert_eq!(request.header("x-hasura-user-id"), Some("42"));
+    assert_eq!(request.header("x-hasura-org-id"), Some("7"));
+    assert_eq!(request.header("x-request-source"), Some("tests"));
+}
+
+#[cfg(feature = "subscription")]
+#[tokio::test]
+pub async fn passes_hasura_headers_to_subscriptions() {
+    use futures_util::StreamExt;
+
+    #[derive(Deserialize, Debug)]
+    pub struct Messages {
+        pub messages_stream: Vec<Message>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct Message {
+        pub id: u32,
+    }
+
+    let endpoint = ws_server::serve(|mut connection| async move {
+        let init = connection.receive().await.unwrap();
+        assert_eq!(init["type"], "connection_init");
+        assert_eq!(
+            init["payload"],
+            json!({ "headers": { "x-hasura-admin-secret": "secret", "x-hasura-role": "user" } })
+        );
+        connection.send(json!({ "type": "connection_ack" })).await;
+
+        let id = connection.receive().await.unwrap()["id"].clone();
+        connection
+            .send(json!({ "id": id, "type": "next", "payload": {
+                "data": { "messages_stream": [{ "id": 1 }, { "id": 2 }] }
+            } }))
+            .await;
+        connection
+            .send(json!({ "id": id, "type": "complete" }))
+            .await;
+    })
+    .await;
+
+    let client = Client::hasura(&endpoint)
+        .admin_secret("secret")
+        .role("user")
+        .build()
+        .unwrap();
+    let batches: Vec<Vec<u32>> = client
+        .subscribe::<Messages, _>(
+            "subscription { messages_stream(batch_size: 2, cursor: { initial_value: { id: 0 } }) { id } }",
+            (),
+        )
+        .map(|result| {
+            let messages = result.unwrap().messages_stream;
+            messages.into_iter().map(|message| message.id).collect()
+        })
+        .collect()
+        .await;
+
+    assert_eq!(batches, vec![vec![1, 2]]);
+}
@@ -0,0 +1,55 @@
+mod server;
+
+use reqwest_graphql::Client;
+use serde::Deserialize;
+use serde_json::json;
+use server::Response;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub viewer: String,
+}
+
+#[tokio::test]
+pub async fn answers_with_faster_duplicate() {
+    let calls = AtomicUsize::new(0);
+    let server = server::serve(move |_| {
+        let response = Response::json(json!({ "data": { "viewer": "me" } }));
+        match calls.fetch_add(1, Ordering::SeqCst) {
+            0 => response.delay(Duration::from_secs(2)),
+            _ => response,
+        }
+    })
+    .await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .hedge(Duration::from_millis(50))
+        .build()
+        .unwrap();
+
+    let start = Instant::now();
+    let data = client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    assert_eq!(data.viewer, "me");
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+pub async fn never_duplicates_mutations() {
+    let server = server::serve(|_| {
+        Response::json(json!({ "data": { "viewer": "me" } })).delay(Duration::from_millis(200))
+    })
+    .await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .hedge(Duration::from_millis(20))
+        .build()
+        .unwrap();
+
+    client.query::<Viewer>("mutation { viewer }").await.unwrap();
+
+    assert_eq!(server.requests().len(), 1);
+}
@@ -0,0 +1,131 @@
+mod server;
+
+use reqwest_graphql::{Client, GraphQLError, ResponseCache, RetryPolicy};
+use serde::Deserialize;
+use serde_json::json;
+use server::Response;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Deserialize, Debug)]
+pub struct Order {
+    pub order: String,
+}
+
+/// Answers 503 to the first request, then succeeds
+async fn flaky_server() -> server::MockServer {
+    let attempts = Arc::new(AtomicUsize::new(0));
+    server::serve(move |_| match attempts.fetch_add(1, Ordering::SeqCst) {
+        0 => Response::text(503, "Unavailable"),
+        _ => Response::json(json!({ "data": { "order": "placed" } })),
+    })
+    .await
+}
+
+fn retry() -> RetryPolicy {
+    RetryPolicy::new(3).base_delay(Duration::from_millis(1))
+}
+
+#[tokio::test]
+pub async fn reuses_generated_key_across_retries() {
+    let server = flaky_server().await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .retry(retry())
+        .build()
+        .unwrap();
+
+    let data = client
+        .mutate_idempotent::<Order, _>("mutation { order }", ())
+        .await
+        .unwrap();
+    client
+        .mutate_idempotent::<Order, _>("mutation { order }", ())
+        .await
+        .unwrap();
+
+    let requests = server.requests();
+    let key = requests[0].header("idempotency-key").unwrap();
+    assert_eq!(data.order, "placed");
+    assert_eq!(key.len(), 36);
+    assert_eq!(requests[1].header("idempotency-key"), Some(key));
+    assert_ne!(requests[2].header("idempotency-key"), Some(key));
+}
+
+#[tokio::test]
+pub async fn never_retries_mutations_without_key() {
+    let server = flaky_server().await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .retry(retry())
+        .build()
+        .unwrap();
+
+    let error = client
+        .mutate::<Order, _>("mutation { order }", ())
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, GraphQLError::Http { status, .. } if status == 503));
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+pub async fn never_caches_mutations() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "order": "placed" } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .cache(ResponseCache::new(Duration::from_secs(60)))
+        .deduplicate(true)
+        .build()
+        .unwrap();
+
+    // Some APIs expose operations with side effects as query fields
+    let (first, second) = tokio::join!(
+        client.mutate::<Order, _>("{ order }", ()),
+        client.mutate::<Order, _>("{ order }", ()),
+    );
+    client.mutate::<Order, _>("{ order }", ()).await.unwrap();
+
+    assert_eq!(first.unwrap().order, "placed");
+    assert_eq!(second.unwrap().order, "placed");
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test]
+pub async fn sends_key_of_caller() {
+    let server = flaky_server().await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .retry(retry())
+        .idempotency_key_header("x-idempotency-key")
+        .build()
+        .unwrap();
+
+    client
+        .request("mutation { order }")
+        .idempotency_key("order-42")
+        .send::<Order>()
+        .await
+        .unwrap();
+
+    for request in server.requests() {
+        assert_eq!(request.header("x-idempotency-key"), Some("order-42"));
+        assert_eq!(request.header("idempotency-key"), None);
+    }
+}
+
+#[tokio::test]
+pub async fn rejects_invalid_key() {
+    let client = Client::new("http://localhost/graphql");
+
+    let error = client
+        .request("mutation { order }")
+        .idempotency_key("line\nbreak")
+        .send::<Order>()
+        .await
+        .unwrap_err();
+
+    assert!(error.to_string().contains("Invalid idempotency key"));
+}
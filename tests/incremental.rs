@@ -0,0 +1,213 @@
+mod server;
+
+use futures_util::StreamExt;
+use reqwest_graphql::{Client, GraphQLErrorMessage, Patch};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use server::Response;
+
+const QUERY: &str = "{ viewer { id ... @defer(label: \"details\") { name } } }";
+
+#[derive(Deserialize, Debug)]
+pub struct Data {
+    pub viewer: Viewer,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub id: String,
+    pub name: String,
+}
+
+fn deferred() -> Response {
+    Response::multipart(&[
+        json!({ "data": { "viewer": { "id": "1" } }, "hasNext": true }),
+        json!({
+            "incremental": [{ "data": { "name": "Ada" }, "path": ["viewer"], "label": "details" }],
+            "hasNext": false
+        }),
+    ])
+}
+
+#[tokio::test]
+pub async fn merges_deferred_payloads() {
+    let server = server::serve(|_| deferred()).await;
+    let client = Client::new(&server.url);
+
+    let data = client.query::<Data>(QUERY).await.unwrap();
+
+    assert_eq!(data.viewer.id, "1");
+    assert_eq!(data.viewer.name, "Ada");
+    let accept = server.requests()[0].header("accept").unwrap().to_string();
+    assert!(accept.starts_with("multipart/mixed"));
+}
+
+#[tokio::test]
+pub async fn streams_deferred_patches() {
+    let server = server::serve(|_| deferred()).await;
+    let client = Client::new(&server.url);
+
+    let patches = client
+        .query_incremental(QUERY, ())
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<Result<Vec<Patch>, _>>()
+        .unwrap();
+
+    assert_eq!(patches.len(), 2);
+    assert!(patches[0].path.is_empty());
+    assert!(patches[0].has_next);
+    assert_eq!(patches[1].path, vec![json!("viewer")]);
+    assert_eq!(patches[1].label.as_deref(), Some("details"));
+    assert!(!patches[1].has_next);
+
+    let mut data = Value::Null;
+    for patch in &patches {
+        patch.apply(&mut data);
+    }
+    assert_eq!(data, json!({ "viewer": { "id": "1", "name": "Ada" } }));
+}
+
+#[tokio::test]
+pub async fn resolves_pending_ids() {
+    let server = server::serve(|_| {
+        Response::multipart(&[
+            json!({
+                "data": { "posts": [{ "id": "1" }] },
+                "pending": [{ "id": "0", "path": ["posts"], "label": "rest" }],
+                "hasNext": true
+            }),
+            json!({ "incremental": [{ "id": "0", "items": [{ "id": "2" }, { "id": "3" }] }], "hasNext": true }),
+            json!({ "completed": [{ "id": "0" }], "hasNext": false }),
+        ])
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    let data = client
+        .query::<Value>("{ posts @stream(initialCount: 1) { id } }")
+        .await
+        .unwrap();
+
+    assert_eq!(
+        data,
+        json!({ "posts": [{ "id": "1" }, { "id": "2" }, { "id": "3" }] })
+    );
+}
+
+#[tokio::test]
+pub async fn reports_deferred_errors() {
+    let server = server::serve(|_| {
+        Response::multipart(&[
+            json!({ "data": { "viewer": { "id": "1" } }, "hasNext": true }),
+            json!({
+                "incremental": [{
+                    "data": { "name": null },
+                    "path": ["viewer"],
+                    "errors": [{ "message": "name is unavailable" }]
+                }],
+                "hasNext": false
+            }),
+        ])
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    let error = client.query::<Value>(QUERY).await.unwrap_err();
+
+    match &error.json().unwrap()[0] {
+        GraphQLErrorMessage::ConventionalError { message, .. } => {
+            assert_eq!(message, "name is unavailable")
+        }
+        other => panic!("unexpected error {:?}", other),
+    }
+}
+
+#[tokio::test]
+pub async fn yields_single_response_as_one_patch() {
+    let server = server::serve(|_| {
+        Response::json(json!({ "data": { "viewer": { "id": "1", "name": "Ada" } } }))
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    let patches = client
+        .query_incremental(QUERY, ())
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(patches.len(), 1);
+    let patch = patches.into_iter().next().unwrap().unwrap();
+    assert!(!patch.has_next);
+    assert_eq!(
+        patch.data,
+        Some(json!({ "viewer": { "id": "1", "name": "Ada" } }))
+    );
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Post {
+    pub id: String,
+}
+
+#[tokio::test]
+pub async fn streams_list_items() {
+    let server = server::serve(|_| {
+        Response::multipart(&[
+            json!({ "data": { "viewer": { "posts": [{ "id": "1" }] } }, "hasNext": true }),
+            json!({
+                "incremental": [{ "items": [{ "id": "2" }], "path": ["viewer", "posts", 1] }],
+                "hasNext": true
+            }),
+            json!({
+                "incremental": [{ "items": [{ "id": "3" }], "path": ["viewer", "posts", 2] }],
+                "hasNext": false
+            }),
+        ])
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    let ids = client
+        .stream_items::<Post, _>(
+            "{ viewer { posts @stream(initialCount: 1) { id } } }",
+            (),
+            "viewer.posts",
+        )
+        .map(|post| post.unwrap().id)
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(ids, ["1", "2", "3"]);
+}
+
+#[tokio::test]
+pub async fn streams_items_of_pending_lists() {
+    let server = server::serve(|_| {
+        Response::multipart(&[
+            json!({
+                "data": { "posts": [] },
+                "pending": [{ "id": "0", "path": ["posts"] }],
+                "hasNext": true
+            }),
+            json!({
+                "incremental": [{ "id": "0", "items": [{ "id": "1" }, { "id": "2" }] }],
+                "completed": [{ "id": "0", "errors": [{ "message": "stream interrupted" }] }],
+                "hasNext": false
+            }),
+        ])
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    let items = client
+        .stream_items::<Post, _>("{ posts @stream { id } }", (), "posts")
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(items.len(), 3);
+    assert_eq!(items[0].as_ref().unwrap().id, "1");
+    assert_eq!(items[1].as_ref().unwrap().id, "2");
+    assert!(items[2].is_err());
+}
@@ -0,0 +1,209 @@
+mod server;
+
+use reqwest_graphql::introspection::{Schema, TypeKind};
+use reqwest_graphql::Client;
+use serde_json::{json, Value};
+use server::Response;
+
+pub fn schema_json() -> Value {
+    let string = json!({ "kind": "SCALAR", "name": "String", "ofType": null });
+    let non_null_id = json!({
+        "kind": "NON_NULL",
+        "name": null,
+        "ofType": { "kind": "SCALAR", "name": "ID", "ofType": null }
+    });
+
+    json!({
+        "__schema": {
+            "queryType": { "name": "Query" },
+            "mutationType": null,
+            "subscriptionType": null,
+            "types": [
+                {
+                    "kind": "OBJECT",
+                    "name": "Query",
+                    "description": null,
+                    "fields": [{
+                        "name": "user",
+                        "description": "Finds a user",
+                        "args": [{
+                            "name": "id",
+                            "description": null,
+                            "type": non_null_id,
+                            "defaultValue": null
+                        }],
+                        "type": { "kind": "OBJECT", "name": "User", "ofType": null },
+                        "isDeprecated": false,
+                        "deprecationReason": null
+                    }],
+                    "inputFields": null,
+                    "interfaces": [],
+                    "enumValues": null,
+                    "possibleTypes": null
+                },
+                {
+                    "kind": "OBJECT",
+                    "name": "User",
+                    "description": "A registered user",
+                    "fields": [
+                        {
+                            "name": "id",
+                            "description": null,
+                            "args": [],
+                            "type": non_null_id,
+                            "isDeprecated": false,
+                            "deprecationReason": null
+                        },
+                        {
+                            "name": "name",
+                            "description": null,
+                            "args": [],
+                            "type": string,
+                            "isDeprecated": true,
+                            "deprecationReason": "Use fullName"
+                        },
+                        {
+                            "name": "role",
+                            "description": null,
+                            "args": [],
+                            "type": { "kind": "ENUM", "name": "Role", "ofType": null },
+                            "isDeprecated": false,
+                            "deprecationReason": null
+                        }
+                    ],
+                    "inputFields": null,
+                    "interfaces": [],
+                    "enumValues": null,
+                    "possibleTypes": null
+                },
+                {
+                    "kind": "ENUM",
+                    "name": "Role",
+                    "description": null,
+                    "fields": null,
+                    "inputFields": null,
+                    "interfaces": null,
+                    "enumValues": [
+                        { "name": "ADMIN", "description": null, "isDeprecated": false, "deprecationReason": null },
+                        { "name": "MEMBER", "description": null, "isDeprecated": false, "deprecationReason": null }
+                    ],
+                    "possibleTypes": null
+                },
+                { "kind": "SCALAR", "name": "ID", "description": null, "fields": null, "inputFields": null, "interfaces": null, "enumValues": null, "possibleTypes": null },
+                { "kind": "SCALAR", "name": "String", "description": null, "fields": null, "inputFields": null, "interfaces": null, "enumValues": null, "possibleTypes": null }
+            ],
+            "directives": []
+        }
+    })
+}
+
+#[tokio::test]
+pub async fn introspects_schema() {
+    let server = server::serve(|_| Response::json(json!({ "data": schema_json() }))).await;
+    let client = Client::new(&server.url);
+
+    let schema = client.introspect().await.unwrap();
+
+    assert!(server.requests()[0].json()["query"]
+        .as_str()
+        .unwrap()
+        .contains("__schema"));
+    assert_eq!(schema.query_type.as_ref().unwrap().name, "Query");
+    assert_eq!(schema.types.len(), 5);
+
+    let user = schema.get_type("User").unwrap();
+    assert_eq!(user.kind, TypeKind::Object);
+    assert!(user.field("name").unwrap().is_deprecated);
+
+    let query = schema.get_type("Query").unwrap();
+    let argument = &query.field("user").unwrap().args[0];
+    assert_eq!(argument.value_type.kind, TypeKind::NonNull);
+    assert_eq!(argument.value_type.named_type(), Some("ID"));
+
+    let role = schema.get_type("Role").unwrap();
+    assert_eq!(role.enum_values.as_ref().unwrap().len(), 2);
+}
+
+#[test]
+pub fn prints_schema_as_sdl() {
+    let schema: Schema = serde_json::from_value(schema_json()["__schema"].clone()).unwrap();
+
+    let expected = r#"type Query {
+  """Finds a user"""
+  user(id: ID!): User
+}
+
+"""A registered user"""
+type User {
+  id: ID!
+  name: String @deprecated(reason: "Use fullName")
+  role: Role
+}
+
+enum Role {
+  ADMIN
+  MEMBER
+}
+"#;
+    assert_eq!(schema.to_sdl(), expected);
+}
+
+#[test]
+pub fn prints_custom_root_types_and_inputs() {
+    let schema: Schema = serde_json::from_value(json!({
+        "queryType": { "name": "RootQuery" },
+        "mutationType": null,
+        "subscriptionType": null,
+        "types": [{
+            "kind": "INPUT_OBJECT",
+            "name": "Filter",
+            "description": "Multi\nline",
+            "fields": null,
+            "inputFields": [{
+                "name": "tags",
+                "description": null,
+                "type": {
+                    "kind": "LIST",
+                    "name": null,
+                    "ofType": {
+                        "kind": "NON_NULL",
+                        "name": null,
+                        "ofType": { "kind": "SCALAR", "name": "String", "ofType": null }
+                    }
+                },
+                "defaultValue": "[]"
+            }],
+            "interfaces": null,
+            "enumValues": null,
+            "possibleTypes": null
+        }],
+        "directives": [{
+            "name": "cached",
+            "description": null,
+            "locations": ["FIELD_DEFINITION", "OBJECT"],
+            "args": [{
+                "name": "ttl",
+                "description": null,
+                "type": { "kind": "SCALAR", "name": "Int", "ofType": null },
+                "defaultValue": null
+            }]
+        }]
+    }))
+    .unwrap();
+
+    let expected = r#"schema {
+  query: RootQuery
+}
+
+directive @cached(ttl: Int) on FIELD_DEFINITION | OBJECT
+
+"""
+Multi
+line
+"""
+input Filter {
+  tags: [String!] = []
+}
+"#;
+    assert_eq!(schema.to_sdl(), expected);
+}
@@ -0,0 +1,104 @@
+mod server;
+
+use futures_util::future::join;
+use reqwest_graphql::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use server::Response;
+use std::time::Duration;
+
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct User {
+    pub id: String,
+    pub name: String,
+}
+
+const QUERY: &str = "query($ids: [ID!]!) { users(ids: $ids) { id name } }";
+
+/// Answers with a user for every requested id but "missing"
+fn users(request: &server::Request) -> Response {
+    let users = request.json()["variables"]["ids"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|id| *id != "missing")
+        .map(|id| json!({ "id": id, "name": format!("User {}", id.as_str().unwrap()) }))
+        .collect::<Vec<_>>();
+    Response::json(json!({ "data": { "users": users } }))
+}
+
+#[tokio::test]
+pub async fn batches_keys_into_single_query() {
+    let server = server::serve(users).await;
+    let client = Client::new(&server.url);
+    let loader = client.loader::<User>(QUERY, "users");
+
+    let (first, others) = join(loader.load("1"), loader.load_many(["2", "1", "missing"])).await;
+
+    assert_eq!(first.unwrap().unwrap().name, "User 1");
+    let others = others
+        .into_iter()
+        .map(Result::unwrap)
+        .map(|user| user.map(|user| user.name))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        others,
+        vec![Some("User 2".to_string()), Some("User 1".to_string()), None]
+    );
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(
+        requests[0].json()["variables"],
+        json!({ "ids": ["1", "2", "missing"] })
+    );
+}
+
+#[tokio::test]
+pub async fn sends_full_batches_immediately() {
+    let server = server::serve(users).await;
+    let client = Client::new(&server.url);
+    let loader = client
+        .loader::<User>(QUERY, "users")
+        .window(Duration::from_secs(5))
+        .max_batch(2);
+
+    let results = loader.load_many(["1", "2"]).await;
+
+    assert!(results
+        .iter()
+        .all(|result| result.as_ref().unwrap().is_some()));
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+pub async fn fails_every_key_of_failed_batch() {
+    let server =
+        server::serve(|_| Response::json(json!({ "errors": [{ "message": "Denied" }] }))).await;
+    let client = Client::new(&server.url);
+    let loader = client.loader::<Value>(QUERY, "users");
+
+    let results = loader.load_many(["1", "2"]).await;
+
+    for result in results {
+        assert_eq!(result.unwrap_err().json().unwrap().len(), 1);
+    }
+}
+
+#[tokio::test]
+pub async fn keeps_kind_of_batch_error_for_every_key() {
+    let server = server::serve(|_| {
+        Response::text(200, r#"{ "data": { "users": none"#).content_type("application/json")
+    })
+    .await;
+    let client = Client::new(&server.url);
+    let loader = client.loader::<User>(QUERY, "users");
+
+    let results = loader.load_many(["1", "2"]).await;
+
+    for result in results {
+        let error = result.unwrap_err();
+        assert_eq!(error.kind(), "deserialize");
+        assert!(error.to_string().contains("none"), "{}", error);
+    }
+}
@@ -0,0 +1,144 @@
+#![cfg(feature = "tracing")]
+
+mod server;
+
+use reqwest_graphql::{Client, RequestLogger};
+use serde::Deserialize;
+use serde_json::json;
+use server::Response;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub viewer: String,
+}
+
+type Fields = HashMap<String, String>;
+
+/// Layer keeping the fields of every event
+#[derive(Clone, Default)]
+struct Events(Arc<Mutex<Vec<Fields>>>);
+
+impl<S: Subscriber> Layer<S> for Events {
+    fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+        let mut fields = Fields::new();
+        event.record(&mut Recorder(&mut fields));
+        self.0.lock().unwrap().push(fields);
+    }
+}
+
+struct Recorder<'a>(&'a mut Fields);
+
+impl Visit for Recorder<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{:?}", value));
+    }
+}
+
+#[tokio::test]
+pub async fn logs_operation_status_and_duration() {
+    let events = Events::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(events.clone()));
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .middleware(RequestLogger::new())
+        .build()
+        .unwrap();
+
+    client
+        .query::<Viewer>("query Viewer { viewer }")
+        .await
+        .unwrap();
+
+    let events = events.0.lock().unwrap();
+    let event = events
+        .iter()
+        .find(|event| event["message"] == "GraphQL request completed")
+        .unwrap();
+    assert_eq!(event["operation_name"], "Viewer");
+    assert_eq!(event["method"], "POST");
+    assert_eq!(event["url"], server.url);
+    assert_eq!(event["status"], "200");
+    assert!(event.contains_key("duration_ms"));
+    assert!(!event.contains_key("request_body"));
+    assert!(!event.contains_key("request_headers"));
+}
+
+#[tokio::test]
+pub async fn redacts_headers_and_variables() {
+    let events = Events::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(events.clone()));
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .header("authorization", "Bearer secret-token")
+        .header("x-api-key", "secret-key")
+        .header("x-tenant", "acme")
+        .middleware(
+            RequestLogger::new()
+                .headers(true)
+                .bodies(true)
+                .redact_header("x-api-key")
+                .redact_variable("password"),
+        )
+        .build()
+        .unwrap();
+
+    client
+        .query_with_vars::<Viewer, _>(
+            "mutation Login($input: LoginInput!) { viewer: login(input: $input) }",
+            json!({ "input": { "user": "me", "password": "hunter2" } }),
+        )
+        .await
+        .unwrap();
+
+    let events = events.0.lock().unwrap();
+    let event = events
+        .iter()
+        .find(|event| event["message"] == "GraphQL request completed")
+        .unwrap();
+    let headers = &event["request_headers"];
+    assert!(headers.contains("authorization: [REDACTED]"));
+    assert!(headers.contains("x-api-key: [REDACTED]"));
+    assert!(headers.contains("x-tenant: acme"));
+    assert!(!headers.contains("secret"));
+    let body = &event["request_body"];
+    assert!(body.contains(r#""password":"[REDACTED]""#));
+    assert!(body.contains(r#""user":"me""#));
+    assert!(!body.contains("hunter2"));
+    assert_eq!(event["response_body"], r#"{"data":{"viewer":"me"}}"#);
+}
+
+#[tokio::test]
+pub async fn logs_failed_requests() {
+    let events = Events::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(events.clone()));
+    let client = Client::builder()
+        .endpoint("http://127.0.0.1:1/graphql")
+        .middleware(RequestLogger::new())
+        .build()
+        .unwrap();
+
+    client.query::<Viewer>("{ viewer }").await.unwrap_err();
+
+    let events = events.0.lock().unwrap();
+    let event = events
+        .iter()
+        .find(|event| event["message"] == "GraphQL request failed")
+        .unwrap();
+    assert!(event.contains_key("error"));
+}
@@ -0,0 +1,41 @@
+#![cfg(feature = "macros")]
+
+mod server;
+
+use reqwest_graphql::{gql, gql_operation, Client};
+use serde::Deserialize;
+use serde_json::json;
+use server::Response;
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub viewer: String,
+}
+
+const VIEWER: &str = gql!("{ viewer }");
+
+#[tokio::test]
+pub async fn sends_checked_documents() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::new(&server.url);
+
+    let data = client.query::<Viewer>(VIEWER).await.unwrap();
+
+    assert_eq!(data.viewer, "me");
+}
+
+#[tokio::test]
+pub async fn extracts_operation_name() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::new(&server.url);
+    let (query, operation_name) =
+        gql_operation!("query Viewer($id: ID) { viewer } fragment Name on User { name }");
+
+    client
+        .query_with_operation::<Viewer, ()>(query, operation_name, ())
+        .await
+        .unwrap();
+
+    assert_eq!(operation_name, "Viewer");
+    assert_eq!(server.requests()[0].json()["operationName"], "Viewer");
+}
@@ -0,0 +1,217 @@
+mod server;
+
+use futures_util::StreamExt;
+use reqwest_graphql::{Client, MetricsSink, RetryPolicy};
+use serde::Deserialize;
+use serde_json::json;
+use server::Response;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub viewer: String,
+}
+
+#[derive(Default)]
+struct Recorder {
+    requests: Mutex<Vec<Option<String>>>,
+    errors: Mutex<Vec<(Option<String>, String)>>,
+    latencies: Mutex<Vec<Duration>>,
+}
+
+impl MetricsSink for Recorder {
+    fn increment_requests(&self, operation_name: Option<&str>) {
+        let operation_name = operation_name.map(str::to_string);
+        self.requests.lock().unwrap().push(operation_name);
+    }
+
+    fn increment_errors(&self, operation_name: Option<&str>, kind: &str) {
+        let operation_name = operation_name.map(str::to_string);
+        self.errors
+            .lock()
+            .unwrap()
+            .push((operation_name, kind.to_string()));
+    }
+
+    fn record_latency(&self, _operation_name: Option<&str>, latency: Duration) {
+        self.latencies.lock().unwrap().push(latency);
+    }
+}
+
+fn client(url: &str, recorder: &Arc<Recorder>) -> Client {
+    Client::builder()
+        .endpoint(url)
+        .metrics(recorder.clone())
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+pub async fn records_requests_and_latency_by_operation() {
+    let server = server::serve(|_| {
+        Response::json(json!({ "data": { "viewer": "me" } })).delay(Duration::from_millis(20))
+    })
+    .await;
+    let recorder = Arc::new(Recorder::default());
+    let client = client(&server.url, &recorder);
+
+    client
+        .query::<Viewer>("query Viewer { viewer }")
+        .await
+        .unwrap();
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    assert_eq!(
+        *recorder.requests.lock().unwrap(),
+        vec![Some("Viewer".to_string()), None]
+    );
+    assert!(recorder.errors.lock().unwrap().is_empty());
+    let latencies = recorder.latencies.lock().unwrap();
+    assert_eq!(latencies.len(), 2);
+    assert!(latencies[0] >= Duration::from_millis(20));
+}
+
+#[tokio::test]
+pub async fn records_errors_by_kind() {
+    let calls = AtomicUsize::new(0);
+    let server = server::serve(move |_| match calls.fetch_add(1, Ordering::SeqCst) {
+        0 => Response::json(json!({ "data": null, "errors": [{ "message": "Forbidden" }] })),
+        _ => Response::text(500, "oops"),
+    })
+    .await;
+    let recorder = Arc::new(Recorder::default());
+    let client = client(&server.url, &recorder);
+
+    client
+        .query::<Viewer>("query Viewer { viewer }")
+        .await
+        .unwrap_err();
+    client
+        .query::<Viewer>("query Viewer { viewer }")
+        .await
+        .unwrap_err();
+
+    let viewer = Some("Viewer".to_string());
+    assert_eq!(
+        *recorder.errors.lock().unwrap(),
+        vec![
+            (viewer.clone(), "graphql".to_string()),
+            (viewer, "http".to_string())
+        ]
+    );
+    assert_eq!(recorder.requests.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+pub async fn records_retried_operations_once() {
+    let calls = AtomicUsize::new(0);
+    let server = server::serve(move |_| match calls.fetch_add(1, Ordering::SeqCst) {
+        0 => Response::text(503, "unavailable"),
+        _ => Response::json(json!({ "data": { "viewer": "me" } })),
+    })
+    .await;
+    let recorder = Arc::new(Recorder::default());
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .retry(RetryPolicy::new(2).base_delay(Duration::from_millis(1)))
+        .metrics(recorder.clone())
+        .build()
+        .unwrap();
+
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    assert_eq!(server.requests().len(), 2);
+    assert_eq!(recorder.requests.lock().unwrap().len(), 1);
+    assert!(recorder.errors.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+pub async fn records_deserialization_errors() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": 1 } }))).await;
+    let recorder = Arc::new(Recorder::default());
+    let client = client(&server.url, &recorder);
+
+    let error = client.query::<Viewer>("{ viewer }").await.unwrap_err();
+    client.query_raw("{ viewer }", ()).await.unwrap();
+
+    assert_eq!(error.kind(), "deserialize");
+    assert_eq!(
+        *recorder.errors.lock().unwrap(),
+        vec![(None, "deserialize".to_string())]
+    );
+    assert_eq!(recorder.requests.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+pub async fn records_errors_of_raw_and_streamed_queries() {
+    let server = server::serve(|_| {
+        Response::json(json!({ "data": null, "errors": [{ "message": "Forbidden" }] }))
+    })
+    .await;
+    let recorder = Arc::new(Recorder::default());
+    let client = client(&server.url, &recorder);
+
+    client.query_raw("{ viewer }", ()).await.unwrap();
+    client
+        .query_streamed::<Viewer, _>("{ viewer }", ())
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        *recorder.errors.lock().unwrap(),
+        vec![(None, "graphql".to_string()), (None, "graphql".to_string())]
+    );
+}
+
+#[tokio::test]
+pub async fn records_batches_with_failed_operations_once() {
+    let server = server::serve(|_| {
+        Response::json(json!([
+            { "data": { "viewer": "me" } },
+            { "data": null, "errors": [{ "message": "Forbidden" }] },
+            { "data": { "viewer": 1 } },
+        ]))
+    })
+    .await;
+    let recorder = Arc::new(Recorder::default());
+    let client = client(&server.url, &recorder);
+
+    let results = client
+        .query_batch::<Viewer, _>(&[("{ viewer }", ()), ("{ viewer }", ()), ("{ viewer }", ())])
+        .await
+        .unwrap();
+
+    assert!(results[0].is_ok());
+    assert_eq!(recorder.requests.lock().unwrap().len(), 1);
+    assert_eq!(
+        *recorder.errors.lock().unwrap(),
+        vec![(None, "graphql".to_string())]
+    );
+}
+
+#[tokio::test]
+pub async fn records_incremental_errors_once() {
+    let server = server::serve(|_| {
+        Response::multipart(&[
+            json!({ "data": { "viewer": "me" }, "hasNext": true }),
+            json!({ "incremental": [{ "path": ["viewer"], "errors": [{ "message": "A" }] }], "hasNext": true }),
+            json!({ "incremental": [{ "path": ["viewer"], "errors": [{ "message": "B" }] }], "hasNext": false }),
+        ])
+    })
+    .await;
+    let recorder = Arc::new(Recorder::default());
+    let client = client(&server.url, &recorder);
+
+    let patches = client
+        .query_incremental("{ viewer ... @defer { name } }", ())
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(patches.len(), 3);
+    assert_eq!(
+        *recorder.errors.lock().unwrap(),
+        vec![(None, "graphql".to_string())]
+    );
+}
@@ -0,0 +1,96 @@
+mod server;
+
+use reqwest::header::HeaderValue;
+use reqwest::{Request, Response};
+use reqwest_graphql::{async_trait, Client, GraphQLError, Middleware, Next};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub viewer: String,
+}
+
+struct Tag {
+    name: &'static str,
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl Middleware for Tag {
+    async fn handle(&self, mut request: Request, next: Next<'_>) -> Result<Response, GraphQLError> {
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("before {}", self.name));
+        request
+            .headers_mut()
+            .append("x-tag", HeaderValue::from_static(self.name));
+
+        let response = next.run(request).await;
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("after {}", self.name));
+        response
+    }
+}
+
+struct Reject;
+
+#[async_trait]
+impl Middleware for Reject {
+    async fn handle(&self, _request: Request, _next: Next<'_>) -> Result<Response, GraphQLError> {
+        Err(GraphQLError::Other("Rejected".into()))
+    }
+}
+
+#[tokio::test]
+pub async fn runs_middlewares_in_order() {
+    let server =
+        server::serve(|_| server::Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .middleware(Tag {
+            name: "outer",
+            log: log.clone(),
+        })
+        .middleware(Tag {
+            name: "inner",
+            log: log.clone(),
+        })
+        .build()
+        .unwrap();
+
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec!["before outer", "before inner", "after inner", "after outer"]
+    );
+    let tags: Vec<_> = server.requests()[0]
+        .headers
+        .iter()
+        .filter(|(key, _)| key == "x-tag")
+        .map(|(_, value)| value.clone())
+        .collect();
+    assert_eq!(tags, vec!["outer", "inner"]);
+}
+
+#[tokio::test]
+pub async fn middleware_can_short_circuit() {
+    let server =
+        server::serve(|_| server::Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .middleware(Reject)
+        .build()
+        .unwrap();
+
+    let error = client.query::<Viewer>("{ viewer }").await.err().unwrap();
+
+    assert_eq!(error.message(), "Rejected");
+    assert!(server.requests().is_empty());
+}
@@ -0,0 +1,129 @@
+use reqwest_graphql::{Client, GraphQLError, MockTransport};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+#[derive(Deserialize, Debug)]
+pub struct Data {
+    pub post: Post,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Post {
+    pub title: String,
+}
+
+#[derive(Serialize)]
+pub struct Vars {
+    pub id: u32,
+}
+
+const QUERY: &str = "query Post($id: ID!) { post(id: $id) { title } }";
+
+#[tokio::test]
+pub async fn answers_registered_queries() {
+    let mock = MockTransport::new();
+    mock.on(
+        "query Post($id: ID!) {\n  post(id: $id) {\n    title\n  }\n}",
+        json!({ "data": { "post": { "title": "Hello" } } }),
+    );
+    let client = Client::mock(&mock);
+
+    let data = client
+        .query_with_vars::<Data, _>(QUERY, Vars { id: 1 })
+        .await
+        .unwrap();
+
+    assert_eq!(data.post.title, "Hello");
+    let requests = mock.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].query, QUERY);
+    assert_eq!(requests[0].variables, json!({ "id": 1 }));
+}
+
+#[tokio::test]
+pub async fn answers_by_operation_name() {
+    let mock = MockTransport::new();
+    mock.on_operation("Post", json!({ "data": { "post": { "title": "Hello" } } }))
+        .on_operation(
+            "Other",
+            json!({ "data": { "post": { "title": "Not this" } } }),
+        );
+    let client = Client::mock(&mock);
+
+    let data = client
+        .query_with_vars::<Data, _>(QUERY, Vars { id: 1 })
+        .await
+        .unwrap();
+
+    assert_eq!(data.post.title, "Hello");
+}
+
+#[tokio::test]
+pub async fn returns_mocked_errors() {
+    let mock = MockTransport::new();
+    mock.on(QUERY, json!({ "errors": [{ "message": "Not found" }] }));
+    let client = Client::mock(&mock);
+
+    let error = client
+        .query_with_vars::<Data, _>(QUERY, Vars { id: 1 })
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, GraphQLError::GraphQL(errors) if errors.len() == 1));
+}
+
+#[tokio::test]
+pub async fn fails_unmatched_queries() {
+    let mock = MockTransport::new();
+    let client = Client::mock(&mock);
+
+    let error = client
+        .query::<Value>("{ viewer { id } }")
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, GraphQLError::Other(message) if message.contains("{ viewer { id } }")));
+    assert_eq!(mock.requests().len(), 1);
+}
+
+#[tokio::test]
+pub async fn records_headers_of_built_clients() {
+    let mock = MockTransport::new();
+    mock.on(
+        "{ viewer { id } }",
+        json!({ "data": { "viewer": { "id": "1" } } }),
+    );
+    let client = Client::builder()
+        .endpoint("https://example.com/graphql")
+        .header("authorization", "Bearer token")
+        .use_get_for_queries(true)
+        .middleware(mock.clone())
+        .build()
+        .unwrap();
+
+    client.query::<Value>("{ viewer { id } }").await.unwrap();
+
+    let request = &mock.requests()[0];
+    assert_eq!(request.header("authorization"), Some("Bearer token"));
+    assert_eq!(request.query, "{ viewer { id } }");
+}
+
+#[tokio::test]
+pub async fn answers_application_graphql_documents() {
+    let mock = MockTransport::new();
+    mock.on(
+        "{ viewer { id } }",
+        json!({ "data": { "viewer": { "id": "1" } } }),
+    );
+    let client = Client::builder()
+        .endpoint("https://example.com/graphql")
+        .use_graphql_content_type(true)
+        .transport(mock.clone())
+        .build()
+        .unwrap();
+
+    let data = client.query::<Value>("{ viewer { id } }").await.unwrap();
+
+    assert_eq!(data, json!({ "viewer": { "id": "1" } }));
+    assert_eq!(mock.requests()[0].variables, Value::Null);
+}
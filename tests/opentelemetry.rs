@@ -0,0 +1,38 @@
+#![cfg(feature = "opentelemetry")]
+
+mod server;
+
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use reqwest_graphql::Client;
+use serde::Deserialize;
+use serde_json::json;
+use server::Response;
+use tracing_subscriber::layer::SubscriberExt;
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub viewer: String,
+}
+
+#[tokio::test]
+pub async fn injects_trace_context() {
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+    let provider = SdkTracerProvider::builder().build();
+    let subscriber = tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::new(&server.url);
+
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    let requests = server.requests();
+    let traceparent = requests[0].header("traceparent").unwrap();
+    let parts: Vec<&str> = traceparent.split('-').collect();
+    assert_eq!(parts.len(), 4);
+    assert_eq!(parts[0], "00");
+    assert_ne!(parts[1], "0".repeat(32));
+}
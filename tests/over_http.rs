@@ -0,0 +1,102 @@
+mod server;
+
+use reqwest_graphql::{Client, GraphQLError, GraphQLErrorMessage};
+use serde::Deserialize;
+use serde_json::json;
+use server::Response;
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub viewer: String,
+}
+
+const GRAPHQL_RESPONSE: &str = "application/graphql-response+json; charset=utf-8";
+
+fn client(url: &str) -> Client {
+    Client::builder()
+        .endpoint(url)
+        .graphql_over_http(true)
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+pub async fn accepts_graphql_response_media_type() {
+    let server = server::serve(|_| {
+        Response::json(json!({ "data": { "viewer": "me" } })).content_type(GRAPHQL_RESPONSE)
+    })
+    .await;
+
+    let data = client(&server.url)
+        .query::<Viewer>("{ viewer }")
+        .await
+        .unwrap();
+
+    assert_eq!(data.viewer, "me");
+    assert_eq!(
+        server.requests()[0].header("accept"),
+        Some("application/graphql-response+json, application/json;q=0.9")
+    );
+}
+
+#[tokio::test]
+pub async fn returns_errors_of_failed_request() {
+    let server = server::serve(|_| {
+        Response::json(json!({ "errors": [{ "message": "Unknown field" }] }))
+            .status(400)
+            .content_type(GRAPHQL_RESPONSE)
+    })
+    .await;
+
+    let error = client(&server.url)
+        .query::<Viewer>("{ unknown }")
+        .await
+        .unwrap_err();
+
+    match error {
+        GraphQLError::GraphQL(errors) => match &errors[..] {
+            [GraphQLErrorMessage::ConventionalError { message, .. }] => {
+                assert_eq!(message, "Unknown field")
+            }
+            errors => panic!("Unexpected errors: {:?}", errors),
+        },
+        error => panic!("Unexpected error: {:?}", error),
+    }
+}
+
+#[tokio::test]
+pub async fn reports_status_of_failed_json_response() {
+    let server = server::serve(|_| {
+        Response::json(json!({ "errors": [{ "message": "Bad gateway" }] })).status(502)
+    })
+    .await;
+
+    let error = client(&server.url)
+        .query::<Viewer>("{ viewer }")
+        .await
+        .unwrap_err();
+
+    match error {
+        GraphQLError::Http { status, body } => {
+            assert_eq!(status, 502);
+            assert!(body.contains("Bad gateway"));
+        }
+        error => panic!("Unexpected error: {:?}", error),
+    }
+}
+
+#[tokio::test]
+pub async fn rejects_unexpected_media_type() {
+    let server =
+        server::serve(|_| Response::text(200, "<html></html>").content_type("text/html")).await;
+
+    let error = client(&server.url)
+        .query::<Viewer>("{ viewer }")
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        error.message(),
+        "Unexpected response content type: text/html"
+    );
+}
@@ -0,0 +1,183 @@
+mod server;
+
+use futures_util::StreamExt;
+use reqwest_graphql::{Client, OffsetPagination};
+use serde::Deserialize;
+use serde_json::json;
+use server::Response;
+
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct Post {
+    pub id: u32,
+}
+
+const POSTS: &str = r#"
+    query ($user: ID!, $after: String) {
+        user(id: $user) {
+            posts(first: 2, after: $after) {
+                edges { node { id } }
+                pageInfo { hasNextPage endCursor }
+            }
+        }
+    }
+"#;
+
+#[tokio::test]
+pub async fn follows_relay_cursors() {
+    let server = server::serve(|request| {
+        let (ids, next) = match request.json()["variables"]["after"].as_str() {
+            None => (vec![1, 2], Some("b")),
+            Some("b") => (vec![3, 4], Some("d")),
+            _ => (vec![5], None),
+        };
+        let edges = ids
+            .into_iter()
+            .map(|id| json!({ "node": { "id": id } }))
+            .collect::<Vec<_>>();
+        Response::json(json!({ "data": { "user": { "posts": {
+            "edges": edges,
+            "pageInfo": { "hasNextPage": next.is_some(), "endCursor": next.unwrap_or("e") }
+        } } } }))
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    let posts = client
+        .paginate::<Post, _>(POSTS, json!({ "user": 1 }), "user.posts")
+        .map(Result::unwrap)
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(
+        posts.iter().map(|post| post.id).collect::<Vec<_>>(),
+        [1, 2, 3, 4, 5]
+    );
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(requests[0].json()["variables"], json!({ "user": 1 }));
+    assert_eq!(
+        requests[2].json()["variables"],
+        json!({ "user": 1, "after": "d" })
+    );
+}
+
+#[tokio::test]
+pub async fn fetches_pages_on_demand() {
+    let server = server::serve(|_| {
+        Response::json(json!({ "data": { "posts": {
+            "nodes": [{ "id": 1 }, { "id": 2 }],
+            "pageInfo": { "hasNextPage": true, "endCursor": "b" }
+        } } }))
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    let posts = client
+        .paginate::<Post, _>(POSTS, (), "posts")
+        .take(2)
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(posts.len(), 2);
+    assert_eq!(server.requests().len(), 1);
+
+    // The same cursor again ends the stream instead of looping
+    let posts = client
+        .paginate::<Post, _>(POSTS, (), "posts")
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(posts.len(), 4);
+}
+
+#[tokio::test]
+pub async fn ends_with_error_for_missing_connection() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "user": null } }))).await;
+    let client = Client::new(&server.url);
+
+    let results = client
+        .paginate::<Post, _>(POSTS, (), "user.posts")
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(results.len(), 1);
+    assert!(results[0].is_err());
+}
+
+#[derive(Deserialize)]
+pub struct Posts {
+    pub posts: PostList,
+}
+
+#[derive(Deserialize)]
+pub struct PostList {
+    pub items: Vec<Post>,
+    pub total: u64,
+}
+
+#[tokio::test]
+pub async fn yields_offset_pages_until_total() {
+    let server = server::serve(|request| {
+        let variables = request.json()["variables"].clone();
+        let skip = variables["skip"].as_u64().unwrap();
+        let take = variables["take"].as_u64().unwrap();
+        let items = (skip..(skip + take).min(5))
+            .map(|id| json!({ "id": id }))
+            .collect::<Vec<_>>();
+        Response::json(json!({ "data": { "posts": { "items": items, "total": 5 } } }))
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    let pages = client
+        .paginate_offset(
+            "query ($skip: Int, $take: Int) { posts(skip: $skip, take: $take) { items { id } total } }",
+            (),
+            OffsetPagination::new("skip", "take", 2).start(1),
+            |data: Posts| (data.posts.items, Some(data.posts.total)),
+        )
+        .map(Result::unwrap)
+        .collect::<Vec<_>>()
+        .await;
+
+    let ids = pages
+        .iter()
+        .map(|page| page.iter().map(|post| post.id).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec![vec![1, 2], vec![3, 4]]);
+    assert_eq!(server.requests().len(), 2);
+    assert_eq!(
+        server.requests()[1].json()["variables"],
+        json!({ "skip": 3, "take": 2 })
+    );
+}
+
+#[tokio::test]
+pub async fn stops_at_short_offset_page() {
+    let server = server::serve(|request| {
+        let skip = request.json()["variables"]["offset"].as_u64().unwrap();
+        let items = if skip == 0 {
+            json!([{ "id": 1 }, { "id": 2 }])
+        } else {
+            json!([{ "id": 3 }])
+        };
+        Response::json(json!({ "data": { "posts": { "items": items, "total": 0 } } }))
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    let pages = client
+        .paginate_offset(
+            "query ($offset: Int, $limit: Int) { posts(offset: $offset, limit: $limit) { items { id } } }",
+            json!({ "published": true }),
+            OffsetPagination::new("offset", "limit", 2),
+            |data: Posts| (data.posts.items, None),
+        )
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(pages.len(), 2);
+    assert_eq!(pages[1].as_ref().unwrap().len(), 1);
+    assert_eq!(
+        server.requests()[0].json()["variables"],
+        json!({ "published": true, "offset": 0, "limit": 2 })
+    );
+}
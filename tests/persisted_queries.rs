@@ -0,0 +1,217 @@
+mod server;
+
+use reqwest_graphql::{Client, PersistedQueries};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use server::Response;
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub viewer: String,
+}
+
+const QUERY: &str = "query Viewer { viewer }";
+
+fn manifest() -> PersistedQueries {
+    PersistedQueries::new().operation("Viewer", Client::query_hash(QUERY))
+}
+
+#[tokio::test]
+pub async fn sends_only_persisted_query_hash() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .persisted_queries(manifest())
+        .build()
+        .unwrap();
+
+    let data = client.query::<Viewer>(QUERY).await.unwrap();
+
+    assert_eq!(data.viewer, "me");
+    assert_eq!(
+        server.requests()[0].json(),
+        json!({
+            "variables": null,
+            "operationName": "Viewer",
+            "extensions": {
+                "persistedQuery": { "version": 1, "sha256Hash": Client::query_hash(QUERY) }
+            }
+        })
+    );
+}
+
+#[tokio::test]
+pub async fn fails_for_operations_missing_from_manifest() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .persisted_queries(manifest())
+        .build()
+        .unwrap();
+
+    let error = client
+        .query::<Viewer>("query Other { viewer }")
+        .await
+        .unwrap_err();
+
+    assert!(error
+        .to_string()
+        .contains("Operation Other isn't in the persisted query manifest"));
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+pub async fn reads_apollo_manifest_file() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let path = std::env::temp_dir().join(format!(
+        "reqwest-graphql-manifest-{}.json",
+        std::process::id()
+    ));
+    let manifest = json!({
+        "format": "apollo-persisted-query-manifest",
+        "version": 1,
+        "operations": [
+            { "id": "abc", "name": "Viewer", "type": "query", "body": QUERY }
+        ]
+    });
+    std::fs::write(&path, manifest.to_string()).unwrap();
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .persisted_queries(PersistedQueries::from_file(&path).unwrap())
+        .build()
+        .unwrap();
+
+    client.query::<Viewer>(QUERY).await.unwrap();
+
+    assert_eq!(
+        server.requests()[0].json()["extensions"]["persistedQuery"]["sha256Hash"],
+        "abc"
+    );
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+pub async fn reads_manifest_mapping_names_to_hashes() {
+    let manifest = PersistedQueries::from_manifest(r#"{ "Viewer": "abc" }"#);
+    assert!(manifest.is_ok());
+
+    let error = PersistedQueries::from_manifest(r#"{ "Viewer": 1 }"#).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("Invalid persisted query manifest"));
+}
+
+fn query_params(path: &str) -> Vec<(String, String)> {
+    reqwest::Url::parse(&format!("http://localhost{}", path))
+        .unwrap()
+        .query_pairs()
+        .into_owned()
+        .collect()
+}
+
+/// Answers like Apollo Server, which only knows the hashes of the documents it received
+fn apollo_server(request: &server::Request) -> Response {
+    let body = match request.method.as_str() {
+        "GET" => Value::Object(
+            query_params(&request.path)
+                .into_iter()
+                .map(|(name, value)| match name.as_str() {
+                    "query" => (name, json!(value)),
+                    _ => (name, serde_json::from_str(&value).unwrap()),
+                })
+                .collect(),
+        ),
+        _ => request.json(),
+    };
+
+    if body.get("query").is_some() {
+        return Response::json(json!({ "data": { "viewer": "me" } }));
+    }
+    Response::json(json!({
+        "errors": [{
+            "message": "PersistedQueryNotFound",
+            "extensions": { "code": "PERSISTED_QUERY_NOT_FOUND" }
+        }]
+    }))
+}
+
+#[tokio::test]
+pub async fn sends_document_when_hash_is_unknown() {
+    let server = server::serve(apollo_server).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .automatic_persisted_queries(true)
+        .build()
+        .unwrap();
+
+    let data = client.query::<Viewer>(QUERY).await.unwrap();
+
+    assert_eq!(data.viewer, "me");
+    let requests = server.requests();
+    let extensions = json!({
+        "persistedQuery": { "version": 1, "sha256Hash": Client::query_hash(QUERY) }
+    });
+    assert_eq!(
+        requests[0].json(),
+        json!({ "variables": null, "extensions": extensions })
+    );
+    assert_eq!(
+        requests[1].json(),
+        json!({ "query": QUERY, "variables": null, "extensions": extensions })
+    );
+}
+
+#[tokio::test]
+pub async fn sends_only_hash_once_known() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .automatic_persisted_queries(true)
+        .build()
+        .unwrap();
+
+    client.query::<Viewer>(QUERY).await.unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].json().get("query").is_none());
+}
+
+#[tokio::test]
+pub async fn sends_persisted_queries_as_get() {
+    let server = server::serve(apollo_server).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .automatic_persisted_queries(true)
+        .use_get_for_queries(true)
+        .build()
+        .unwrap();
+
+    client
+        .query_with_vars::<Viewer, _>(QUERY, json!({ "id": 1 }))
+        .await
+        .unwrap();
+
+    let requests = server.requests();
+    let extensions = format!(
+        r#"{{"persistedQuery":{{"sha256Hash":"{}","version":1}}}}"#,
+        Client::query_hash(QUERY)
+    );
+    assert_eq!(requests[0].method, "GET");
+    assert_eq!(
+        query_params(&requests[0].path),
+        vec![
+            ("variables".to_string(), r#"{"id":1}"#.to_string()),
+            ("extensions".to_string(), extensions.clone()),
+        ]
+    );
+    assert_eq!(requests[1].method, "GET");
+    assert_eq!(
+        query_params(&requests[1].path),
+        vec![
+            ("variables".to_string(), r#"{"id":1}"#.to_string()),
+            ("extensions".to_string(), extensions),
+            ("query".to_string(), QUERY.to_string()),
+        ]
+    );
+}
@@ -0,0 +1,110 @@
+mod server;
+
+use reqwest_graphql::{Client, GraphQLError, RetryPolicy};
+use serde::Deserialize;
+use serde_json::json;
+use server::Response;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub viewer: String,
+}
+
+#[tokio::test]
+pub async fn builds_request_without_sending_it() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .header("x-tenant", "acme")
+        .timeout(Duration::from_secs(5))
+        .build()
+        .unwrap();
+
+    let request = client
+        .build_request(
+            "query Viewer($id: ID!) { viewer(id: $id) }",
+            json!({ "id": 1 }),
+        )
+        .unwrap()
+        .build()
+        .unwrap();
+
+    assert_eq!(request.method(), "POST");
+    assert_eq!(request.url().as_str(), server.url);
+    assert_eq!(request.headers()["x-tenant"], "acme");
+    assert_eq!(request.timeout(), Some(&Duration::from_secs(5)));
+    let body: serde_json::Value =
+        serde_json::from_slice(request.body().unwrap().as_bytes().unwrap()).unwrap();
+    assert_eq!(
+        body,
+        json!({
+            "query": "query Viewer($id: ID!) { viewer(id: $id) }",
+            "variables": { "id": 1 }
+        })
+    );
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+pub async fn sends_built_request_through_client() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::new(&server.url);
+
+    let mut request = client
+        .build_request("{ viewer }", json!({}))
+        .unwrap()
+        .build()
+        .unwrap();
+    request
+        .headers_mut()
+        .insert("x-signature", "signed".parse().unwrap());
+    let response = client.send_request::<Viewer>(request).await.unwrap();
+
+    assert_eq!(response.data.viewer, "me");
+    assert_eq!(server.requests()[0].header("x-signature"), Some("signed"));
+}
+
+#[tokio::test]
+pub async fn retries_external_request() {
+    let calls = AtomicUsize::new(0);
+    let server = server::serve(move |_| match calls.fetch_add(1, Ordering::SeqCst) {
+        0 => Response::text(503, "unavailable"),
+        _ => Response::json(json!({ "data": { "viewer": "me" } })),
+    })
+    .await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .retry(RetryPolicy::new(2).base_delay(Duration::from_millis(1)))
+        .build()
+        .unwrap();
+
+    let request = reqwest::Client::new()
+        .post(&server.url)
+        .json(&json!({ "query": "query Viewer { viewer }", "operationName": "Viewer" }))
+        .build()
+        .unwrap();
+    let response = client.send_request::<Viewer>(request).await.unwrap();
+
+    assert_eq!(response.data.viewer, "me");
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+pub async fn parses_errors_of_external_request() {
+    let server = server::serve(|_| {
+        Response::json(json!({ "data": null, "errors": [{ "message": "Forbidden" }] }))
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    let request = reqwest::Client::new()
+        .post(&server.url)
+        .json(&json!({ "query": "{ viewer }" }))
+        .build()
+        .unwrap();
+    let error = client.send_request::<Viewer>(request).await.unwrap_err();
+
+    assert!(matches!(error, GraphQLError::GraphQL(_)));
+}
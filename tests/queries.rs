@@ -1,3 +1,9 @@
+#![allow(
+    clippy::redundant_static_lifetimes,
+    clippy::unnecessary_cast,
+    clippy::len_zero
+)]
+
 mod structs;
 
 use crate::structs::{inputs::SinglePostVariables, AllPosts, SinglePost};
@@ -29,6 +35,7 @@ pub async fn fetches_one_post() {
 }
 
 #[tokio::test]
+#[allow(deprecated)]
 pub async fn fetches_all_posts() {
     let mut headers = HashMap::new();
     headers.insert("content-type", "application/json");
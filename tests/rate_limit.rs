@@ -0,0 +1,56 @@
+mod server;
+
+use reqwest_graphql::{Client, RateLimit};
+use serde::Deserialize;
+use serde_json::json;
+use server::Response;
+use std::time::{Duration, Instant};
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub viewer: String,
+}
+
+#[tokio::test]
+pub async fn spaces_requests_after_burst() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .rate_limit(RateLimit::new(10.0).burst(2))
+        .build()
+        .unwrap();
+
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    // The second request is still within the burst
+    let start = Instant::now();
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+    assert!(start.elapsed() < Duration::from_millis(50));
+
+    let (a, b, c) = tokio::join!(
+        client.query::<Viewer>("{ viewer }"),
+        client.query::<Viewer>("{ viewer }"),
+        client.query::<Viewer>("{ viewer }")
+    );
+    a.unwrap();
+    b.unwrap();
+    c.unwrap();
+
+    // The following ones are sent at 10 per second
+    assert!(start.elapsed() >= Duration::from_millis(280));
+    assert_eq!(server.requests().len(), 5);
+}
+
+#[tokio::test]
+pub async fn rejects_rates_which_are_not_positive() {
+    for per_second in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+        let error = Client::builder()
+            .endpoint("http://localhost/graphql")
+            .rate_limit(RateLimit::new(per_second))
+            .build()
+            .err()
+            .unwrap();
+
+        assert!(error.to_string().contains("Invalid rate limit"));
+    }
+}
@@ -0,0 +1,99 @@
+mod server;
+
+use reqwest_graphql::{Client, RetryPolicy};
+use serde::Deserialize;
+use serde_json::json;
+use server::Response;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub viewer: String,
+}
+
+#[tokio::test]
+pub async fn sends_generated_request_ids() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .request_ids(true)
+        .build()
+        .unwrap();
+
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    let requests = server.requests();
+    let first = requests[0].header("x-request-id").unwrap();
+    let second = requests[1].header("x-request-id").unwrap();
+    assert_eq!(first.len(), 36);
+    assert_ne!(first, second);
+}
+
+#[tokio::test]
+pub async fn carries_request_id_in_errors() {
+    let server = server::serve(
+        |request| match request.json()["variables"]["page"].as_str() {
+            Some("error") => Response::json(json!({
+                "errors": [{ "message": "Denied", "extensions": { "code": "FORBIDDEN" } }]
+            })),
+            _ => Response::text(500, "Internal Server Error"),
+        },
+    )
+    .await;
+    let counter = Arc::new(AtomicUsize::new(0));
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .request_id_header("x-correlation-id")
+        .request_id_generator(move || format!("req-{}", counter.fetch_add(1, Ordering::SeqCst)))
+        .build()
+        .unwrap();
+
+    let graphql = client
+        .query_with_vars::<Viewer, _>("{ viewer }", json!({ "page": "error" }))
+        .await
+        .unwrap_err();
+    let http = client
+        .query_with_vars::<Viewer, _>("{ viewer }", json!({ "page": "down" }))
+        .await
+        .unwrap_err();
+
+    let requests = server.requests();
+    assert_eq!(requests[0].header("x-correlation-id"), Some("req-0"));
+    assert_eq!(requests[1].header("x-correlation-id"), Some("req-1"));
+    assert_eq!(graphql.request_id(), Some("req-0"));
+    assert!(graphql.has_code("FORBIDDEN"));
+    assert_eq!(http.request_id(), Some("req-1"));
+    assert!(http.to_string().contains("request ID req-1"));
+}
+
+#[tokio::test]
+pub async fn sends_request_id_of_call_with_every_attempt() {
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let counter = attempts.clone();
+    let server = server::serve(move |_| match counter.fetch_add(1, Ordering::SeqCst) {
+        0 => Response::text(503, "Unavailable"),
+        _ => Response::json(json!({ "data": { "viewer": "me" } })),
+    })
+    .await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .retry(RetryPolicy::new(2).base_delay(Duration::from_millis(1)))
+        .build()
+        .unwrap();
+
+    let data = client
+        .request("{ viewer }")
+        .request_id("checkout-42")
+        .send::<Viewer>()
+        .await
+        .unwrap();
+
+    assert_eq!(data.viewer, "me");
+    for request in server.requests() {
+        assert_eq!(request.header("x-request-id"), Some("checkout-42"));
+    }
+    assert_eq!(attempts.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,131 @@
+mod server;
+
+use reqwest_graphql::{Client, ErrorCode, RetryPolicy};
+use serde::Deserialize;
+use serde_json::json;
+use server::Response;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub viewer: String,
+}
+
+fn policy(max_attempts: u32) -> RetryPolicy {
+    RetryPolicy::new(max_attempts).base_delay(Duration::from_millis(1))
+}
+
+#[tokio::test]
+pub async fn retries_transient_statuses() {
+    let calls = AtomicUsize::new(0);
+    let server = server::serve(move |_| match calls.fetch_add(1, Ordering::SeqCst) {
+        0 => Response::text(503, "unavailable"),
+        1 => Response::text(502, "bad gateway"),
+        _ => Response::json(json!({ "data": { "viewer": "me" } })),
+    })
+    .await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .retry(policy(3))
+        .build()
+        .unwrap();
+
+    let data = client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    assert_eq!(data.viewer, "me");
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test]
+pub async fn stops_after_max_attempts() {
+    let server = server::serve(|_| Response::text(504, "timeout")).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .retry(policy(2))
+        .build()
+        .unwrap();
+
+    assert!(client.query::<Viewer>("{ viewer }").await.is_err());
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+pub async fn does_not_retry_other_statuses() {
+    let server = server::serve(|_| Response::text(500, "error")).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .retry(policy(3))
+        .build()
+        .unwrap();
+
+    assert!(client.query::<Viewer>("{ viewer }").await.is_err());
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+pub async fn retries_selected_error_codes() {
+    let calls = AtomicUsize::new(0);
+    let server = server::serve(move |_| match calls.fetch_add(1, Ordering::SeqCst) {
+        0 => Response::json(json!({
+            "errors": [{ "message": "Oops", "extensions": { "code": "INTERNAL_SERVER_ERROR" } }]
+        })),
+        1 => Response::json(json!({
+            "errors": [{ "message": "Slow down", "extensions": { "code": "RATE_LIMITED" } }]
+        })),
+        _ => Response::json(json!({ "data": { "viewer": "me" } })),
+    })
+    .await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .retry(policy(3).retry_on_codes([ErrorCode::InternalServerError, "RATE_LIMITED".into()]))
+        .build()
+        .unwrap();
+
+    let data = client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    assert_eq!(data.viewer, "me");
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test]
+pub async fn returns_errors_not_matching_predicate() {
+    let server = server::serve(|_| {
+        Response::json(json!({
+            "errors": [{ "message": "Denied", "extensions": { "code": "FORBIDDEN" } }]
+        }))
+    })
+    .await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .retry(policy(3).retry_if(|error| error.has_code(ErrorCode::InternalServerError)))
+        .build()
+        .unwrap();
+
+    let error = client.query::<Viewer>("{ viewer }").await.unwrap_err();
+
+    assert!(error.has_code(ErrorCode::Forbidden));
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+pub async fn waits_for_retry_after() {
+    let calls = AtomicUsize::new(0);
+    let server = server::serve(move |_| match calls.fetch_add(1, Ordering::SeqCst) {
+        0 => Response::text(429, "rate limited").header("retry-after", "0.2"),
+        _ => Response::json(json!({ "data": { "viewer": "me" } })),
+    })
+    .await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .retry(policy(2))
+        .build()
+        .unwrap();
+
+    let start = Instant::now();
+    let data = client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    assert_eq!(data.viewer, "me");
+    assert_eq!(server.requests().len(), 2);
+    assert!(start.elapsed() >= Duration::from_millis(200));
+}
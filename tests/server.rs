@@ -0,0 +1,369 @@
+#![allow(dead_code)]
+
+use serde_json::Value;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{self, RootCertStore, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+
+/// Request received by the mock server
+#[derive(Clone, Debug)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn json(&self) -> Value {
+        serde_json::from_slice(&self.body).unwrap()
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+/// Response returned by the mock server
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    pub delay: Option<Duration>,
+}
+
+impl Response {
+    pub fn json(value: Value) -> Self {
+        Self {
+            status: 200,
+            headers: vec![("content-type".into(), "application/json".into())],
+            body: value.to_string().into_bytes(),
+            delay: None,
+        }
+    }
+
+    pub fn text(status: u16, body: &str) -> Self {
+        Self {
+            status,
+            headers: vec![("content-type".into(), "text/plain".into())],
+            body: body.as_bytes().to_vec(),
+            delay: None,
+        }
+    }
+
+    /// Incremental delivery response with a part for each payload
+    pub fn multipart(parts: &[Value]) -> Self {
+        let mut body = String::new();
+        for part in parts {
+            body.push_str("\r\n---\r\ncontent-type: application/json; charset=utf-8\r\n\r\n");
+            body.push_str(&part.to_string());
+        }
+        body.push_str("\r\n-----\r\n");
+
+        Self {
+            status: 200,
+            headers: vec![(
+                "content-type".into(),
+                "multipart/mixed; boundary=\"-\"; deferSpec=20220824".into(),
+            )],
+            body: body.into_bytes(),
+            delay: None,
+        }
+    }
+
+    pub fn status(mut self, status: u16) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    pub fn content_type(mut self, content_type: &str) -> Self {
+        self.headers.retain(|(key, _)| key != "content-type");
+        self.header("content-type", content_type)
+    }
+
+    pub fn header(mut self, key: &str, value: &str) -> Self {
+        self.headers.push((key.into(), value.into()));
+        self
+    }
+}
+
+/// Minimal HTTP/1.1 server answering every request with the given handler
+pub struct MockServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+    connections: Arc<AtomicUsize>,
+}
+
+impl MockServer {
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    pub fn connections(&self) -> usize {
+        self.connections.load(Ordering::SeqCst)
+    }
+}
+
+pub async fn serve<F>(handler: F) -> MockServer
+where
+    F: Fn(&Request) -> Response + Send + Sync + 'static,
+{
+    start(None, handler).await
+}
+
+/// Like `serve`, but over HTTPS with the certificate from `tests/fixtures/server.pem`,
+/// client certificates signed by `tests/fixtures/ca.pem` are required if `client_auth` is set
+pub async fn serve_tls<F>(client_auth: bool, handler: F) -> MockServer
+where
+    F: Fn(&Request) -> Response + Send + Sync + 'static,
+{
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .unwrap();
+
+    let builder = if client_auth {
+        let mut roots = RootCertStore::empty();
+        roots.add(certificate("ca.pem")).unwrap();
+        let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+            .build()
+            .unwrap();
+        builder.with_client_cert_verifier(verifier)
+    } else {
+        builder.with_no_client_auth()
+    };
+
+    let key = PrivateKeyDer::from_pem_file(fixture("server.key")).unwrap();
+    let config = builder
+        .with_single_cert(vec![certificate("server.pem")], key)
+        .unwrap();
+
+    start(Some(TlsAcceptor::from(Arc::new(config))), handler).await
+}
+
+/// Like `serve`, but listening on a Unix domain socket at `path`, `url` is the endpoint to
+/// use along with the socket
+#[cfg(unix)]
+pub async fn serve_unix<F>(path: &std::path::Path, handler: F) -> MockServer
+where
+    F: Fn(&Request) -> Response + Send + Sync + 'static,
+{
+    let _ = std::fs::remove_file(path);
+    let listener = tokio::net::UnixListener::bind(path).unwrap();
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let connections = Arc::new(AtomicUsize::new(0));
+    let handler = Arc::new(handler);
+
+    let server = MockServer {
+        url: "http://localhost/graphql".into(),
+        requests: requests.clone(),
+        connections: connections.clone(),
+    };
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            connections.fetch_add(1, Ordering::SeqCst);
+            let requests = requests.clone();
+            let handler = handler.clone();
+            tokio::spawn(handle_connection(stream, requests, handler));
+        }
+    });
+
+    server
+}
+
+pub fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+fn certificate(name: &str) -> CertificateDer<'static> {
+    CertificateDer::from_pem_file(fixture(name)).unwrap()
+}
+
+async fn start<F>(tls: Option<TlsAcceptor>, handler: F) -> MockServer
+where
+    F: Fn(&Request) -> Response + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    let url = format!("{}://{}/graphql", scheme, listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let connections = Arc::new(AtomicUsize::new(0));
+    let handler = Arc::new(handler);
+
+    let server = MockServer {
+        url,
+        requests: requests.clone(),
+        connections: connections.clone(),
+    };
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            connections.fetch_add(1, Ordering::SeqCst);
+            let requests = requests.clone();
+            let handler = handler.clone();
+            let tls = tls.clone();
+
+            tokio::spawn(async move {
+                let _ = match tls {
+                    Some(tls) => match tls.accept(stream).await {
+                        Ok(stream) => handle_connection(stream, requests, handler).await,
+                        Err(_) => Ok(()),
+                    },
+                    None => handle_connection(stream, requests, handler).await,
+                };
+            });
+        }
+    });
+
+    server
+}
+
+async fn handle_connection<S, F>(
+    mut stream: S,
+    requests: Arc<Mutex<Vec<Request>>>,
+    handler: Arc<F>,
+) -> std::io::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    F: Fn(&Request) -> Response,
+{
+    let mut buffer = Vec::new();
+
+    loop {
+        let request = match read_request(&mut stream, &mut buffer).await? {
+            Some(request) => request,
+            None => return Ok(()),
+        };
+
+        let response = handler(&request);
+        requests.lock().unwrap().push(request);
+
+        if let Some(delay) = response.delay {
+            tokio::time::sleep(delay).await;
+        }
+
+        let mut head = format!("HTTP/1.1 {} Status\r\n", response.status);
+        for (key, value) in &response.headers {
+            head.push_str(&format!("{}: {}\r\n", key, value));
+        }
+        head.push_str(&format!("content-length: {}\r\n\r\n", response.body.len()));
+
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(&response.body).await?;
+    }
+}
+
+async fn read_request<S: AsyncRead + Unpin>(
+    stream: &mut S,
+    buffer: &mut Vec<u8>,
+) -> std::io::Result<Option<Request>> {
+    let head_end = loop {
+        if let Some(position) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break position;
+        }
+        if !fill(stream, buffer).await? {
+            return Ok(None);
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..head_end]).into_owned();
+    buffer.drain(..head_end + 4);
+
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next().unwrap_or_default().split(' ');
+    let method = request_line.next().unwrap_or_default().to_string();
+    let path = request_line.next().unwrap_or_default().to_string();
+
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.clone())
+    };
+
+    let body = if header("transfer-encoding").as_deref() == Some("chunked") {
+        read_chunked(stream, buffer).await?
+    } else {
+        let length = header("content-length")
+            .and_then(|value| value.parse::<usize>().ok())
+            .unwrap_or(0);
+        while buffer.len() < length {
+            if !fill(stream, buffer).await? {
+                return Ok(None);
+            }
+        }
+        buffer.drain(..length).collect()
+    };
+
+    Ok(Some(Request {
+        method,
+        path,
+        headers,
+        body,
+    }))
+}
+
+async fn read_chunked<S: AsyncRead + Unpin>(
+    stream: &mut S,
+    buffer: &mut Vec<u8>,
+) -> std::io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+
+    loop {
+        let line_end = loop {
+            if let Some(position) = buffer.windows(2).position(|window| window == b"\r\n") {
+                break position;
+            }
+            if !fill(stream, buffer).await? {
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
+        };
+        let size_line = String::from_utf8_lossy(&buffer[..line_end]).into_owned();
+        let size = usize::from_str_radix(size_line.trim(), 16).unwrap_or(0);
+        buffer.drain(..line_end + 2);
+
+        while buffer.len() < size + 2 {
+            if !fill(stream, buffer).await? {
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
+        }
+        body.extend(buffer.drain(..size));
+        buffer.drain(..2);
+
+        if size == 0 {
+            return Ok(body);
+        }
+    }
+}
+
+async fn fill<S: AsyncRead + Unpin>(stream: &mut S, buffer: &mut Vec<u8>) -> std::io::Result<bool> {
+    let mut chunk = [0u8; 4096];
+    let read = stream.read(&mut chunk).await?;
+    buffer.extend_from_slice(&chunk[..read]);
+    Ok(read > 0)
+}
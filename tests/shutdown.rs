@@ -0,0 +1,208 @@
+mod server;
+#[cfg(feature = "subscription")]
+mod ws_server;
+
+use reqwest_graphql::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use server::Response;
+use std::time::{Duration, Instant};
+
+#[derive(Deserialize, Debug)]
+pub struct User {
+    pub id: String,
+}
+
+#[tokio::test]
+pub async fn waits_for_requests_in_flight() {
+    let server = server::serve(|_| {
+        Response::json(json!({ "data": { "ok": true } })).delay(Duration::from_millis(100))
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    let in_flight = tokio::spawn({
+        let client = client.clone();
+        async move { client.query::<Value>("{ ok }").await }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    client.shutdown(Duration::from_secs(5)).await.unwrap();
+
+    assert_eq!(in_flight.await.unwrap().unwrap(), json!({ "ok": true }));
+    let error = client.query::<Value>("{ ok }").await.unwrap_err();
+    assert!(error.is_shutdown());
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+pub async fn fails_when_requests_outlive_deadline() {
+    let server = server::serve(|_| {
+        Response::json(json!({ "data": { "ok": true } })).delay(Duration::from_millis(500))
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    let in_flight = tokio::spawn({
+        let client = client.clone();
+        async move { client.query::<Value>("{ ok }").await }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let error = client
+        .shutdown(Duration::from_millis(50))
+        .await
+        .unwrap_err();
+
+    assert!(error.is_timeout());
+    assert!(in_flight.await.unwrap().is_ok());
+}
+
+/// Answers right away, with a body which takes a while to arrive
+pub struct SlowBody;
+
+#[reqwest_graphql::async_trait]
+impl reqwest_graphql::Transport for SlowBody {
+    async fn execute(
+        &self,
+        _: reqwest::Request,
+    ) -> Result<reqwest::Response, reqwest_graphql::GraphQLError> {
+        let body = futures_util::stream::once(async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            Ok::<_, std::io::Error>(r#"{ "data": { "ok": true } }"#)
+        });
+        let response = http::Response::new(reqwest::Body::wrap_stream(body));
+        Ok(reqwest::Response::from(response))
+    }
+}
+
+#[tokio::test]
+pub async fn waits_for_responses_to_be_read() {
+    let client = Client::builder()
+        .endpoint("http://localhost/graphql")
+        .transport(SlowBody)
+        .build()
+        .unwrap();
+
+    let in_flight = tokio::spawn({
+        let client = client.clone();
+        async move { client.query::<Value>("{ ok }").await }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    client.shutdown(Duration::from_secs(5)).await.unwrap();
+
+    assert!(in_flight.is_finished());
+    assert_eq!(in_flight.await.unwrap().unwrap(), json!({ "ok": true }));
+}
+
+#[tokio::test]
+pub async fn sends_queued_loader_batches() {
+    let server = server::serve(|request| {
+        let users = request.json()["variables"]["ids"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|id| json!({ "id": id }))
+            .collect::<Vec<_>>();
+        Response::json(json!({ "data": { "users": users } }))
+    })
+    .await;
+    let client = Client::new(&server.url);
+    let loader = client
+        .loader::<User>("query($ids: [ID!]!) { users(ids: $ids) { id } }", "users")
+        .window(Duration::from_secs(60));
+
+    let load = tokio::spawn({
+        let loader = loader.clone();
+        async move { loader.load("1").await }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let start = Instant::now();
+    client.shutdown(Duration::from_secs(5)).await.unwrap();
+
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(load.await.unwrap().unwrap().unwrap().id, "1");
+    assert!(loader.load("2").await.unwrap_err().is_shutdown());
+}
+
+#[cfg(feature = "subscription")]
+#[tokio::test]
+pub async fn completes_subscriptions_and_closes_websocket() {
+    use futures_util::StreamExt;
+
+    let (received, mut messages) = tokio::sync::mpsc::unbounded_channel();
+    let endpoint = ws_server::serve(move |mut connection| {
+        let received = received.clone();
+        async move {
+            connection.receive().await;
+            connection.send(json!({ "type": "connection_ack" })).await;
+            let id = connection.receive().await.unwrap()["id"].clone();
+            connection
+                .send(json!({ "id": id, "type": "next", "payload": { "data": { "id": "1" } } }))
+                .await;
+
+            let complete = connection.receive().await.unwrap();
+            received.send(complete["id"] == id).unwrap();
+            received.send(complete["type"] == "complete").unwrap();
+            received.send(connection.receive().await.is_none()).unwrap();
+        }
+    })
+    .await;
+    let client = Client::new(&endpoint);
+    let mut subscription = client.subscribe::<User, _>("subscription { user { id } }", ());
+    assert_eq!(subscription.next().await.unwrap().unwrap().id, "1");
+
+    client.shutdown(Duration::from_secs(5)).await.unwrap();
+
+    assert!(subscription.next().await.is_none());
+    for _ in 0..3 {
+        assert!(messages.recv().await.unwrap());
+    }
+    let mut after = client.subscribe::<User, _>("subscription { user { id } }", ());
+    assert!(after.next().await.unwrap().unwrap_err().is_shutdown());
+}
+
+/// Answers with an event stream which sends a single event and stays open
+#[cfg(feature = "subscription")]
+pub struct OpenEventStream;
+
+#[cfg(feature = "subscription")]
+#[reqwest_graphql::async_trait]
+impl reqwest_graphql::Transport for OpenEventStream {
+    async fn execute(
+        &self,
+        _: reqwest::Request,
+    ) -> Result<reqwest::Response, reqwest_graphql::GraphQLError> {
+        use futures_util::StreamExt;
+
+        let event = "event: next\ndata: {\"data\":{\"id\":\"1\"}}\n\n";
+        let events = futures_util::stream::once(async move { Ok::<_, std::io::Error>(event) })
+            .chain(futures_util::stream::pending());
+        let response = http::Response::builder()
+            .header("content-type", "text/event-stream")
+            .body(reqwest::Body::wrap_stream(events))
+            .unwrap();
+        Ok(reqwest::Response::from(response))
+    }
+}
+
+#[cfg(feature = "subscription")]
+#[tokio::test]
+pub async fn ends_sse_subscriptions() {
+    use futures_util::StreamExt;
+    use reqwest_graphql::SubscriptionProtocol;
+
+    let client = Client::builder()
+        .endpoint("http://localhost/graphql")
+        .transport(OpenEventStream)
+        .subscription_protocol(SubscriptionProtocol::Sse)
+        .build()
+        .unwrap();
+    let mut subscription = client.subscribe::<User, _>("subscription { user { id } }", ());
+    assert_eq!(subscription.next().await.unwrap().unwrap().id, "1");
+
+    client.shutdown(Duration::from_secs(5)).await.unwrap();
+
+    let end = tokio::time::timeout(Duration::from_secs(5), subscription.next()).await;
+    assert!(end.unwrap().is_none());
+    let mut after = client.subscribe::<User, _>("subscription { user { id } }", ());
+    assert!(after.next().await.unwrap().unwrap_err().is_shutdown());
+}
@@ -0,0 +1,103 @@
+#![cfg(feature = "sigv4")]
+
+mod server;
+
+use hmac::{Hmac, Mac};
+use reqwest_graphql::{AwsCredentials, Client, SigV4};
+use serde::Deserialize;
+use serde_json::json;
+use server::Response;
+use sha2::{Digest, Sha256};
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub viewer: String,
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[tokio::test]
+pub async fn signs_requests_with_sigv4() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let credentials =
+        AwsCredentials::new("AKIDEXAMPLE", "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY")
+            .session_token("session");
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .middleware(SigV4::new("eu-west-1", credentials))
+        .build()
+        .unwrap();
+
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    let request = &server.requests()[0];
+    let timestamp = request.header("x-amz-date").unwrap();
+    let date = &timestamp[..8];
+    assert_eq!(timestamp.len(), 16);
+    assert_eq!(request.header("x-amz-security-token"), Some("session"));
+
+    let host = server
+        .url
+        .trim_start_matches("http://")
+        .split('/')
+        .next()
+        .unwrap();
+    let canonical_request = format!(
+        "POST\n{}\n\ncontent-type:application/json\nhost:{}\nx-amz-date:{}\nx-amz-security-token:session\n\ncontent-type;host;x-amz-date;x-amz-security-token\n{}",
+        request.path,
+        host,
+        timestamp,
+        hex(&Sha256::digest(&request.body))
+    );
+    let scope = format!("{}/eu-west-1/appsync/aws4_request", date);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        timestamp,
+        scope,
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+    let key = hmac(b"AWS4wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", date);
+    let key = hmac(&hmac(&hmac(&key, "eu-west-1"), "appsync"), "aws4_request");
+    let signature = hex(&hmac(&key, &string_to_sign));
+
+    assert_eq!(
+        request.header("authorization").unwrap(),
+        format!(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/{}, SignedHeaders=content-type;host;x-amz-date;x-amz-security-token, Signature={}",
+            scope, signature
+        )
+    );
+}
+
+#[tokio::test]
+pub async fn fetches_credentials_for_every_request() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .middleware(
+            SigV4::new("us-east-1", || async {
+                Ok(AwsCredentials::new("ROTATED", "secret"))
+            })
+            .service("execute-api"),
+        )
+        .build()
+        .unwrap();
+
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    let authorization = server.requests()[0]
+        .header("authorization")
+        .unwrap()
+        .to_string();
+    assert!(authorization.contains("Credential=ROTATED/"));
+    assert!(authorization.contains("/us-east-1/execute-api/aws4_request"));
+    assert!(authorization.contains("SignedHeaders=content-type;host;x-amz-date,"));
+}
@@ -0,0 +1,98 @@
+#![cfg(feature = "socks")]
+
+mod server;
+
+use reqwest_graphql::Client;
+use serde::Deserialize;
+use serde_json::json;
+use server::Response;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub viewer: String,
+}
+
+/// Minimal SOCKS5 proxy with username/password authentication, returns its URL and the
+/// credentials it received
+async fn socks_proxy() -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("socks5://{}", listener.local_addr().unwrap());
+    let credentials = Arc::new(Mutex::new(Vec::new()));
+    let log = credentials.clone();
+
+    tokio::spawn(async move {
+        while let Ok((mut client, _)) = listener.accept().await {
+            let log = log.clone();
+            tokio::spawn(async move {
+                // Greeting, select username/password authentication
+                let mut head = [0u8; 2];
+                client.read_exact(&mut head).await.unwrap();
+                let mut methods = vec![0u8; head[1] as usize];
+                client.read_exact(&mut methods).await.unwrap();
+                client.write_all(&[5, 2]).await.unwrap();
+
+                let mut version = [0u8; 2];
+                client.read_exact(&mut version).await.unwrap();
+                let mut username = vec![0u8; version[1] as usize];
+                client.read_exact(&mut username).await.unwrap();
+                let mut length = [0u8; 1];
+                client.read_exact(&mut length).await.unwrap();
+                let mut password = vec![0u8; length[0] as usize];
+                client.read_exact(&mut password).await.unwrap();
+                log.lock().unwrap().push(format!(
+                    "{}:{}",
+                    String::from_utf8_lossy(&username),
+                    String::from_utf8_lossy(&password)
+                ));
+                client.write_all(&[1, 0]).await.unwrap();
+
+                // Connect request with an IPv4 address
+                let mut request = [0u8; 10];
+                client.read_exact(&mut request).await.unwrap();
+                let port = u16::from_be_bytes([request[8], request[9]]);
+                let address = format!(
+                    "{}.{}.{}.{}:{}",
+                    request[4], request[5], request[6], request[7], port
+                );
+                let mut upstream = TcpStream::connect(address).await.unwrap();
+                client
+                    .write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0])
+                    .await
+                    .unwrap();
+
+                let _ = tokio::io::copy_bidirectional(&mut client, &mut upstream).await;
+            });
+        }
+    });
+
+    (url, credentials)
+}
+
+#[tokio::test]
+pub async fn sends_requests_through_socks5_proxy() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let (proxy, credentials) = socks_proxy().await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .socks5_proxy(&proxy, Some(("user", "secret")))
+        .build()
+        .unwrap();
+
+    let data = client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    assert_eq!(data.viewer, "me");
+    assert_eq!(credentials.lock().unwrap().clone(), vec!["user:secret"]);
+}
+
+#[test]
+pub fn rejects_non_socks_proxy_url() {
+    let result = Client::builder()
+        .endpoint("http://localhost")
+        .socks5_proxy("http://localhost:1080", None)
+        .build();
+
+    assert!(result.is_err());
+}
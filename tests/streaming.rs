@@ -0,0 +1,80 @@
+mod server;
+
+use reqwest_graphql::{Client, GraphQLError, GraphQLErrorMessage};
+use serde::Deserialize;
+use serde_json::json;
+use server::Response;
+
+#[derive(Deserialize, Debug)]
+pub struct Posts {
+    pub posts: Vec<Post>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Post {
+    pub id: u32,
+    pub title: String,
+}
+
+#[tokio::test]
+pub async fn deserializes_large_response_while_streaming() {
+    let posts = (0..20_000)
+        .map(|id| json!({ "id": id, "title": format!("Post {}", id) }))
+        .collect::<Vec<_>>();
+    let server =
+        server::serve(move |_| Response::json(json!({ "data": { "posts": posts } }))).await;
+    let client = Client::new(&server.url);
+
+    let data = client
+        .query_streamed::<Posts, _>("{ posts { id title } }", ())
+        .await
+        .unwrap();
+
+    assert_eq!(data.posts.len(), 20_000);
+    assert_eq!(data.posts[19_999].title, "Post 19999");
+}
+
+#[tokio::test]
+pub async fn returns_graphql_errors_while_streaming() {
+    let server =
+        server::serve(|_| Response::json(json!({ "errors": [{ "message": "Not authorized" }] })))
+            .await;
+    let client = Client::new(&server.url);
+
+    let error = client
+        .query_streamed::<Posts, _>("{ posts { id title } }", ())
+        .await
+        .unwrap_err();
+
+    match error {
+        GraphQLError::GraphQL(errors) => match &errors[..] {
+            [GraphQLErrorMessage::ConventionalError { message, .. }] => {
+                assert_eq!(message, "Not authorized")
+            }
+            errors => panic!("Unexpected errors: {:?}", errors),
+        },
+        error => panic!("Unexpected error: {:?}", error),
+    }
+}
+
+#[tokio::test]
+pub async fn reports_path_of_invalid_value_while_streaming() {
+    let server = server::serve(|_| {
+        Response::json(json!({ "data": { "posts": [{ "id": "one", "title": "First" }] } }))
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    let error = client
+        .query_streamed::<Posts, _>("{ posts { id title } }", ())
+        .await
+        .unwrap_err();
+
+    match error {
+        GraphQLError::Deserialize { path, raw_body, .. } => {
+            assert_eq!(path, "data.posts[0].id");
+            assert!(raw_body.is_empty());
+        }
+        error => panic!("Unexpected error: {:?}", error),
+    }
+}
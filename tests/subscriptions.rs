@@ -0,0 +1,827 @@
+#![cfg(feature = "subscription")]
+
+mod server;
+mod ws_server;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use futures_util::StreamExt;
+use reqwest_graphql::{Client, GraphQLError, RetryPolicy, SubscriptionEvent, SubscriptionProtocol};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use server::Response;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Deserialize, Debug)]
+pub struct Tick {
+    pub tick: u32,
+}
+
+#[derive(Serialize)]
+pub struct TickVariables {
+    pub from: u32,
+}
+
+#[tokio::test]
+pub async fn streams_graphql_transport_ws_events() {
+    let endpoint = ws_server::serve(|mut connection| async move {
+        assert_eq!(connection.protocol.as_deref(), Some("graphql-transport-ws"));
+        assert_eq!(
+            connection.receive().await.unwrap()["type"],
+            "connection_init"
+        );
+        connection.send(json!({ "type": "connection_ack" })).await;
+
+        let subscribe = connection.receive().await.unwrap();
+        assert_eq!(subscribe["type"], "subscribe");
+        let id = subscribe["id"].clone();
+        let from = subscribe["payload"]["variables"]["from"].as_u64().unwrap();
+
+        connection.send(json!({ "type": "ping" })).await;
+        assert_eq!(connection.receive().await.unwrap()["type"], "pong");
+
+        for tick in from..from + 3 {
+            connection
+                .send(json!({ "id": id, "type": "next", "payload": { "data": { "tick": tick } } }))
+                .await;
+        }
+        connection
+            .send(json!({ "id": id, "type": "complete" }))
+            .await;
+    })
+    .await;
+
+    let client = Client::new(&endpoint);
+    let ticks: Vec<u32> = client
+        .subscribe::<Tick, _>(
+            "subscription ($from: Int!) { tick(from: $from) }",
+            TickVariables { from: 5 },
+        )
+        .map(|result| result.unwrap().tick)
+        .collect()
+        .await;
+
+    assert_eq!(ticks, vec![5, 6, 7]);
+}
+
+#[tokio::test]
+pub async fn surfaces_subscription_errors() {
+    let endpoint = ws_server::serve(|mut connection| async move {
+        connection.receive().await;
+        connection.send(json!({ "type": "connection_ack" })).await;
+        let id = connection.receive().await.unwrap()["id"].clone();
+        connection
+            .send(json!({ "id": id, "type": "error", "payload": [{ "message": "Unknown field" }] }))
+            .await;
+    })
+    .await;
+
+    let client = Client::new(&endpoint);
+    let results: Vec<_> = client
+        .subscribe::<Tick, _>("subscription { unknown }", ())
+        .collect()
+        .await;
+
+    assert_eq!(results.len(), 1);
+    let error = results.into_iter().next().unwrap().err().unwrap();
+    assert_eq!(error.json().as_ref().unwrap().len(), 1);
+}
+
+#[tokio::test]
+pub async fn streams_legacy_graphql_ws_events() {
+    let endpoint = ws_server::serve(|mut connection| async move {
+        assert_eq!(connection.protocol.as_deref(), Some("graphql-ws"));
+        assert_eq!(
+            connection.receive().await.unwrap()["type"],
+            "connection_init"
+        );
+        connection.send(json!({ "type": "connection_ack" })).await;
+        connection.send(json!({ "type": "ka" })).await;
+
+        let start = connection.receive().await.unwrap();
+        assert_eq!(start["type"], "start");
+        let id = start["id"].clone();
+
+        connection.send(json!({ "type": "ka" })).await;
+        connection
+            .send(json!({ "id": id, "type": "data", "payload": { "data": { "tick": 1 } } }))
+            .await;
+        connection
+            .send(json!({ "id": id, "type": "complete" }))
+            .await;
+    })
+    .await;
+
+    let client = Client::builder()
+        .endpoint(&endpoint)
+        .subscription_protocol(SubscriptionProtocol::GraphQLWs)
+        .build()
+        .unwrap();
+    let ticks: Vec<u32> = client
+        .subscribe::<Tick, _>("subscription { tick }", ())
+        .map(|result| result.unwrap().tick)
+        .collect()
+        .await;
+
+    assert_eq!(ticks, vec![1]);
+}
+
+#[tokio::test]
+pub async fn speaks_appsync_realtime_protocol() {
+    let endpoint = ws_server::serve(|mut connection| async move {
+        assert_eq!(connection.protocol.as_deref(), Some("graphql-ws"));
+        let (path, query) = connection.uri.split_once('?').unwrap();
+        assert_eq!(path, "/graphql/realtime");
+        let header = query
+            .strip_prefix("header=")
+            .unwrap()
+            .strip_suffix("&payload=e30=")
+            .unwrap();
+        let header: serde_json::Value =
+            serde_json::from_slice(&BASE64.decode(header).unwrap()).unwrap();
+        assert_eq!(header["host"], "127.0.0.1");
+        assert_eq!(header["x-api-key"], "da2-key");
+
+        assert_eq!(
+            connection.receive().await.unwrap()["type"],
+            "connection_init"
+        );
+        connection
+            .send(json!({ "type": "connection_ack", "payload": { "connectionTimeoutMs": 300000 } }))
+            .await;
+        connection.send(json!({ "type": "ka" })).await;
+
+        let start = connection.receive().await.unwrap();
+        assert_eq!(start["type"], "start");
+        let data: serde_json::Value =
+            serde_json::from_str(start["payload"]["data"].as_str().unwrap()).unwrap();
+        assert_eq!(data["variables"], json!({ "from": 3 }));
+        assert_eq!(
+            start["payload"]["extensions"]["authorization"]["x-api-key"],
+            "da2-key"
+        );
+
+        let id = start["id"].clone();
+        connection
+            .send(json!({ "id": id, "type": "start_ack" }))
+            .await;
+        connection
+            .send(json!({ "id": id, "type": "data", "payload": { "data": { "tick": 3 } } }))
+            .await;
+        connection
+            .send(json!({ "id": id, "type": "complete" }))
+            .await;
+    })
+    .await;
+
+    let client = Client::builder()
+        .endpoint(&endpoint)
+        .header("x-api-key", "da2-key")
+        .subscription_protocol(SubscriptionProtocol::AppSync)
+        .build()
+        .unwrap();
+    let ticks: Vec<u32> = client
+        .subscribe::<Tick, _>(
+            "subscription ($from: Int) { tick(from: $from) }",
+            TickVariables { from: 3 },
+        )
+        .map(|result| result.unwrap().tick)
+        .collect()
+        .await;
+
+    assert_eq!(ticks, vec![3]);
+}
+
+fn event_stream(body: &str) -> Response {
+    Response {
+        headers: vec![("content-type".into(), "text/event-stream".into())],
+        ..Response::text(200, body)
+    }
+}
+
+#[tokio::test]
+pub async fn streams_sse_events() {
+    let server = server::serve(|_| {
+        event_stream(concat!(
+            "event: next\ndata: {\"data\":{\"tick\":1}}\n\n",
+            ": keep-alive\n\n",
+            "event: next\r\ndata: {\"data\":\r\ndata: {\"tick\":2}}\r\n\r\n",
+            "event: complete\ndata:\n\n",
+            "event: next\ndata: {\"data\":{\"tick\":3}}\n\n",
+        ))
+    })
+    .await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .subscription_protocol(SubscriptionProtocol::Sse)
+        .header("authorization", "Bearer secret")
+        .build()
+        .unwrap();
+
+    let ticks = client
+        .subscribe::<Tick, _>(
+            "subscription ($from: Int!) { tick(from: $from) }",
+            TickVariables { from: 1 },
+        )
+        .map(|tick| tick.unwrap().tick)
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(ticks, [1, 2]);
+    let request = &server.requests()[0];
+    assert_eq!(request.method, "POST");
+    assert_eq!(request.header("accept"), Some("text/event-stream"));
+    assert_eq!(request.header("authorization"), Some("Bearer secret"));
+    assert_eq!(request.json()["variables"], json!({ "from": 1 }));
+}
+
+/// Request received by `SingleConnection`
+pub struct Sent {
+    pub method: String,
+    pub url: String,
+    pub token: Option<String>,
+    pub body: Option<serde_json::Value>,
+}
+
+fn event(name: &str, data: serde_json::Value) -> String {
+    format!("event: {}\ndata: {}\n\n", name, data)
+}
+
+/// Server for the single connection mode of graphql-sse, answering the reserved event stream
+/// with the events of the operation once it is sent, and leaving it open if `open` is set
+pub struct SingleConnection {
+    pub sent: Arc<Mutex<Vec<Sent>>>,
+    pub operation: tokio::sync::watch::Sender<String>,
+    pub events: fn(&str) -> String,
+    pub open: bool,
+}
+
+impl SingleConnection {
+    pub fn new(events: fn(&str) -> String, open: bool) -> Self {
+        Self {
+            sent: Arc::default(),
+            operation: tokio::sync::watch::channel(String::new()).0,
+            events,
+            open,
+        }
+    }
+}
+
+#[reqwest_graphql::async_trait]
+impl reqwest_graphql::Transport for SingleConnection {
+    async fn execute(&self, request: reqwest::Request) -> Result<reqwest::Response, GraphQLError> {
+        let body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .map(|body| serde_json::from_slice::<serde_json::Value>(body).unwrap());
+        self.sent.lock().unwrap().push(Sent {
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            token: request
+                .headers()
+                .get("x-graphql-event-stream-token")
+                .map(|token| token.to_str().unwrap().to_string()),
+            body: body.clone(),
+        });
+
+        let body = match request.method().as_str() {
+            "PUT" => reqwest::Body::from("token-1"),
+            "GET" => {
+                let mut operation = self.operation.subscribe();
+                let events = self.events;
+                let event = futures_util::stream::once(async move {
+                    operation.changed().await.unwrap();
+                    let id = operation.borrow().clone();
+                    Ok::<_, std::io::Error>(events(&id))
+                });
+                match self.open {
+                    true => {
+                        reqwest::Body::wrap_stream(event.chain(futures_util::stream::pending()))
+                    }
+                    false => reqwest::Body::wrap_stream(event),
+                }
+            }
+            "POST" => {
+                let id = body.unwrap()["extensions"]["operationId"].clone();
+                self.operation
+                    .send_replace(id.as_str().unwrap().to_string());
+                reqwest::Body::from("")
+            }
+            _ => reqwest::Body::from(""),
+        };
+        Ok(reqwest::Response::from(http::Response::new(body)))
+    }
+}
+
+#[tokio::test]
+pub async fn streams_sse_single_connection_events() {
+    let server = SingleConnection::new(
+        |id| {
+            [
+                event(
+                    "next",
+                    json!({ "id": "other", "payload": { "data": { "tick": 9 } } }),
+                ),
+                event(
+                    "next",
+                    json!({ "id": id, "payload": { "data": { "tick": 1 } } }),
+                ),
+                event("complete", json!({ "id": "other" })),
+                event(
+                    "next",
+                    json!({ "id": id, "payload": { "data": { "tick": 2 } } }),
+                ),
+                event("complete", json!({ "id": id })),
+            ]
+            .concat()
+        },
+        false,
+    );
+    let sent = server.sent.clone();
+    let client = Client::builder()
+        .endpoint("http://localhost/graphql")
+        .transport(server)
+        .subscription_protocol(SubscriptionProtocol::SseSingleConnection)
+        .on_request_body(|body| body["extensions"] = json!({ "clientInfo": "tests" }))
+        .build()
+        .unwrap();
+
+    for _ in 0..2 {
+        let ticks = client
+            .subscribe::<Tick, _>("subscription { tick }", ())
+            .map(|tick| tick.unwrap().tick)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(ticks, [1, 2]);
+    }
+
+    let sent = sent.lock().unwrap();
+    let methods = sent
+        .iter()
+        .map(|request| request.method.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(methods, ["PUT", "GET", "POST", "PUT", "GET", "POST"]);
+    assert!(sent[1..3]
+        .iter()
+        .all(|request| request.token.as_deref() == Some("token-1")));
+    let extensions = sent[2].body.as_ref().unwrap()["extensions"].clone();
+    assert_eq!(extensions["clientInfo"], "tests");
+    let id = extensions["operationId"].as_str().unwrap();
+    assert!(!id.is_empty());
+    assert_ne!(
+        sent[5].body.as_ref().unwrap()["extensions"]["operationId"],
+        id
+    );
+}
+
+#[tokio::test]
+pub async fn stops_dropped_sse_single_connection_operations() {
+    let server = SingleConnection::new(
+        |id| {
+            event(
+                "next",
+                json!({ "id": id, "payload": { "data": { "tick": 1 } } }),
+            )
+        },
+        true,
+    );
+    let sent = server.sent.clone();
+    let client = Client::builder()
+        .endpoint("http://localhost/graphql")
+        .transport(server)
+        .subscription_protocol(SubscriptionProtocol::SseSingleConnection)
+        .build()
+        .unwrap();
+
+    let mut subscription = client.subscribe::<Tick, _>("subscription { tick }", ());
+    assert_eq!(subscription.next().await.unwrap().unwrap().tick, 1);
+    drop(subscription);
+
+    for _ in 0..100 {
+        if sent.lock().unwrap().len() == 4 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let sent = sent.lock().unwrap();
+    let id = sent[2].body.as_ref().unwrap()["extensions"]["operationId"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert_eq!(sent[3].method, "DELETE");
+    assert_eq!(
+        sent[3].url,
+        format!("http://localhost/graphql?operationId={}", id)
+    );
+    assert_eq!(sent[3].token.as_deref(), Some("token-1"));
+}
+
+#[tokio::test]
+pub async fn reports_sse_http_errors() {
+    let server = server::serve(|_| Response::text(405, "Method Not Allowed")).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .subscription_protocol(SubscriptionProtocol::Sse)
+        .build()
+        .unwrap();
+
+    let results = client
+        .subscribe::<Tick, _>("subscription { tick }", ())
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(results.len(), 1);
+    assert!(matches!(
+        &results[0],
+        Err(GraphQLError::Http { status, body }) if status.as_u16() == 405 && body == "Method Not Allowed"
+    ));
+}
+
+/// Server sending one tick per connection, dropping the first ones and completing on the last
+async fn serve_dropping(connections: Arc<AtomicUsize>, drops: usize) -> String {
+    ws_server::serve(move |mut connection| {
+        let attempt = connections.fetch_add(1, Ordering::SeqCst);
+        async move {
+            assert_eq!(
+                connection.receive().await.unwrap()["type"],
+                "connection_init"
+            );
+            connection.send(json!({ "type": "connection_ack" })).await;
+            let subscribe = connection.receive().await.unwrap();
+            assert_eq!(subscribe["type"], "subscribe");
+            let id = subscribe["id"].clone();
+
+            connection
+                .send(
+                    json!({ "id": id, "type": "next", "payload": { "data": { "tick": attempt } } }),
+                )
+                .await;
+            if attempt < drops {
+                connection.close().await;
+            } else {
+                connection
+                    .send(json!({ "id": id, "type": "complete" }))
+                    .await;
+            }
+        }
+    })
+    .await
+}
+
+#[tokio::test]
+pub async fn resubscribes_after_connection_drops() {
+    let connections = Arc::new(AtomicUsize::new(0));
+    let endpoint = serve_dropping(connections.clone(), 2).await;
+    let client = Client::builder()
+        .endpoint(&endpoint)
+        .subscription_reconnect(RetryPolicy::new(3).base_delay(Duration::from_millis(1)))
+        .build()
+        .unwrap();
+
+    let ticks: Vec<u32> = client
+        .subscribe::<Tick, _>("subscription { tick }", ())
+        .map(|result| result.unwrap().tick)
+        .collect()
+        .await;
+
+    assert_eq!(ticks, vec![0, 1, 2]);
+    assert_eq!(connections.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+pub async fn reports_reconnections_as_events() {
+    let endpoint = serve_dropping(Arc::new(AtomicUsize::new(0)), 1).await;
+    let client = Client::builder()
+        .endpoint(&endpoint)
+        .subscription_reconnect(RetryPolicy::new(3).base_delay(Duration::from_millis(1)))
+        .build()
+        .unwrap();
+
+    let events: Vec<_> = client
+        .subscribe::<Tick, _>("subscription { tick }", ())
+        .events()
+        .map(|event| match event.unwrap() {
+            SubscriptionEvent::Data(tick) => Some(tick.tick),
+            SubscriptionEvent::Reconnected => None,
+        })
+        .collect()
+        .await;
+
+    assert_eq!(events, vec![Some(0), None, Some(1)]);
+}
+
+#[tokio::test]
+pub async fn ends_when_connection_drops_without_reconnect() {
+    let connections = Arc::new(AtomicUsize::new(0));
+    let endpoint = serve_dropping(connections.clone(), 1).await;
+
+    let ticks: Vec<u32> = Client::new(&endpoint)
+        .subscribe::<Tick, _>("subscription { tick }", ())
+        .map(|result| result.unwrap().tick)
+        .collect()
+        .await;
+
+    assert_eq!(ticks, vec![0]);
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+pub async fn gives_up_reconnecting_after_max_attempts() {
+    let connections = Arc::new(AtomicUsize::new(0));
+    let counter = connections.clone();
+    let endpoint = ws_server::serve(move |mut connection| {
+        let attempt = counter.fetch_add(1, Ordering::SeqCst);
+        async move {
+            connection.receive().await;
+            if attempt > 0 {
+                connection
+                    .send(json!({ "type": "connection_error", "payload": { "message": "Shutting down" } }))
+                    .await;
+                return;
+            }
+            connection.send(json!({ "type": "connection_ack" })).await;
+            connection.receive().await;
+            connection.close().await;
+        }
+    })
+    .await;
+    let client = Client::builder()
+        .endpoint(&endpoint)
+        .subscription_reconnect(RetryPolicy::new(2).base_delay(Duration::from_millis(1)))
+        .build()
+        .unwrap();
+
+    let results: Vec<_> = client
+        .subscribe::<Tick, _>("subscription { tick }", ())
+        .collect()
+        .await;
+
+    assert_eq!(results.len(), 1);
+    assert!(matches!(
+        &results[0],
+        Err(GraphQLError::GraphQL(errors)) if errors.len() == 1
+    ));
+    assert_eq!(connections.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+pub async fn sends_connection_init_payload() {
+    let endpoint = ws_server::serve(|mut connection| async move {
+        let init = connection.receive().await.unwrap();
+        assert_eq!(init["payload"], json!({ "authToken": "secret" }));
+        connection.send(json!({ "type": "connection_ack" })).await;
+        let id = connection.receive().await.unwrap()["id"].clone();
+        connection
+            .send(json!({ "id": id, "type": "complete" }))
+            .await;
+    })
+    .await;
+    let client = Client::builder()
+        .endpoint(&endpoint)
+        .connection_init_payload(json!({ "authToken": "secret" }))
+        .build()
+        .unwrap();
+
+    let results: Vec<_> = client
+        .subscribe::<Tick, _>("subscription { tick }", ())
+        .collect()
+        .await;
+
+    assert!(results.is_empty());
+}
+
+#[tokio::test]
+pub async fn asks_provider_for_payload_on_every_connection() {
+    let endpoint = ws_server::serve(|mut connection| async move {
+        let init = connection.receive().await.unwrap();
+        let token = init["payload"]["authToken"].as_str().unwrap().to_string();
+        connection.send(json!({ "type": "connection_ack" })).await;
+        let id = connection.receive().await.unwrap()["id"].clone();
+
+        let tick = token.trim_start_matches("token-").parse::<u32>().unwrap();
+        connection
+            .send(json!({ "id": id, "type": "next", "payload": { "data": { "tick": tick } } }))
+            .await;
+        if tick == 0 {
+            connection.close().await;
+        } else {
+            connection
+                .send(json!({ "id": id, "type": "complete" }))
+                .await;
+        }
+    })
+    .await;
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let client = Client::builder()
+        .endpoint(&endpoint)
+        .subscription_reconnect(RetryPolicy::new(3).base_delay(Duration::from_millis(1)))
+        .connection_init_provider(move || {
+            let call = counter.fetch_add(1, Ordering::SeqCst);
+            async move { Ok::<_, GraphQLError>(json!({ "authToken": format!("token-{}", call) })) }
+        })
+        .build()
+        .unwrap();
+
+    let ticks: Vec<u32> = client
+        .subscribe::<Tick, _>("subscription { tick }", ())
+        .map(|result| result.unwrap().tick)
+        .collect()
+        .await;
+
+    assert_eq!(ticks, vec![0, 1]);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+pub async fn times_out_silent_connections() {
+    let endpoint = ws_server::serve(|mut connection| async move {
+        connection.receive().await;
+        connection.send(json!({ "type": "connection_ack" })).await;
+        connection.receive().await;
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    })
+    .await;
+    let client = Client::builder()
+        .endpoint(&endpoint)
+        .subscription_keep_alive(Duration::from_millis(50))
+        .build()
+        .unwrap();
+
+    let results: Vec<_> = client
+        .subscribe::<Tick, _>("subscription { tick }", ())
+        .collect()
+        .await;
+
+    assert_eq!(results.len(), 1);
+    assert!(results[0].as_ref().unwrap_err().is_timeout());
+}
+
+#[tokio::test]
+pub async fn keep_alive_messages_hold_connection_open() {
+    let endpoint = ws_server::serve(|mut connection| async move {
+        connection.receive().await;
+        connection.send(json!({ "type": "connection_ack" })).await;
+        let id = connection.receive().await.unwrap()["id"].clone();
+        for _ in 0..4 {
+            tokio::time::sleep(Duration::from_millis(30)).await;
+            connection.send(json!({ "type": "ping" })).await;
+            assert_eq!(connection.receive().await.unwrap()["type"], "pong");
+        }
+        connection
+            .send(json!({ "id": id, "type": "next", "payload": { "data": { "tick": 1 } } }))
+            .await;
+        connection
+            .send(json!({ "id": id, "type": "complete" }))
+            .await;
+    })
+    .await;
+    let client = Client::builder()
+        .endpoint(&endpoint)
+        .subscription_keep_alive(Duration::from_millis(80))
+        .build()
+        .unwrap();
+
+    let ticks: Vec<u32> = client
+        .subscribe::<Tick, _>("subscription { tick }", ())
+        .map(|result| result.unwrap().tick)
+        .collect()
+        .await;
+
+    assert_eq!(ticks, vec![1]);
+}
+
+#[tokio::test]
+pub async fn reconnects_dead_connections() {
+    let connections = Arc::new(AtomicUsize::new(0));
+    let counter = connections.clone();
+    let endpoint = ws_server::serve(move |mut connection| {
+        let attempt = counter.fetch_add(1, Ordering::SeqCst);
+        async move {
+            connection.receive().await;
+            connection.send(json!({ "type": "connection_ack" })).await;
+            let id = connection.receive().await.unwrap()["id"].clone();
+            if attempt == 0 {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                return;
+            }
+            connection
+                .send(json!({ "id": id, "type": "next", "payload": { "data": { "tick": 1 } } }))
+                .await;
+            connection
+                .send(json!({ "id": id, "type": "complete" }))
+                .await;
+        }
+    })
+    .await;
+    let client = Client::builder()
+        .endpoint(&endpoint)
+        .subscription_keep_alive(Duration::from_millis(50))
+        .subscription_reconnect(RetryPolicy::new(3).base_delay(Duration::from_millis(1)))
+        .build()
+        .unwrap();
+
+    let events: Vec<_> = client
+        .subscribe::<Tick, _>("subscription { tick }", ())
+        .events()
+        .map(|event| match event.unwrap() {
+            SubscriptionEvent::Data(tick) => Some(tick.tick),
+            SubscriptionEvent::Reconnected => None,
+        })
+        .collect()
+        .await;
+
+    assert_eq!(events, vec![None, Some(1)]);
+    assert_eq!(connections.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+pub async fn shares_one_websocket_between_subscriptions() {
+    let connections = Arc::new(AtomicUsize::new(0));
+    let counter = connections.clone();
+    let endpoint = ws_server::serve(move |mut connection| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        async move {
+            connection.receive().await;
+            connection.send(json!({ "type": "connection_ack" })).await;
+            let first = connection.receive().await.unwrap();
+            let second = connection.receive().await.unwrap();
+            assert_ne!(first["id"], second["id"]);
+
+            for subscribe in [first, second] {
+                let id = subscribe["id"].clone();
+                let from = subscribe["payload"]["variables"]["from"].clone();
+                connection
+                    .send(json!({ "id": id, "type": "next", "payload": { "data": { "tick": from } } }))
+                    .await;
+                connection
+                    .send(json!({ "id": id, "type": "complete" }))
+                    .await;
+            }
+            assert!(connection.receive().await.is_none());
+        }
+    })
+    .await;
+    let client = Client::new(&endpoint);
+    let ticks = |from| {
+        client
+            .subscribe::<Tick, _>(
+                "subscription ($from: Int!) { tick(from: $from) }",
+                TickVariables { from },
+            )
+            .map(|result| result.unwrap().tick)
+            .collect::<Vec<_>>()
+    };
+
+    let (first, second) = tokio::join!(ticks(1), ticks(10));
+
+    assert_eq!(first, vec![1]);
+    assert_eq!(second, vec![10]);
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+pub async fn completes_dropped_subscriptions() {
+    let (stopped, mut stops) = tokio::sync::mpsc::unbounded_channel();
+    let endpoint = ws_server::serve(move |mut connection| {
+        let stopped = stopped.clone();
+        async move {
+            connection.receive().await;
+            connection.send(json!({ "type": "connection_ack" })).await;
+            let mut ids = Vec::new();
+            for _ in 0..2 {
+                let id = connection.receive().await.unwrap()["id"].clone();
+                connection
+                    .send(json!({ "id": id, "type": "next", "payload": { "data": { "tick": 1 } } }))
+                    .await;
+                ids.push(id);
+            }
+            let (first, second) = (&ids[0], &ids[1]);
+
+            let stop = connection.receive().await.unwrap();
+            stopped.send(stop["id"] == *second).unwrap();
+            stopped.send(stop["type"] == "complete").unwrap();
+            connection
+                .send(json!({ "id": first, "type": "next", "payload": { "data": { "tick": 2 } } }))
+                .await;
+            connection
+                .send(json!({ "id": first, "type": "complete" }))
+                .await;
+        }
+    })
+    .await;
+    let client = Client::new(&endpoint);
+    let mut first = client.subscribe::<Tick, _>("subscription { tick }", ());
+    let mut second = client.subscribe::<Tick, _>("subscription { tick }", ());
+
+    assert_eq!(first.next().await.unwrap().unwrap().tick, 1);
+    assert_eq!(second.next().await.unwrap().unwrap().tick, 1);
+    drop(second);
+
+    assert!(stops.recv().await.unwrap());
+    assert!(stops.recv().await.unwrap());
+    let rest: Vec<u32> = first.map(|result| result.unwrap().tick).collect().await;
+    assert_eq!(rest, vec![2]);
+}
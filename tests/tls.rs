@@ -0,0 +1,124 @@
+mod server;
+
+use reqwest_graphql::Client;
+use serde::Deserialize;
+use serde_json::json;
+use server::Response;
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub viewer: String,
+}
+
+#[tokio::test]
+pub async fn trusts_custom_root_certificate() {
+    let server = server::serve_tls(false, |_| {
+        Response::json(json!({ "data": { "viewer": "me" } }))
+    })
+    .await;
+    let ca = std::fs::read(server::fixture("ca.pem")).unwrap();
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .add_root_certificate(&ca)
+        .build()
+        .unwrap();
+
+    let data = client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    assert_eq!(data.viewer, "me");
+}
+
+#[tokio::test]
+pub async fn rejects_unknown_certificate_authority() {
+    let server = server::serve_tls(false, |_| {
+        Response::json(json!({ "data": { "viewer": "me" } }))
+    })
+    .await;
+    let client = Client::new(&server.url);
+
+    assert!(client.query::<Viewer>("{ viewer }").await.is_err());
+}
+
+#[tokio::test]
+pub async fn accepts_invalid_certificates_when_asked() {
+    let server = server::serve_tls(false, |_| {
+        Response::json(json!({ "data": { "viewer": "me" } }))
+    })
+    .await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .danger_accept_invalid_certs(true)
+        .build()
+        .unwrap();
+
+    assert!(client.query::<Viewer>("{ viewer }").await.is_ok());
+}
+
+#[test]
+pub fn rejects_invalid_root_certificate() {
+    let result = Client::builder()
+        .endpoint("https://localhost")
+        .add_root_certificate(b"not a certificate")
+        .build();
+
+    assert!(result.is_err());
+}
+
+fn client_builder(url: &str) -> reqwest_graphql::ClientBuilder {
+    let ca = std::fs::read(server::fixture("ca.pem")).unwrap();
+    Client::builder().endpoint(url).add_root_certificate(&ca)
+}
+
+#[tokio::test]
+pub async fn authenticates_with_pkcs12_identity() {
+    let server = server::serve_tls(true, |_| {
+        Response::json(json!({ "data": { "viewer": "me" } }))
+    })
+    .await;
+    let archive = std::fs::read(server::fixture("client.p12")).unwrap();
+    let client = client_builder(&server.url)
+        .identity_pkcs12(&archive, "secret")
+        .build()
+        .unwrap();
+
+    let data = client.query::<Viewer>("{ viewer }").await.unwrap();
+
+    assert_eq!(data.viewer, "me");
+}
+
+#[tokio::test]
+pub async fn authenticates_with_pem_identity() {
+    let server = server::serve_tls(true, |_| {
+        Response::json(json!({ "data": { "viewer": "me" } }))
+    })
+    .await;
+    let certificate = std::fs::read(server::fixture("client.pem")).unwrap();
+    let key = std::fs::read(server::fixture("client.key")).unwrap();
+    let client = client_builder(&server.url)
+        .identity_pem(&certificate, &key)
+        .build()
+        .unwrap();
+
+    assert!(client.query::<Viewer>("{ viewer }").await.is_ok());
+}
+
+#[tokio::test]
+pub async fn fails_without_required_identity() {
+    let server = server::serve_tls(true, |_| {
+        Response::json(json!({ "data": { "viewer": "me" } }))
+    })
+    .await;
+    let client = client_builder(&server.url).build().unwrap();
+
+    assert!(client.query::<Viewer>("{ viewer }").await.is_err());
+}
+
+#[test]
+pub fn rejects_invalid_identity() {
+    let result = Client::builder()
+        .endpoint("https://localhost")
+        .identity_pkcs12(b"not an archive", "secret")
+        .build();
+
+    assert!(result.is_err());
+}
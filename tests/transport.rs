@@ -0,0 +1,78 @@
+use reqwest::{Request, Response};
+use reqwest_graphql::{
+    async_trait, Client, GraphQLError, Middleware, MockTransport, Next, Transport,
+};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+
+/// Answers every request, recording the headers it received
+#[derive(Clone, Default)]
+pub struct Recorder {
+    pub received: Arc<Mutex<Vec<Option<String>>>>,
+}
+
+#[async_trait]
+impl Transport for Recorder {
+    async fn execute(&self, request: Request) -> Result<Response, GraphQLError> {
+        let tag = request
+            .headers()
+            .get("x-tag")
+            .and_then(|value| value.to_str().ok())
+            .map(String::from);
+        self.received.lock().unwrap().push(tag);
+
+        let body = json!({ "data": { "url": request.url().as_str() } }).to_string();
+        Ok(Response::from(http::Response::new(body)))
+    }
+}
+
+pub struct Tag;
+
+#[async_trait]
+impl Middleware for Tag {
+    async fn handle(&self, mut request: Request, next: Next<'_>) -> Result<Response, GraphQLError> {
+        request
+            .headers_mut()
+            .insert("x-tag", "tagged".parse().unwrap());
+        next.run(request).await
+    }
+}
+
+#[tokio::test]
+pub async fn sends_requests_through_the_transport() {
+    let recorder = Recorder::default();
+    let client = Client::builder()
+        .endpoint("http://unix.socket/graphql")
+        .middleware(Tag)
+        .transport(recorder.clone())
+        .build()
+        .unwrap();
+
+    let data = client.query::<Value>("{ url }").await.unwrap();
+
+    assert_eq!(data["url"], "http://unix.socket/graphql");
+    assert_eq!(
+        *recorder.received.lock().unwrap(),
+        [Some("tagged".to_string())]
+    );
+}
+
+#[tokio::test]
+pub async fn uses_mock_as_transport() {
+    let mock = MockTransport::new();
+    mock.on(
+        "{ viewer { id } }",
+        json!({ "data": { "viewer": { "id": "1" } } }),
+    );
+    let client = Client::builder()
+        .endpoint("https://example.com/graphql")
+        .header("x-tag", "built")
+        .transport(mock.clone())
+        .build()
+        .unwrap();
+
+    let data = client.query::<Value>("{ viewer { id } }").await.unwrap();
+
+    assert_eq!(data["viewer"]["id"], "1");
+    assert_eq!(mock.requests()[0].header("x-tag"), Some("built"));
+}
@@ -0,0 +1,44 @@
+#![cfg(all(unix, feature = "unix-socket"))]
+
+mod server;
+
+use reqwest_graphql::Client;
+use serde_json::{json, Value};
+use server::Response;
+
+#[tokio::test]
+pub async fn queries_over_unix_socket() {
+    let path = std::env::temp_dir().join(format!("reqwest-graphql-{}.sock", std::process::id()));
+    let server = server::serve_unix(&path, |request| {
+        Response::json(json!({ "data": { "path": request.path } }))
+    })
+    .await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .header("x-client", "tests")
+        .unix_socket(&path)
+        .build()
+        .unwrap();
+
+    let data = client.query::<Value>("{ path }").await.unwrap();
+
+    assert_eq!(data["path"], "/graphql");
+    let request = &server.requests()[0];
+    assert_eq!(request.method, "POST");
+    assert_eq!(request.header("host"), Some("localhost"));
+    assert_eq!(request.header("x-client"), Some("tests"));
+    assert_eq!(request.json()["query"], "{ path }");
+}
+
+#[tokio::test]
+pub async fn reports_missing_sockets() {
+    let client = Client::builder()
+        .endpoint("http://localhost/graphql")
+        .unix_socket("/nonexistent/api.sock")
+        .build()
+        .unwrap();
+
+    let error = client.query::<Value>("{ path }").await.unwrap_err();
+
+    assert!(error.message().contains("/nonexistent/api.sock"));
+}
@@ -0,0 +1,150 @@
+mod server;
+
+use futures_util::StreamExt;
+use reqwest_graphql::{Client, Upload};
+use serde::Deserialize;
+use serde_json::json;
+use server::Response;
+use std::sync::{Arc, Mutex};
+
+#[derive(Deserialize, Debug)]
+pub struct Uploaded {
+    pub upload: bool,
+}
+
+#[tokio::test]
+pub async fn sends_multipart_request() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "upload": true } }))).await;
+    let client = Client::new(&server.url);
+
+    let data = client
+        .query_with_files::<Uploaded, _>(
+            "mutation ($files: [Upload!]!) { upload(files: $files) }",
+            json!({ "files": [null, null] }),
+            vec![
+                (
+                    "variables.files.0",
+                    Upload::new("a.txt", "first file").mime("text/plain"),
+                ),
+                ("variables.files.1", Upload::new("b.txt", "second file")),
+            ],
+        )
+        .await
+        .unwrap();
+    assert!(data.upload);
+
+    let request = &server.requests()[0];
+    assert!(request
+        .header("content-type")
+        .unwrap()
+        .starts_with("multipart/form-data"));
+
+    let body = request.text();
+    let operations = body.find("name=\"operations\"").unwrap();
+    let map = body.find("name=\"map\"").unwrap();
+    let first = body.find("first file").unwrap();
+    assert!(operations < map && map < first);
+    assert!(body.contains(r#"{"0":["variables.files.0"],"1":["variables.files.1"]}"#));
+    assert!(body.contains("filename=\"b.txt\""));
+    assert!(body.contains("second file"));
+}
+
+#[tokio::test]
+pub async fn rejects_invalid_upload_path() {
+    let client = Client::new("http://127.0.0.1:1/graphql");
+
+    let error = client
+        .query_with_files::<Uploaded, _>(
+            "mutation ($files: [Upload!]!) { upload(files: $files) }",
+            json!({ "files": [] }),
+            vec![("variables.files.3", Upload::new("a.txt", "data"))],
+        )
+        .await
+        .err()
+        .unwrap();
+
+    assert!(error.message().contains("variables.files.3"));
+}
+
+#[tokio::test]
+pub async fn reports_upload_progress() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "upload": true } }))).await;
+    let client = Client::new(&server.url);
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let file = vec![7u8; 150 * 1024];
+
+    let upload = Upload::new("large.bin", file.clone()).on_progress({
+        let reports = reports.clone();
+        move |sent, total| reports.lock().unwrap().push((sent, total))
+    });
+    let data = client
+        .query_with_files::<Uploaded, _>(
+            "mutation ($file: Upload!) { upload(file: $file) }",
+            json!({ "file": null }),
+            vec![("variables.file", upload)],
+        )
+        .await
+        .unwrap();
+    assert!(data.upload);
+
+    let total = file.len() as u64;
+    assert_eq!(
+        *reports.lock().unwrap(),
+        vec![(64 * 1024, total), (128 * 1024, total), (total, total)]
+    );
+    let request = &server.requests()[0];
+    assert!(request
+        .body
+        .windows(file.len())
+        .any(|window| window == file));
+}
+
+#[tokio::test]
+pub async fn streams_uploads_from_chunks() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "upload": true } }))).await;
+    let client = Client::new(&server.url);
+    let chunks = futures_util::stream::iter(["first ", "second ", "third"])
+        .map(|chunk| Ok::<_, std::io::Error>(chunk.as_bytes().to_vec()));
+    let reports = Arc::new(Mutex::new(Vec::new()));
+
+    let upload = Upload::from_stream("chunks.txt", chunks, 18).on_progress({
+        let reports = reports.clone();
+        move |sent, total| reports.lock().unwrap().push((sent, total))
+    });
+    let data = client
+        .query_with_files::<Uploaded, _>(
+            "mutation ($file: Upload!) { upload(file: $file) }",
+            json!({ "file": null }),
+            vec![("variables.file", upload)],
+        )
+        .await
+        .unwrap();
+
+    assert!(data.upload);
+    assert!(server.requests()[0].text().contains("first second third"));
+    assert_eq!(*reports.lock().unwrap(), vec![(6, 18), (13, 18), (18, 18)]);
+}
+
+#[tokio::test]
+pub async fn streams_uploads_from_readers() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "upload": true } }))).await;
+    let client = Client::new(&server.url);
+    let reader = std::io::Cursor::new(b"read from an AsyncRead".to_vec());
+
+    client
+        .query_with_files::<Uploaded, _>(
+            "mutation ($file: Upload!) { upload(file: $file) }",
+            json!({ "file": null }),
+            vec![(
+                "variables.file",
+                Upload::from_reader("reader.txt", reader, 22).mime("text/plain"),
+            )],
+        )
+        .await
+        .unwrap();
+
+    let body = server.requests()[0].text();
+    assert!(body.contains("filename=\"reader.txt\""));
+    assert!(body.contains("text/plain"));
+    assert!(body.contains("read from an AsyncRead"));
+}
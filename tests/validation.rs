@@ -0,0 +1,156 @@
+#![cfg(feature = "validation")]
+
+mod server;
+
+use reqwest_graphql::introspection::Schema;
+use reqwest_graphql::{Client, GraphQLError};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use server::Response;
+
+#[derive(Deserialize, Debug)]
+pub struct Viewer {
+    pub viewer: String,
+}
+
+#[test]
+pub fn validates_query_syntax() {
+    let client = Client::new("http://localhost/graphql");
+
+    assert!(client
+        .validate("query ($id: ID!) { user(id: $id) { name } }")
+        .is_ok());
+
+    let error = client.validate("{ user(id: 1) { name }").unwrap_err();
+    assert!(error.message().contains("1:"));
+}
+
+#[tokio::test]
+pub async fn rejects_invalid_documents_before_sending() {
+    let server = server::serve(|_| Response::json(json!({ "data": { "viewer": "me" } }))).await;
+    let client = Client::builder()
+        .endpoint(&server.url)
+        .validate_queries(true)
+        .build()
+        .unwrap();
+
+    let error = client.query::<Viewer>("{ viewer ").await.unwrap_err();
+    assert!(matches!(error, GraphQLError::Syntax(_)));
+    assert!(server.requests().is_empty());
+
+    client.query::<Viewer>("{ viewer }").await.unwrap();
+    assert_eq!(server.requests().len(), 1);
+}
+
+fn object(name: &str, fields: Value) -> Value {
+    json!({ "kind": "OBJECT", "name": name, "description": null, "fields": fields,
+        "inputFields": null, "interfaces": [], "enumValues": null, "possibleTypes": null })
+}
+
+fn scalar(name: &str) -> Value {
+    json!({ "kind": "SCALAR", "name": name, "description": null, "fields": null,
+        "inputFields": null, "interfaces": null, "enumValues": null, "possibleTypes": null })
+}
+
+fn field(name: &str, args: Value, field_type: Value) -> Value {
+    json!({ "name": name, "description": null, "args": args, "type": field_type,
+        "isDeprecated": false, "deprecationReason": null })
+}
+
+fn schema() -> Schema {
+    let id = json!({ "kind": "SCALAR", "name": "ID", "ofType": null });
+    let non_null_id = json!({ "kind": "NON_NULL", "name": null, "ofType": id });
+    let string = json!({ "kind": "SCALAR", "name": "String", "ofType": null });
+    let user = json!({ "kind": "OBJECT", "name": "User", "ofType": null });
+    let id_argument =
+        json!([{ "name": "id", "description": null, "type": non_null_id, "defaultValue": null }]);
+
+    serde_json::from_value(json!({
+        "queryType": { "name": "Query" },
+        "mutationType": null,
+        "subscriptionType": null,
+        "types": [
+            object("Query", json!([field("user", id_argument, user.clone())])),
+            object("User", json!([
+                field("id", json!([]), non_null_id),
+                field("name", json!([]), string),
+                field("friend", json!([]), user)
+            ])),
+            scalar("ID"),
+            scalar("String")
+        ]
+    }))
+    .unwrap()
+}
+
+#[test]
+pub fn accepts_documents_matching_the_schema() {
+    let client = Client::new("http://localhost/graphql");
+    let query = r#"
+        query ($id: ID!) { user(id: $id) { ...Names friend { __typename id } } }
+        fragment Names on User { name ... on User { id } }
+    "#;
+
+    assert_eq!(client.validate_against_schema(query, &schema()), Ok(()));
+}
+
+#[test]
+pub fn reports_unknown_fields_arguments_and_variables() {
+    let client = Client::new("http://localhost/graphql");
+    let query = r#"query ($id: Uuid) {
+        user(id: $id, name: $name) { nam friend }
+        post { id }
+    }"#;
+
+    let errors = client
+        .validate_against_schema(query, &schema())
+        .unwrap_err();
+    let messages = errors
+        .iter()
+        .map(|error| error.message())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        messages,
+        vec![
+            "Variable `$id` has unknown type `Uuid`",
+            "Variable `$name` is not defined",
+            "Unknown argument `name` on field `Query.user`",
+            "Unknown field `nam` on type `User`",
+            "Field `friend` of type `User` must have a selection of subfields",
+            "Unknown field `post` on type `Query`",
+        ]
+    );
+    assert_eq!(errors[3].position(), Some((2, 38)));
+}
+
+#[test]
+pub fn reports_missing_arguments_and_unsupported_operations() {
+    let client = Client::new("http://localhost/graphql");
+
+    let errors = client
+        .validate_against_schema("{ user { id { value } } }", &schema())
+        .unwrap_err();
+    assert_eq!(
+        errors[0].message(),
+        "Missing required argument `id` on field `Query.user`"
+    );
+    assert_eq!(
+        errors[1].message(),
+        "Field `id` of type `ID` cannot have a selection of subfields"
+    );
+
+    let errors = client
+        .validate_against_schema("mutation { user { id } }", &schema())
+        .unwrap_err();
+    assert_eq!(
+        errors[0].message(),
+        "Schema does not support mutation operations"
+    );
+
+    let errors = client
+        .validate_against_schema("{ user(id: 1) { ...Missing }", &schema())
+        .unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].position(), None);
+}
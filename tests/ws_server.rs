@@ -0,0 +1,89 @@
+#![cfg(feature = "subscription")]
+#![allow(dead_code, clippy::result_large_err)]
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::Value;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+/// Server side of a single WebSocket connection
+pub struct Connection {
+    pub protocol: Option<String>,
+    pub uri: String,
+    pub headers: Vec<(String, String)>,
+    socket: WebSocketStream<TcpStream>,
+}
+
+impl Connection {
+    pub async fn receive(&mut self) -> Option<Value> {
+        while let Some(Ok(message)) = self.socket.next().await {
+            if let Message::Text(text) = message {
+                return Some(serde_json::from_str(&text).unwrap());
+            }
+        }
+        None
+    }
+
+    pub async fn send(&mut self, message: Value) {
+        let _ = self.socket.send(Message::Text(message.to_string())).await;
+    }
+
+    pub async fn close(mut self) {
+        let _ = self.socket.close(None).await;
+    }
+}
+
+/// Accepts WebSocket connections on a random port, returning the http:// endpoint
+pub async fn serve<F, Fut>(handler: F) -> String
+where
+    F: Fn(Connection) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/graphql", listener.local_addr().unwrap());
+    let handler = Arc::new(handler);
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let handler = handler.clone();
+
+            tokio::spawn(async move {
+                let mut protocol = None;
+                let mut uri = String::new();
+                let mut headers = Vec::new();
+                let callback = |request: &Request, mut response: Response| {
+                    uri = request.uri().to_string();
+                    for (key, value) in request.headers() {
+                        headers.push((
+                            key.to_string(),
+                            value.to_str().unwrap_or_default().to_string(),
+                        ));
+                    }
+                    if let Some(value) = request.headers().get("sec-websocket-protocol") {
+                        protocol = Some(value.to_str().unwrap().to_string());
+                        response
+                            .headers_mut()
+                            .insert("sec-websocket-protocol", value.clone());
+                    }
+                    Ok(response)
+                };
+
+                if let Ok(socket) = tokio_tungstenite::accept_hdr_async(stream, callback).await {
+                    handler(Connection {
+                        protocol,
+                        uri,
+                        headers,
+                        socket,
+                    })
+                    .await;
+                }
+            });
+        }
+    });
+
+    url
+}